@@ -0,0 +1,94 @@
+//! Stable per-interface accent colors
+//!
+//! Each interface name is hashed (FNV-1a, so the result is identical across
+//! restarts, platforms and Rust versions) onto a curated palette that avoids
+//! the WAN green and error red used elsewhere in the UI.
+
+use gpui::{hsla, rgb, Hsla};
+use std::collections::HashMap;
+
+/// Curated accent palette - no greens (bg-wan) and no reds (errors)
+const PALETTE: [u32; 8] = [
+    0x4a90e2, // blue
+    0xf5a623, // orange
+    0xbd10e0, // purple
+    0x50e3c2, // teal
+    0xf8e71c, // yellow
+    0xff6fb5, // pink
+    0x8b9dff, // periwinkle
+    0xc49a6c, // tan
+];
+
+/// Golden angle as a fraction of a full turn, used for hue rotation
+const GOLDEN_ANGLE: f32 = 0.381_966;
+
+/// FNV-1a hash - stable regardless of platform or std hasher changes
+fn stable_hash(name: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Accent color for a single interface, independent of what else is shown
+pub fn color_for_interface(name: &str) -> Hsla {
+    let slot = (stable_hash(name) % PALETTE.len() as u64) as usize;
+    rgb(PALETTE[slot]).into()
+}
+
+/// Fallback color derived by rotating the hue, used once the palette is exhausted
+fn rotated_hue(seed: u64, step: usize) -> Hsla {
+    let base = (seed % 360) as f32 / 360.0;
+    let hue = (base + step as f32 * GOLDEN_ANGLE).fract();
+    // Skip the green band (~90°-160°) and the red band (<15° / >345°)
+    let hue = if (0.25..0.45).contains(&hue) {
+        hue + 0.2
+    } else if !(0.04..0.96).contains(&hue) {
+        hue + 0.08
+    } else {
+        hue
+    };
+    hsla(hue.fract(), 0.65, 0.6, 1.0)
+}
+
+/// Assign accent colors to a set of visible interfaces
+///
+/// Each name starts at its hashed palette slot; on collision it probes to the
+/// next free slot, so fewer names than the palette size never share a color.
+/// Names that don't fit in the palette fall back to hue rotation.
+pub fn assign_colors<'a>(names: impl IntoIterator<Item = &'a str>) -> HashMap<String, Hsla> {
+    // Sort so the assignment doesn't depend on poll order
+    let mut names: Vec<&str> = names.into_iter().collect();
+    names.sort_unstable();
+    names.dedup();
+
+    let mut taken = [false; PALETTE.len()];
+    let mut colors = HashMap::with_capacity(names.len());
+    let mut overflow = 0;
+
+    for name in names {
+        let hash = stable_hash(name);
+        let start = (hash % PALETTE.len() as u64) as usize;
+
+        let free = (0..PALETTE.len())
+            .map(|offset| (start + offset) % PALETTE.len())
+            .find(|&slot| !taken[slot]);
+
+        let color = match free {
+            Some(slot) => {
+                taken[slot] = true;
+                rgb(PALETTE[slot]).into()
+            }
+            None => {
+                overflow += 1;
+                rotated_hue(hash, overflow)
+            }
+        };
+
+        colors.insert(name.to_string(), color);
+    }
+
+    colors
+}
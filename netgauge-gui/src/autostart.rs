@@ -0,0 +1,285 @@
+//! Launch-at-login support
+//!
+//! Writes the per-user autostart artifact for each platform, pointing at the
+//! current executable with `--minimized`:
+//! - Windows: `HKCU\Software\Microsoft\Windows\CurrentVersion\Run` value
+//! - macOS:   `~/Library/LaunchAgents/<label>.plist`
+//! - Linux:   `~/.config/autostart/netgauge.desktop`
+//!
+//! The on-disk entry is the source of truth; nothing is cached in memory.
+
+use std::io;
+
+/// Flag passed to the app when it is started at login
+pub const LAUNCH_FLAG: &str = "--minimized";
+
+/// Whether this platform has an autostart mechanism at all
+pub fn is_supported() -> bool {
+    cfg!(any(target_os = "linux", target_os = "macos", target_os = "windows"))
+}
+
+/// Whether an autostart entry is currently registered
+pub fn is_enabled() -> bool {
+    platform::read_entry().is_some()
+}
+
+/// Register or remove the autostart entry for the current executable
+pub fn set_enabled(enabled: bool) -> io::Result<()> {
+    if enabled {
+        let exe = std::env::current_exe()?;
+        platform::write_entry(&platform::entry_for(&exe))
+    } else {
+        platform::remove_entry()
+    }
+}
+
+/// Re-point an existing entry at the current executable if it has moved
+/// (e.g. after an update or reinstall). Does nothing when autostart is off.
+pub fn refresh() -> io::Result<()> {
+    let Some(current) = platform::read_entry() else {
+        return Ok(());
+    };
+
+    let exe = std::env::current_exe()?;
+    let expected = platform::entry_for(&exe);
+    if current != expected {
+        platform::write_entry(&expected)?;
+    }
+    Ok(())
+}
+
+/// Whether this process was started by the autostart entry
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == LAUNCH_FLAG)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn not_found_ok(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+// ============================================================================
+// Linux - XDG autostart .desktop entry
+// ============================================================================
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{not_found_ok, LAUNCH_FLAG};
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    fn entry_path() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config.join("autostart").join("netgauge.desktop"))
+    }
+
+    pub fn entry_for(exe: &Path) -> String {
+        // Exec= quoting: wrap in double quotes, escape `"`, `\`, `` ` `` and `$`
+        let mut quoted = String::new();
+        for c in exe.to_string_lossy().chars() {
+            if matches!(c, '"' | '\\' | '`' | '$') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+
+        format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=NetGauge\n\
+             Comment=Network bandwidth monitor\n\
+             Exec=\"{}\" {}\n\
+             Terminal=false\n\
+             X-GNOME-Autostart-enabled=true\n",
+            quoted, LAUNCH_FLAG
+        )
+    }
+
+    pub fn read_entry() -> Option<String> {
+        fs::read_to_string(entry_path()?).ok()
+    }
+
+    pub fn write_entry(contents: &str) -> io::Result<()> {
+        let path = entry_path().ok_or_else(|| io::Error::other("HOME is not set"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn remove_entry() -> io::Result<()> {
+        match entry_path() {
+            Some(path) => not_found_ok(fs::remove_file(path)),
+            None => Ok(()),
+        }
+    }
+}
+
+// ============================================================================
+// macOS - LaunchAgent plist
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{not_found_ok, LAUNCH_FLAG};
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    const LABEL: &str = "io.github.devplus31.netgauge";
+
+    fn entry_path() -> Option<PathBuf> {
+        let home = std::env::var_os("HOME")?;
+        Some(
+            Path::new(&home)
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", LABEL)),
+        )
+    }
+
+    fn xml_escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    pub fn entry_for(exe: &Path) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            LABEL,
+            xml_escape(&exe.to_string_lossy()),
+            LAUNCH_FLAG
+        )
+    }
+
+    pub fn read_entry() -> Option<String> {
+        fs::read_to_string(entry_path()?).ok()
+    }
+
+    pub fn write_entry(contents: &str) -> io::Result<()> {
+        let path = entry_path().ok_or_else(|| io::Error::other("HOME is not set"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn remove_entry() -> io::Result<()> {
+        match entry_path() {
+            Some(path) => not_found_ok(fs::remove_file(path)),
+            None => Ok(()),
+        }
+    }
+}
+
+// ============================================================================
+// Windows - HKCU Run key (via reg.exe)
+// ============================================================================
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::LAUNCH_FLAG;
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE_NAME: &str = "NetGauge";
+
+    pub fn entry_for(exe: &Path) -> String {
+        format!("\"{}\" {}", exe.display(), LAUNCH_FLAG)
+    }
+
+    pub fn read_entry() -> Option<String> {
+        let output = Command::new("reg")
+            .args(["query", RUN_KEY, "/v", VALUE_NAME])
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        // example line: "    NetGauge    REG_SZ    "C:\...\netgauge-gui.exe" --minimized"
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.split_once("REG_SZ"))
+            .map(|(_, value)| value.trim().to_string())
+    }
+
+    pub fn write_entry(contents: &str) -> io::Result<()> {
+        run(&[
+            "add", RUN_KEY, "/v", VALUE_NAME, "/t", "REG_SZ", "/d", contents, "/f",
+        ])
+    }
+
+    pub fn remove_entry() -> io::Result<()> {
+        if read_entry().is_none() {
+            return Ok(());
+        }
+        run(&["delete", RUN_KEY, "/v", VALUE_NAME, "/f"])
+    }
+
+    fn run(args: &[&str]) -> io::Result<()> {
+        let status = Command::new("reg").args(args).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "reg {} failed: {}",
+                args[0], status
+            )))
+        }
+    }
+}
+
+// ============================================================================
+// Other platforms - unsupported
+// ============================================================================
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    pub fn entry_for(exe: &Path) -> String {
+        exe.display().to_string()
+    }
+
+    pub fn read_entry() -> Option<String> {
+        None
+    }
+
+    pub fn write_entry(_contents: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "launch at login is not supported on this platform",
+        ))
+    }
+
+    pub fn remove_entry() -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+//! Persisted GUI settings
+//!
+//! A small line-based `key = value` file, so no serialization dependency is needed:
+//!
+//! ```text
+//! # netgauge settings
+//! animate = true
+//! hide_noise = false
+//! opacity = 60
+//! sort = manual
+//! sparkline_scale = independent
+//! selected = enp3s0
+//! selected = wlp2s0
+//! order = ppp0 (WAN)
+//! order = wlp2s0
+//! alias.enp3s0 = Desk Ethernet
+//! ```
+//!
+//! SNMP routers are hand-edited: each `snmp.<label>` line adds a target, with
+//! interfaces picked by index or by name pattern (auto-detected at startup).
+//! Without any, the built-in router settings are used.
+//!
+//! ```text
+//! snmp.router = 192.168.1.1:161
+//! snmp.router.community = public
+//! snmp.router.interface = ppp
+//! snmp.ap = 192.168.1.2:161
+//! snmp.ap.index = 3
+//! snmp.ap.index = 7
+//! snmp.ap.hc_counters = false
+//! ```
+//!
+//! `hc_counters = false` keeps a target on the 32-bit ifInOctets/ifOutOctets, for
+//! older agents whose 64-bit counters misbehave.
+//!
+//! A target that only speaks SNMPv3 takes a user instead of a community
+//! (protocols default to SHA and AES; without `priv_password` it's authNoPriv):
+//!
+//! ```text
+//! snmp.isp.v3.user = netgauge
+//! snmp.isp.v3.auth = SHA-256
+//! snmp.isp.v3.auth_password = secret1
+//! snmp.isp.v3.priv = AES
+//! snmp.isp.v3.priv_password = secret2
+//! ```
+//!
+//! Location: `$XDG_CONFIG_HOME/netgauge/config` (Linux),
+//! `~/Library/Application Support/netgauge/config` (macOS),
+//! `%APPDATA%\netgauge\config` (Windows).
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Interfaces to monitor - None until the user has changed the selection
+    pub selected: Option<Vec<String>>,
+    /// Display names keyed by raw interface name
+    pub aliases: HashMap<String, String>,
+    /// Ease values between polls (None = use the built-in default)
+    pub animate: Option<bool>,
+    /// Leave loopback and container links out of the selector (None = use the built-in default)
+    pub hide_noise: Option<bool>,
+    /// Main window background opacity in percent (None = fully opaque)
+    pub opacity: Option<u8>,
+    /// How cards are ordered (None = automatic)
+    pub sort: Option<SortMode>,
+    /// How sparkline heights are scaled across cards (None = shared)
+    pub sparkline_scale: Option<SparklineScale>,
+    /// Card names in the user's drag-and-drop order
+    pub order: Vec<String>,
+    /// SNMP agents to poll, in file order
+    pub snmp_targets: Vec<SnmpTarget>,
+}
+
+/// How cards are ordered in the main window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortMode {
+    /// Local interfaces as reported by the OS, then WAN cards
+    #[default]
+    Automatic,
+    /// The saved `order`; cards not in it are appended at the end
+    Manual,
+}
+
+/// How sparkline heights are scaled across cards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SparklineScale {
+    /// One axis for all cards, so a busy interface dwarfs an idle one
+    #[default]
+    Shared,
+    /// Each card auto-scales to its own peak
+    Independent,
+}
+
+/// One SNMP agent (router, access point, ...)
+#[derive(Clone, Debug)]
+pub struct SnmpTarget {
+    /// Short name, used to prefix card names when several targets are configured
+    pub label: String,
+    /// `host:port` of the agent
+    pub address: String,
+    /// None = "public"
+    pub community: Option<String>,
+    /// SNMPv3 credentials; the community is ignored when set
+    pub v3: Option<SnmpV3Credentials>,
+    /// None = read the 64-bit HC counters when the agent has them
+    pub hc_counters: Option<bool>,
+    pub interfaces: Vec<SnmpInterface>,
+}
+
+/// An SNMPv3 user, with protocols by name as net-snmp spells them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnmpV3Credentials {
+    pub user: String,
+    /// None = SHA
+    pub auth_protocol: Option<String>,
+    pub auth_password: String,
+    /// None = AES
+    pub priv_protocol: Option<String>,
+    /// None = authNoPriv
+    pub priv_password: Option<String>,
+}
+
+/// How an interface on an SNMP target is chosen
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnmpInterface {
+    /// Fixed ifIndex
+    Index(u32),
+    /// Interfaces whose ifDescr match this case-insensitive regex; the first
+    /// one that is up is used
+    Pattern(String),
+}
+
+impl Config {
+    /// Load the saved settings, falling back to defaults if the file is missing or unreadable
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config_path().ok_or_else(|| io::Error::other("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.render())
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "animate" => config.animate = value.parse().ok(),
+                "hide_noise" => config.hide_noise = value.parse().ok(),
+                "opacity" => config.opacity = value.parse().ok(),
+                "sort" => {
+                    config.sort = match value {
+                        "automatic" => Some(SortMode::Automatic),
+                        "manual" => Some(SortMode::Manual),
+                        _ => None,
+                    }
+                }
+                "sparkline_scale" => {
+                    config.sparkline_scale = match value {
+                        "shared" => Some(SparklineScale::Shared),
+                        "independent" => Some(SparklineScale::Independent),
+                        _ => None,
+                    }
+                }
+                "order" => config.order.push(value.to_string()),
+                "selected" => {
+                    // An empty value records a deliberately empty selection
+                    let selected = config.selected.get_or_insert_with(Vec::new);
+                    if !value.is_empty() {
+                        selected.push(value.to_string());
+                    }
+                }
+                _ => {
+                    if let Some(name) = key.strip_prefix("alias.") {
+                        config.aliases.insert(name.to_string(), value.to_string());
+                    } else if let Some(rest) = key.strip_prefix("snmp.") {
+                        config.parse_snmp(rest, value);
+                    }
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Handle a `snmp.<label>[.<field>]` line (`key` is without the `snmp.` prefix)
+    fn parse_snmp(&mut self, key: &str, value: &str) {
+        let (label, field) = match key.split_once('.') {
+            Some((label, field)) => (label, Some(field)),
+            None => (key, None),
+        };
+
+        let index = match self.snmp_targets.iter().position(|t| t.label == label) {
+            Some(index) => index,
+            None => {
+                self.snmp_targets.push(SnmpTarget {
+                    label: label.to_string(),
+                    address: String::new(),
+                    community: None,
+                    v3: None,
+                    hc_counters: None,
+                    interfaces: Vec::new(),
+                });
+                self.snmp_targets.len() - 1
+            }
+        };
+        let target = &mut self.snmp_targets[index];
+
+        match field {
+            None => target.address = value.to_string(),
+            Some("community") => target.community = Some(value.to_string()),
+            Some("hc_counters") => target.hc_counters = value.parse().ok(),
+            Some("index") => {
+                if let Ok(if_index) = value.parse() {
+                    target.interfaces.push(SnmpInterface::Index(if_index));
+                }
+            }
+            Some("interface") => target
+                .interfaces
+                .push(SnmpInterface::Pattern(value.to_string())),
+            Some(field) => {
+                let Some(field) = field.strip_prefix("v3.") else {
+                    return;
+                };
+                let v3 = target.v3.get_or_insert_with(SnmpV3Credentials::default);
+                let value = value.to_string();
+                match field {
+                    "user" => v3.user = value,
+                    "auth" => v3.auth_protocol = Some(value),
+                    "auth_password" => v3.auth_password = value,
+                    "priv" => v3.priv_protocol = Some(value),
+                    "priv_password" => v3.priv_password = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::from("# netgauge settings\n");
+
+        if let Some(animate) = self.animate {
+            out.push_str(&format!("animate = {}\n", animate));
+        }
+        if let Some(hide_noise) = self.hide_noise {
+            out.push_str(&format!("hide_noise = {}\n", hide_noise));
+        }
+        if let Some(opacity) = self.opacity {
+            out.push_str(&format!("opacity = {}\n", opacity));
+        }
+        match self.sort {
+            Some(SortMode::Automatic) => out.push_str("sort = automatic\n"),
+            Some(SortMode::Manual) => out.push_str("sort = manual\n"),
+            None => {}
+        }
+        match self.sparkline_scale {
+            Some(SparklineScale::Shared) => out.push_str("sparkline_scale = shared\n"),
+            Some(SparklineScale::Independent) => out.push_str("sparkline_scale = independent\n"),
+            None => {}
+        }
+
+        match &self.selected {
+            Some(selected) if selected.is_empty() => out.push_str("selected =\n"),
+            Some(selected) => {
+                let mut selected = selected.clone();
+                selected.sort();
+                for name in selected {
+                    out.push_str(&format!("selected = {}\n", name));
+                }
+            }
+            None => {}
+        }
+
+        // Order matters here, unlike the selection
+        for name in &self.order {
+            out.push_str(&format!("order = {}\n", name));
+        }
+
+        let mut aliases: Vec<_> = self.aliases.iter().collect();
+        aliases.sort();
+        for (name, alias) in aliases {
+            out.push_str(&format!("alias.{} = {}\n", name, alias));
+        }
+
+        for target in &self.snmp_targets {
+            out.push_str(&format!("snmp.{} = {}\n", target.label, target.address));
+            if let Some(community) = &target.community {
+                out.push_str(&format!(
+                    "snmp.{}.community = {}\n",
+                    target.label, community
+                ));
+            }
+            if let Some(hc_counters) = target.hc_counters {
+                out.push_str(&format!(
+                    "snmp.{}.hc_counters = {}\n",
+                    target.label, hc_counters
+                ));
+            }
+            if let Some(v3) = &target.v3 {
+                let label = &target.label;
+                out.push_str(&format!("snmp.{}.v3.user = {}\n", label, v3.user));
+                if let Some(protocol) = &v3.auth_protocol {
+                    out.push_str(&format!("snmp.{}.v3.auth = {}\n", label, protocol));
+                }
+                out.push_str(&format!(
+                    "snmp.{}.v3.auth_password = {}\n",
+                    label, v3.auth_password
+                ));
+                if let Some(protocol) = &v3.priv_protocol {
+                    out.push_str(&format!("snmp.{}.v3.priv = {}\n", label, protocol));
+                }
+                if let Some(password) = &v3.priv_password {
+                    out.push_str(&format!("snmp.{}.v3.priv_password = {}\n", label, password));
+                }
+            }
+            for interface in &target.interfaces {
+                match interface {
+                    SnmpInterface::Index(if_index) => {
+                        out.push_str(&format!("snmp.{}.index = {}\n", target.label, if_index))
+                    }
+                    SnmpInterface::Pattern(pattern) => {
+                        out.push_str(&format!("snmp.{}.interface = {}\n", target.label, pattern))
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+
+    let dir = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|h| h.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| home().map(|h| h.join(".config")))
+    }?;
+
+    Some(dir.join("netgauge").join("config"))
+}
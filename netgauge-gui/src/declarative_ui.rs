@@ -3,43 +3,291 @@
 //! This module provides Tailwind-inspired style parsing and macros
 //! that work directly with GPUI's Div type - no intermediate Element struct.
 
-use std::sync::Arc;
-use gpui::{div, prelude::*, px, rgb, uniform_list, AnyElement, Div, FontWeight, Styled};
+use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use gpui::{
+    anchored, deferred, div, prelude::*, px, rgb, svg, uniform_list, AlignItems, AnyElement, App,
+    Div, ElementId, FontWeight, Pixels, Point, Rgba, SharedString, Stateful, StyleRefinement,
+    Styled, Svg, Window,
+};
 
-/// Apply a single style string to a Div
-pub fn apply_style(el: Div, style: &str) -> Div {
-    match style {
+use crate::icons::Icon;
+
+/// Colors registered by the app (theme tokens such as `surface` or `muted`)
+static PALETTE: RwLock<Option<HashMap<String, Rgba>>> = RwLock::new(None);
+
+/// Bumped by every `set_palette`, so styles parsed with the old colors are dropped
+static PALETTE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Outline of elements with unknown classes in debug builds, so typos stand out
+const MISSING_COLOR: u32 = 0xff00ff;
+
+/// Family behind `font-mono`: one that ships with each platform, so digits have a fixed width
+const MONO_FONT: &str = if cfg!(target_os = "macos") {
+    "Menlo"
+} else if cfg!(target_os = "windows") {
+    "Consolas"
+} else {
+    "DejaVu Sans Mono"
+};
+
+/// Unknown classes already reported, as "file:line: class"
+static WARNED_CLASSES: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Register the app's named colors, replacing any previously registered set
+///
+/// Names become usable as `bg-<name>`, `text-<name>` and `border-<name>` and take
+/// precedence over the built-in names. Call again to switch themes at runtime;
+/// views pick the new colors up on their next render.
+pub fn set_palette(palette: HashMap<&str, Rgba>) {
+    let palette = palette
+        .into_iter()
+        .map(|(name, color)| (name.to_string(), color))
+        .collect();
+    *PALETTE.write().unwrap() = Some(palette);
+    PALETTE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A registered name, falling back to the built-in ones
+fn palette_color(name: &str) -> Option<Rgba> {
+    let registered = PALETTE
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|palette| palette.get(name).copied());
+    registered.or_else(|| builtin_color(name))
+}
+
+/// Built-in names usable as `bg-<name>` and `border-<name>`
+fn builtin_color(name: &str) -> Option<Rgba> {
+    let hex = match name {
+        "gray" => 0x333333,
+        "dark" => 0x1e1e1e,
+        "light-gray" => 0x444444,
+        "blue" => 0x4a90e2,
+        "footer" => 0x252525,
+        "wan" => 0x2d5a3d,
+        "error" => 0x8b2d2d,
+        "tooltip" => 0x444444,
+        _ => return None,
+    };
+    Some(rgb(hex))
+}
+
+/// A palette name, or a bracketed arbitrary value: `[#f80]`, `[#ff8800]`, `[rgb(255, 136, 0)]`
+fn color_value(value: &str) -> Option<Rgba> {
+    let Some(arbitrary) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) else {
+        return palette_color(value);
+    };
+
+    let color = parse_arbitrary_color(arbitrary);
+    if color.is_none() && cfg!(debug_assertions) {
+        eprintln!("[warn] declarative_ui: ignoring invalid color [{}]", arbitrary);
+    }
+    color
+}
+
+fn parse_arbitrary_color(value: &str) -> Option<Rgba> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        // #abc is shorthand for #aabbcc
+        let hex: String = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return None,
+        };
+        return u32::from_str_radix(&hex, 16).ok().map(rgb);
+    }
+
+    let channels = value.strip_prefix("rgb(")?.strip_suffix(')')?;
+    let channels: Vec<u8> = channels
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some(rgb((r as u32) << 16 | (g as u32) << 8 | b as u32)),
+        _ => None,
+    }
+}
+
+/// `el` painted by `paint` in the missing color when `value` is a color name that's
+/// neither registered nor built in (debug builds only; malformed `[...]` values
+/// are warned about by `color_value`)
+fn mark_missing_color<E>(el: E, value: &str, paint: impl FnOnce(E, Rgba) -> E) -> E {
+    if cfg!(debug_assertions) && !value.starts_with('[') {
+        paint(el, rgb(MISSING_COLOR))
+    } else {
+        el
+    }
+}
+
+/// Color behind a `bg-*` class, for callers that need to adjust it (e.g. its alpha)
+pub fn background_color(class: &str) -> Option<Rgba> {
+    color_value(class.strip_prefix("bg-")?)
+}
+
+/// Apply a single style string to anything styleable (a Div, or a hover style refinement)
+///
+/// Unrecognized classes are ignored, except that unknown color names show up in
+/// magenta in debug builds; see `unknown_classes` to find them.
+pub fn apply_style<E: Styled>(el: E, style: &str) -> E {
+    match try_apply_style(el, style) {
+        Ok(el) | Err(el) => el,
+    }
+}
+
+/// `apply_style`, handing the element back unchanged as `Err` when the class
+/// (or its value) isn't recognized
+fn try_apply_style<E: Styled>(mut el: E, style: &str) -> Result<E, E> {
+    Ok(match style {
         "flex" => el.flex(),
         "flex-col" | "col" => el.flex_col(),
         "flex-row" | "row" => el.flex_row(),
         "flex-grow" => el.flex_grow(),
+        "flex-1" => el.flex_1(),
+        "flex-shrink-0" => el.flex_shrink_0(),
+        "flex-wrap" => el.flex_wrap(),
+        "justify-start" => el.justify_start(),
         "justify-center" => el.justify_center(),
+        "justify-end" => el.justify_end(),
         "justify-between" => el.justify_between(),
+        "justify-around" => el.justify_around(),
+        "items-start" => el.items_start(),
         "items-center" => el.items_center(),
+        "items-end" => el.items_end(),
+        "items-stretch" => {
+            // gpui has no builder method for this one
+            el.style().align_items = Some(AlignItems::Stretch);
+            el
+        }
         "font-bold" | "bold" => el.font_weight(FontWeight::BOLD),
+        "font-mono" => el.font_family(MONO_FONT),
+        // font-["JetBrains Mono"]; the quotes are optional
+        s if s.starts_with("font-[") && s.ends_with(']') => {
+            let family = s["font-[".len()..s.len() - 1].trim_matches(|c| c == '"' || c == '\'');
+            if family.trim().is_empty() {
+                return Err(el);
+            }
+            el.font_family(family.trim().to_string())
+        }
+        "italic" => el.italic(),
         "cursor-pointer" => el.cursor_pointer(),
         "size-full" => el.size_full(),
-        "bg-gray" => el.bg(rgb(0x333333)),
-        "bg-dark" => el.bg(rgb(0x1e1e1e)),
-        "bg-light-gray" => el.bg(rgb(0x444444)),
-        "bg-blue" => el.bg(rgb(0x4a90e2)),
-        "bg-footer" => el.bg(rgb(0x252525)),
-        "bg-wan" => el.bg(rgb(0x2d5a3d)),
+        "relative" => el.relative(),
+        "absolute" => el.absolute(),
+        "overflow-hidden" => el.overflow_hidden(),
+        "overflow-x-hidden" => el.overflow_x_hidden(),
+        "overflow-y-hidden" => el.overflow_y_hidden(),
+        "rounded" => el.rounded(px(4.0)),
+        "border" => el.border(px(1.0)),
+        s if s.starts_with("bg-") => match background_color(s) {
+            Some(color) => el.bg(color),
+            None => return Err(mark_missing_color(el, &s["bg-".len()..], |el, c| el.bg(c))),
+        },
         "text-white" => el.text_color(rgb(0xffffff)),
         "text-gray" => el.text_color(rgb(0xcccccc)),
         "text-dim" => el.text_color(rgb(0x666666)),
         "text-blue" => el.text_color(rgb(0x4a90e2)),
+        "text-red" => el.text_color(rgb(0xe05252)),
         "text-xl" => el.text_size(px(20.0)),
         "text-lg" => el.text_size(px(18.0)),
         "text-sm" => el.text_size(px(14.0)),
         "text-xs" => el.text_size(px(12.0)),
         "text-2xl" => el.text_size(px(24.0)),
+        "accent" => match palette_color("accent") {
+            Some(color) => el.text_color(color),
+            None => return Err(el),
+        },
+        "text-left" => el.text_left(),
+        "text-center" => el.text_center(),
+        "text-right" => el.text_right(),
+        "truncate" => el.truncate(),
+        "whitespace-nowrap" => el.whitespace_nowrap(),
         s if s.starts_with("gap-") => {
             if let Ok(v) = s["gap-".len()..].parse::<f32>() { el.gap(px(v)) } else { el }
         }
         s if s.starts_with("p-") => {
             if let Ok(v) = s["p-".len()..].parse::<f32>() { el.p(px(v)) } else { el }
         }
+        s if s.starts_with("px-") => {
+            if let Ok(v) = s["px-".len()..].parse::<f32>() { el.px(px(v)) } else { el }
+        }
+        s if s.starts_with("py-") => {
+            if let Ok(v) = s["py-".len()..].parse::<f32>() { el.py(px(v)) } else { el }
+        }
+        s if s.starts_with("pt-") => {
+            if let Ok(v) = s["pt-".len()..].parse::<f32>() { el.pt(px(v)) } else { el }
+        }
+        s if s.starts_with("pb-") => {
+            if let Ok(v) = s["pb-".len()..].parse::<f32>() { el.pb(px(v)) } else { el }
+        }
+        s if s.starts_with("pl-") => {
+            if let Ok(v) = s["pl-".len()..].parse::<f32>() { el.pl(px(v)) } else { el }
+        }
+        s if s.starts_with("pr-") => {
+            if let Ok(v) = s["pr-".len()..].parse::<f32>() { el.pr(px(v)) } else { el }
+        }
+        // opacity-0 .. opacity-100, dims the whole subtree
+        s if s.starts_with("opacity-") => {
+            if let Ok(v) = s["opacity-".len()..].parse::<f32>() {
+                el.opacity(v.clamp(0.0, 100.0) / 100.0)
+            } else {
+                el
+            }
+        }
+        s if s.starts_with("rounded-") => {
+            if let Ok(v) = s["rounded-".len()..].parse::<f32>() { el.rounded(px(v)) } else { el }
+        }
+        // border-2 sets the width, border-<color> the color
+        s if s.starts_with("border-") => {
+            let value = &s["border-".len()..];
+            if let Ok(v) = value.parse::<f32>() {
+                el.border(px(v))
+            } else if let Some(color) = color_value(value) {
+                el.border_color(color)
+            } else {
+                return Err(mark_missing_color(el, value, |el, c| el.border_color(c)));
+            }
+        }
+        s if s.starts_with("m-") => {
+            if let Ok(v) = s["m-".len()..].parse::<f32>() { el.m(px(v)) } else { el }
+        }
+        s if s.starts_with("mx-") => {
+            if let Ok(v) = s["mx-".len()..].parse::<f32>() { el.mx(px(v)) } else { el }
+        }
+        s if s.starts_with("my-") => {
+            if let Ok(v) = s["my-".len()..].parse::<f32>() { el.my(px(v)) } else { el }
+        }
+        s if s.starts_with("mt-") => {
+            if let Ok(v) = s["mt-".len()..].parse::<f32>() { el.mt(px(v)) } else { el }
+        }
+        s if s.starts_with("mb-") => {
+            if let Ok(v) = s["mb-".len()..].parse::<f32>() { el.mb(px(v)) } else { el }
+        }
+        s if s.starts_with("ml-") => {
+            if let Ok(v) = s["ml-".len()..].parse::<f32>() { el.ml(px(v)) } else { el }
+        }
+        s if s.starts_with("mr-") => {
+            if let Ok(v) = s["mr-".len()..].parse::<f32>() { el.mr(px(v)) } else { el }
+        }
+        s if s.starts_with("min-w-") => {
+            if let Ok(v) = s["min-w-".len()..].parse::<f32>() { el.min_w(px(v)) } else { el }
+        }
+        s if s.starts_with("max-w-") => {
+            if let Ok(v) = s["max-w-".len()..].parse::<f32>() { el.max_w(px(v)) } else { el }
+        }
+        s if s.starts_with("min-h-") => {
+            if let Ok(v) = s["min-h-".len()..].parse::<f32>() { el.min_h(px(v)) } else { el }
+        }
+        s if s.starts_with("max-h-") => {
+            if let Ok(v) = s["max-h-".len()..].parse::<f32>() { el.max_h(px(v)) } else { el }
+        }
         s if s.starts_with("w-") => {
             if let Ok(v) = s["w-".len()..].parse::<f32>() { el.w(px(v)) } else { el }
         }
@@ -49,57 +297,733 @@ pub fn apply_style(el: Div, style: &str) -> Div {
         s if s.starts_with("size-") => {
             if let Ok(v) = s["size-".len()..].parse::<f32>() { el.size(px(v)) } else { el }
         }
+        // Offsets for `absolute` elements, from the nearest `relative` parent
+        s if s.starts_with("inset-") => {
+            if let Ok(v) = s["inset-".len()..].parse::<f32>() { el.inset(px(v)) } else { el }
+        }
+        s if s.starts_with("top-") => {
+            if let Ok(v) = s["top-".len()..].parse::<f32>() { el.top(px(v)) } else { el }
+        }
+        s if s.starts_with("right-") => {
+            if let Ok(v) = s["right-".len()..].parse::<f32>() { el.right(px(v)) } else { el }
+        }
+        s if s.starts_with("bottom-") => {
+            if let Ok(v) = s["bottom-".len()..].parse::<f32>() { el.bottom(px(v)) } else { el }
+        }
+        s if s.starts_with("left-") => {
+            if let Ok(v) = s["left-".len()..].parse::<f32>() { el.left(px(v)) } else { el }
+        }
         s if s.starts_with("text-") && s.len() > 5 && s.chars().nth(5).map(|c| c.is_ascii_digit()).unwrap_or(false) => {
             if let Ok(v) = s["text-".len()..].parse::<f32>() { el.text_size(px(v)) } else { el }
         }
-        _ => el,
-    }
+        // text-<palette name> or text-[#hex]
+        s if s.starts_with("text-") => match color_value(&s["text-".len()..]) {
+            Some(color) => el.text_color(color),
+            None => {
+                return Err(mark_missing_color(el, &s["text-".len()..], |el, c| el.text_color(c)));
+            }
+        },
+        _ => return Err(el),
+    })
+}
+
+/// Split a class string on whitespace, except inside `[...]` so arbitrary values
+/// may contain spaces (`font-["JetBrains Mono"]`, `bg-[rgb(255, 136, 0)]`)
+fn classes(styles: &str) -> impl Iterator<Item = &str> {
+    let mut rest = styles.trim_start();
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                c.is_whitespace() && depth <= 0
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let (class, tail) = rest.split_at(end);
+        rest = tail.trim_start();
+        Some(class)
+    })
 }
 
-/// Apply multiple space-separated styles to a Div
-pub fn apply_styles(mut el: Div, styles: &str) -> Div {
-    for style in styles.split_whitespace() {
-        el = apply_style(el, style);
+/// Apply multiple space-separated styles to a Div (or any other interactive element)
+///
+/// Classes prefixed with `hover:` (e.g. `hover:bg-blue`) apply only while the
+/// pointer is over the element.
+pub fn apply_styles<E: Styled + InteractiveElement>(mut el: E, styles: &str) -> E {
+    let mut hover_styles = Vec::new();
+    for style in classes(styles) {
+        match style.strip_prefix("hover:") {
+            Some(hover_style) => hover_styles.push(hover_style),
+            None => el = apply_style(el, style),
+        }
+    }
+
+    if !hover_styles.is_empty() {
+        el = el.hover(|mut refinement| {
+            for style in hover_styles {
+                refinement = apply_style(refinement, style);
+            }
+            refinement
+        });
     }
     el
 }
 
+/// Classes in `styles` that `apply_styles` doesn't recognize (typos, bad values
+/// such as an unknown color name)
+pub fn unknown_classes(styles: &str) -> Vec<&str> {
+    classes(styles)
+        .filter(|class| {
+            let class = class.strip_prefix("hover:").unwrap_or(class);
+            try_apply_style(StyleRefinement::default(), class).is_err()
+        })
+        .collect()
+}
+
+/// Debug builds: report unknown classes in `styles` once per call site, returning
+/// whether there were any. Release builds skip the check.
+fn check_classes(styles: &str, file: &'static str, line: u32) -> bool {
+    if !cfg!(debug_assertions) {
+        return false;
+    }
+
+    let unknown = unknown_classes(styles);
+    if unknown.is_empty() {
+        return false;
+    }
+    let mut warned = WARNED_CLASSES.lock().unwrap();
+    let warned = warned.get_or_insert_with(HashSet::new);
+    for class in unknown {
+        if warned.insert(format!("{}:{}: {}", file, line, class)) {
+            eprintln!("[warn] {}:{}: unknown style class \"{}\"", file, line, class);
+        }
+    }
+    true
+}
+
+/// `apply_styles` for the macros, which pass their call site
+///
+/// In debug builds, unknown classes are reported and the element gets a magenta
+/// outline.
+pub fn apply_styles_at<E: Styled + InteractiveElement>(
+    el: E,
+    styles: &str,
+    file: &'static str,
+    line: u32,
+) -> E {
+    let el = apply_styles(el, styles);
+    if check_classes(styles, file, line) {
+        el.border(px(1.0)).border_color(rgb(MISSING_COLOR))
+    } else {
+        el
+    }
+}
+
+/// A class string parsed into style refinements
+struct ParsedStyles {
+    base: StyleRefinement,
+    hover: Option<StyleRefinement>,
+}
+
+/// Parsed literal class strings and the palette generation they were parsed under
+type StyleCache = (u64, HashMap<&'static str, Rc<ParsedStyles>>);
+
+thread_local! {
+    /// Parsed literal class strings, shared by every call site using the same string
+    static PARSED_STYLES: RefCell<StyleCache> = RefCell::new((0, HashMap::new()));
+}
+
+/// `apply_styles_at` for class strings known at compile time (the macros use it
+/// for string literals)
+///
+/// Each string is parsed and checked once; later renders merge the cached result
+/// instead of splitting and matching the classes again every frame. The cache
+/// starts over after `set_palette`, since parsing resolved the color names.
+pub fn apply_static_styles_at<E: Styled + InteractiveElement>(
+    mut el: E,
+    styles: &'static str,
+    file: &'static str,
+    line: u32,
+) -> E {
+    let parsed = PARSED_STYLES.with(|cache| {
+        let (parsed_under, cache) = &mut *cache.borrow_mut();
+        let generation = PALETTE_GENERATION.load(Ordering::Relaxed);
+        if *parsed_under != generation {
+            cache.clear();
+            *parsed_under = generation;
+        }
+        let parsed = cache.entry(styles).or_insert_with(|| {
+            let mut base = StyleRefinement::default();
+            let mut hover = None;
+            for style in classes(styles) {
+                match style.strip_prefix("hover:") {
+                    Some(hover_style) => {
+                        hover = Some(apply_style(hover.unwrap_or_default(), hover_style))
+                    }
+                    None => base = apply_style(base, style),
+                }
+            }
+            if check_classes(styles, file, line) {
+                base = base.border(px(1.0)).border_color(rgb(MISSING_COLOR));
+            }
+            Rc::new(ParsedStyles { base, hover })
+        });
+        parsed.clone()
+    });
+
+    el.style().refine(&parsed.base);
+    match &parsed.hover {
+        Some(hover) => {
+            let hover = hover.clone();
+            el.hover(move |style| style.refined(hover))
+        }
+        None => el,
+    }
+}
+
 /// Create a styled div from a style string
 pub fn styled_div(styles: &str) -> Div {
     apply_styles(div(), styles)
 }
 
-/// Create a uniform_list with styling
-pub fn styled_list<F>(
-    id: &'static str,
+/// Create a styled div that scrolls vertically when its children overflow
+///
+/// Scroll position is element state, so unlike `styled_div` this needs an id
+/// that is unique among its siblings.
+pub fn scrollable_div(id: impl Into<ElementId>, styles: &str) -> Stateful<Div> {
+    styled_div(styles).id(id).overflow_y_scroll()
+}
+
+/// A built-in icon, 16px and white unless `styles` says otherwise (`size-*`, `text-*`)
+///
+/// Needs the app to be started with `icons::Assets` as its asset source.
+pub fn icon(icon: Icon, styles: &str) -> Svg {
+    let el = svg()
+        .path(icon.path())
+        .flex_shrink_0()
+        .size(px(16.0))
+        .text_color(rgb(0xffffff));
+    apply_styles(el, styles)
+}
+
+/// `content` with `floating` drawn on top of it, placed by `styles`
+/// (e.g. "bottom-4 right-4"). Later siblings paint above earlier ones, so the
+/// floating child covers the content without needing a z-index.
+pub fn overlay(content: impl IntoElement, floating: impl IntoElement, styles: &str) -> Div {
+    styled_div("relative")
+        .child(content)
+        .child(styled_div(&format!("absolute {}", styles)).child(floating))
+}
+
+/// What a click handler is told about the click
+#[derive(Clone, Debug)]
+pub struct ClickEvent {
+    /// Id (or jsx `key`) of the clicked element, if it has one
+    pub element_id: Option<ElementId>,
+}
+
+/// What a hover handler is told when the pointer enters or leaves an element
+#[derive(Clone, Debug)]
+pub struct HoverEvent {
+    /// True on enter, false on leave
+    pub hovered: bool,
+}
+
+/// What a scroll handler is told about a scroll-wheel or trackpad gesture
+#[derive(Clone, Debug)]
+pub struct ScrollEvent {
+    /// Scroll distance, with line-based deltas converted at the window's line height
+    pub delta: Point<Pixels>,
+}
+
+// The handlers below get the window and app context so they can open windows or
+// update globals directly. For the click handlers, set the element's id first: it
+// is read when the handler is attached to fill in the event's `element_id`.
+// Windows are refreshed after each handler runs.
+
+/// Run `handler` on left mouse-down
+pub fn on_click<E: InteractiveElement>(
+    mut el: E,
+    handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+) -> E {
+    let element_id = el.interactivity().element_id.clone();
+    el.on_mouse_down(gpui::MouseButton::Left, move |_ev, window, cx| {
+        let event = ClickEvent {
+            element_id: element_id.clone(),
+        };
+        handler(&event, window, cx);
+        cx.refresh_windows();
+    })
+}
+
+/// Run `handler` on the second left mouse-down of a double click
+pub fn on_double_click<E: InteractiveElement>(
+    mut el: E,
+    handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+) -> E {
+    let element_id = el.interactivity().element_id.clone();
+    el.on_mouse_down(gpui::MouseButton::Left, move |ev, window, cx| {
+        if ev.click_count != 2 {
+            return;
+        }
+        let event = ClickEvent {
+            element_id: element_id.clone(),
+        };
+        handler(&event, window, cx);
+        cx.refresh_windows();
+    })
+}
+
+/// Run `handler` on left mouse-up
+pub fn on_mouse_up<E: InteractiveElement>(
+    mut el: E,
+    handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+) -> E {
+    let element_id = el.interactivity().element_id.clone();
+    el.on_mouse_up(gpui::MouseButton::Left, move |_ev, window, cx| {
+        let event = ClickEvent {
+            element_id: element_id.clone(),
+        };
+        handler(&event, window, cx);
+        cx.refresh_windows();
+    })
+}
+
+/// Run `handler` when the pointer enters or leaves the element (which needs an id)
+pub fn on_hover<E: StatefulInteractiveElement>(
+    el: E,
+    handler: impl Fn(&HoverEvent, &mut Window, &mut App) + 'static,
+) -> E {
+    el.on_hover(move |hovered, window, cx| {
+        let event = HoverEvent { hovered: *hovered };
+        handler(&event, window, cx);
+        cx.refresh_windows();
+    })
+}
+
+/// Show `text` in a small popup after the pointer rests on the element (which
+/// needs an id); gpui applies the show delay. Themed by the `tooltip` color.
+pub fn tooltip<E: StatefulInteractiveElement>(el: E, text: impl Into<SharedString>) -> E {
+    let text = text.into();
+    el.tooltip(move |_window, cx| {
+        let text = text.clone();
+        cx.new(|_cx| Tooltip { text }).into()
+    })
+}
+
+struct Tooltip {
+    text: SharedString,
+}
+
+impl Render for Tooltip {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        styled_div("px-4 py-1 rounded bg-tooltip border border-light-gray text-white text-xs")
+            .debug_selector(|| format!("tooltip: {}", self.text))
+            .child(self.text.clone())
+    }
+}
+
+/// Run `handler` on scroll-wheel and trackpad scrolling over the element
+pub fn on_scroll<E: InteractiveElement>(
+    el: E,
+    handler: impl Fn(&ScrollEvent, &mut Window, &mut App) + 'static,
+) -> E {
+    el.on_scroll_wheel(move |ev, window, cx| {
+        let event = ScrollEvent {
+            delta: ev.delta.pixel_delta(window.line_height()),
+        };
+        handler(&event, window, cx);
+        cx.refresh_windows();
+    })
+}
+
+/// Extras for `styled_list_with`
+#[derive(Default)]
+pub struct ListOptions {
+    /// Classes of a divider drawn under each item (e.g. `"h-1 bg-light-gray"`)
+    ///
+    /// uniform_list needs every row to be the same height, so the last item gets
+    /// the divider too, just invisible.
+    pub separator: Option<&'static str>,
+    /// Shown in place of the list when it has no items
+    pub empty: Option<AnyElement>,
+}
+
+/// Create a uniform_list with styling, plus separators and/or an empty state
+///
+/// Classes go through the same `apply_styles` as divs, so the list supports every
+/// class (hover variants included) without a list-specific copy of the matcher.
+/// The id may be built at runtime (e.g. a `SharedString`); it needn't be `'static`.
+/// The `<list>` element is this with default options.
+pub fn styled_list_with<F>(
+    id: impl Into<ElementId>,
     count: usize,
     styles: &str,
     renderer: F,
+    options: ListOptions,
 ) -> AnyElement
 where
     F: Fn(usize) -> AnyElement + Send + Sync + 'static,
 {
+    if count == 0
+        && let Some(empty) = options.empty
+    {
+        return empty;
+    }
+
     let renderer = Arc::new(renderer);
-    let mut list = uniform_list(id, count, move |range, _window, _cx| {
-        range.map(|ix| renderer(ix)).collect()
+    let separator = options.separator;
+    let list = uniform_list(id, count, move |range, _window, _cx| {
+        range
+            .map(|ix| match separator {
+                Some(classes) => {
+                    let divider = styled_div(classes);
+                    let divider = if ix + 1 == count { divider.opacity(0.0) } else { divider };
+                    styled_div("flex col")
+                        .child(renderer(ix))
+                        .child(divider)
+                        .into_any_element()
+                }
+                None => renderer(ix),
+            })
+            .collect()
     });
 
-    // Apply styles to the list
-    for style in styles.split_whitespace() {
-        list = match style {
-            "flex-grow" => list.flex_grow(),
-            "size-full" => list.size_full(),
-            s if s.starts_with("p-") => {
-                if let Ok(v) = s["p-".len()..].parse::<f32>() { list.p(px(v)) } else { list }
-            }
-            s if s.starts_with("gap-") => {
-                if let Ok(v) = s["gap-".len()..].parse::<f32>() { list.gap(px(v)) } else { list }
+    apply_styles(list, styles).into_any_element()
+}
+
+/// A menu action, run with the window it was picked in
+pub type Action = dyn Fn(&mut Window, &mut App);
+
+/// One entry of a popup menu
+pub struct MenuItem {
+    pub label: SharedString,
+    pub on_select: Box<Action>,
+}
+
+impl MenuItem {
+    pub fn new(
+        label: impl Into<SharedString>,
+        on_select: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            on_select: Box::new(on_select),
+        }
+    }
+}
+
+/// Popup menu anchored at `position` (window coordinates), drawn above everything else
+///
+/// Items are picked on mouse-up, so a press that slides off an item picks nothing.
+/// `on_dismiss` runs after an item is selected and on any click outside the menu;
+/// the owner should drop the menu from its state there. Escape handling is left to
+/// the owning view since it holds the focus.
+pub fn popup_menu(
+    position: Point<Pixels>,
+    items: Vec<MenuItem>,
+    on_dismiss: impl Fn(&mut Window, &mut App) + 'static,
+) -> AnyElement {
+    let on_dismiss: Rc<Action> = Rc::new(on_dismiss);
+
+    let mut menu = styled_div("flex col bg-light-gray text-white text-sm p-1 w-160");
+    for item in items {
+        let on_dismiss = on_dismiss.clone();
+        let entry = styled_div("p-2 cursor-pointer hover:bg-blue").child(item.label.clone());
+        menu = menu.child(on_mouse_up(entry, move |_event, window, cx| {
+            (item.on_select)(window, cx);
+            on_dismiss(window, cx);
+        }));
+    }
+
+    let menu = menu.on_mouse_down_out(move |_ev, window, cx| on_dismiss(window, cx));
+
+    deferred(anchored().position(position).snap_to_window().child(menu)).into_any_element()
+}
+
+/// A component's event callback, told what happened
+pub type Handler<T> = dyn Fn(&T, &mut Window, &mut App);
+
+/// A checkbox: a bordered box, filled when checked, followed by a label
+///
+/// Built with `checkbox(checked, label)` and the setters below. It grows to fill
+/// the row it sits in, so the whole row is clickable. `on_toggle` receives the new
+/// value; a disabled checkbox is dimmed and ignores clicks.
+#[derive(Default)]
+pub struct Checkbox {
+    checked: bool,
+    label: SharedString,
+    disabled: bool,
+    on_toggle: Option<Rc<Handler<bool>>>,
+}
+
+pub fn checkbox(checked: bool, label: impl Into<SharedString>) -> Checkbox {
+    Checkbox::default().checked(checked).label(label)
+}
+
+impl Checkbox {
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<SharedString>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Takes the same shape as gpui listeners, so `cx.listener(...)` works here
+    pub fn on_toggle(mut self, on_toggle: impl Fn(&bool, &mut Window, &mut App) + 'static) -> Self {
+        self.on_toggle = Some(Rc::new(on_toggle));
+        self
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+}
+
+impl IntoElement for Checkbox {
+    type Element = Div;
+
+    fn into_element(self) -> Div {
+        const GROUP: &str = "checkbox";
+
+        let box_style = if self.checked { "bg-blue border-blue" } else { "border-light-gray" };
+        let mut check_box = styled_div(&format!(
+            "flex items-center justify-center flex-shrink-0 size-14 rounded-3 border {}",
+            box_style
+        ));
+        if self.checked {
+            check_box = check_box.child(styled_div("size-6 rounded-1 bg-white"));
+        }
+
+        let mut el = styled_div("flex row flex-1 items-center gap-2")
+            .child(check_box.group_hover(GROUP, |style| apply_style(style, "border-blue")))
+            .child(styled_div("truncate").child(self.label));
+
+        if self.disabled {
+            return apply_style(el, "opacity-50");
+        }
+        el = el.group(GROUP).cursor_pointer();
+        if let Some(on_toggle) = self.on_toggle {
+            let checked = self.checked;
+            el = el.on_mouse_down(gpui::MouseButton::Left, move |_ev, window, cx| {
+                on_toggle(&!checked, window, cx);
+                cx.refresh_windows();
+            });
+        }
+        el
+    }
+}
+
+/// A set of selected items shared between a checkbox list and whoever acts on it
+///
+/// Clones share the same set. Every change bumps a version number, so readers
+/// (e.g. a background task) can tell whether the selection moved since they last
+/// looked without comparing sets.
+pub struct Selection<T> {
+    items: Arc<RwLock<HashSet<T>>>,
+    version: Arc<AtomicU64>,
+}
+
+impl<T> Clone for Selection<T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> Selection<T> {
+    pub fn new(items: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            items: Arc::new(RwLock::new(items.into_iter().collect())),
+            version: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.read().unwrap().contains(item)
+    }
+
+    /// A copy of the current selection
+    pub fn snapshot(&self) -> HashSet<T> {
+        self.items.read().unwrap().clone()
+    }
+
+    /// Select or deselect `item`, returning whether that changed anything
+    pub fn set(&self, item: T, selected: bool) -> bool {
+        let mut items = self.items.write().unwrap();
+        let changed = if selected { items.insert(item) } else { items.remove(&item) };
+        if changed {
+            // Bumped under the lock, so a snapshot taken after reading the
+            // version is never older than it
+            self.version.fetch_add(1, Ordering::SeqCst);
+        }
+        changed
+    }
+
+    /// Current version; read it before `snapshot` to know what the snapshot covers
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    pub fn changed_since(&self, version: u64) -> bool {
+        self.version() != version
+    }
+}
+
+/// A virtual list with one checkbox per item, bound to `selection`
+///
+/// `row` receives each item with its checkbox - checked state and toggling
+/// already wired up - to label it and lay the row out.
+pub fn checkbox_list<T, R>(
+    id: impl Into<ElementId>,
+    items: Vec<T>,
+    selection: &Selection<T>,
+    styles: &str,
+    row: R,
+    options: ListOptions,
+) -> AnyElement
+where
+    T: Clone + Eq + Hash + Send + Sync + 'static,
+    R: Fn(&T, Checkbox) -> AnyElement + Send + Sync + 'static,
+{
+    let selection = selection.clone();
+    let count = items.len();
+    let renderer = move |ix: usize| {
+        let item = &items[ix];
+        let toggle = {
+            let selection = selection.clone();
+            let item = item.clone();
+            move |checked: &bool, _window: &mut Window, _cx: &mut App| {
+                selection.set(item.clone(), *checked);
             }
-            _ => list,
         };
+        let checkbox = Checkbox::default()
+            .checked(selection.contains(item))
+            .on_toggle(toggle);
+        row(item, checkbox)
+    };
+    styled_list_with(id, count, styles, renderer, options)
+}
+
+/// Look of a `Button`, in theme palette colors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonVariant {
+    /// Filled with the accent color - the main action of a view
+    #[default]
+    Primary,
+    /// Outlined surface
+    Secondary,
+    /// Accent-colored text only, for toolbars and inline controls
+    Ghost,
+}
+
+impl ButtonVariant {
+    /// Classes for the resting, hovered and pressed states
+    fn styles(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            ButtonVariant::Primary => ("bg-accent text-primary", "opacity-90", "opacity-75"),
+            ButtonVariant::Secondary => (
+                "bg-surface text-primary border border-surface-alt",
+                "bg-surface-alt",
+                "opacity-75",
+            ),
+            ButtonVariant::Ghost => ("text-accent", "text-primary", "opacity-75"),
+        }
     }
+}
+
+/// A clickable button with hover, pressed and disabled states
+///
+/// Built with `button(id)`, then `.child(...)` for the label (any element) and the
+/// setters below. The id is what gpui tracks the pressed state by, so it must be
+/// unique among siblings. A disabled button is dimmed and never calls `on_click`.
+pub struct Button {
+    id: ElementId,
+    variant: ButtonVariant,
+    disabled: bool,
+    on_click: Option<Rc<Handler<ClickEvent>>>,
+    children: Vec<AnyElement>,
+}
 
-    list.into_any_element()
+pub fn button(id: impl Into<ElementId>) -> Button {
+    Button {
+        id: id.into(),
+        variant: ButtonVariant::default(),
+        disabled: false,
+        on_click: None,
+        children: Vec::new(),
+    }
+}
+
+impl Button {
+    pub fn variant(mut self, variant: ButtonVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn on_click(
+        mut self,
+        on_click: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_click = Some(Rc::new(on_click));
+        self
+    }
+}
+
+impl ParentElement for Button {
+    fn extend(&mut self, elements: impl IntoIterator<Item = AnyElement>) {
+        self.children.extend(elements);
+    }
+}
+
+impl IntoElement for Button {
+    type Element = Stateful<Div>;
+
+    fn into_element(self) -> Stateful<Div> {
+        let (base, hover, active) = self.variant.styles();
+        let el = styled_div(&format!(
+            "flex row items-center justify-center gap-1 px-8 py-2 rounded-4 text-sm {}",
+            base
+        ))
+        .children(self.children);
+
+        if self.disabled {
+            return apply_style(el, "opacity-50").id(self.id);
+        }
+
+        let el = el
+            .cursor_pointer()
+            .hover(|style| apply_style(style, hover))
+            .id(self.id)
+            .active(|style| apply_style(style, active));
+        match self.on_click {
+            Some(handler) => on_click(el, move |event, window, cx| handler(event, window, cx)),
+            None => el,
+        }
+    }
 }
 
 // ============================================================================
@@ -117,15 +1041,42 @@ where
 ///             text["Label:"]
 ///             text[some_variable]
 ///         }
+///         div["flex col gap-4"] {
+///             maybe(banner)
+///             for card in cards { (card) }
+///         }
+///         if connected { text["Online"] } else { text["Offline"] }
 ///     }
 /// }
 /// ```
+///
+/// `(expr)` adds any element-valued expression as a child and `maybe(expr)` an
+/// `Option` of one; `for` repeats its body once per item and `if` adds its branch's
+/// children only when taken, so these nest and mix freely with static children.
 #[macro_export]
 macro_rules! ui {
+    // String literal classes are parsed once and cached; other expressions every render
+    ( div [ $styles:literal ] { $($children:tt)* } ) => {
+        {
+            let mut el = $crate::declarative_ui::apply_static_styles_at(
+                gpui::div(),
+                $styles,
+                file!(),
+                line!(),
+            );
+            $crate::ui_children!(el, $($children)*);
+            el
+        }
+    };
+    ( div [ $styles:literal ] ) => {
+        $crate::declarative_ui::apply_static_styles_at(gpui::div(), $styles, file!(), line!())
+    };
+
     // div["styles"] { children... }
     ( div [ $styles:expr ] { $($children:tt)* } ) => {
         {
-            let mut el = $crate::declarative_ui::styled_div($styles);
+            let mut el =
+                $crate::declarative_ui::apply_styles_at(gpui::div(), $styles, file!(), line!());
             $crate::ui_children!(el, $($children)*);
             el
         }
@@ -133,13 +1084,58 @@ macro_rules! ui {
 
     // div["styles"] - no children
     ( div [ $styles:expr ] ) => {
-        $crate::declarative_ui::styled_div($styles)
+        $crate::declarative_ui::apply_styles_at(gpui::div(), $styles, file!(), line!())
     };
 }
 
 /// Helper macro to collect children
 #[macro_export]
 macro_rules! ui_children {
+    // for loop internals: collect the iterator tokens up to the body braces
+    (@for $el:ident, $item:pat, [$($iter:tt)*] => { $($body:tt)* } $($rest:tt)* ) => {
+        for $item in $($iter)* {
+            $el = $el.child($crate::ui! { $($body)* });
+        }
+        $crate::ui_children!($el, $($rest)*);
+    };
+    (@for $el:ident, $item:pat, [$($iter:tt)*] { $($body:tt)* } $($rest:tt)* ) => {
+        for $item in $($iter)* {
+            $crate::ui_children!($el, $($body)*);
+        }
+        $crate::ui_children!($el, $($rest)*);
+    };
+    (@for $el:ident, $item:pat, [$($iter:tt)*] $next:tt $($rest:tt)* ) => {
+        $crate::ui_children!(@for $el, $item, [$($iter)* $next] $($rest)*);
+    };
+
+    // if internals: collect the condition tokens up to the branch braces, then
+    // follow any else-if chain so the whole chain becomes one if expression
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } else if $($rest:tt)* ) => {
+        $crate::ui_children!(
+            @if $el,
+            [$($done)* if $($cond)* { $crate::ui_children!($el, $($then)*); } else],
+            []
+            $($rest)*
+        );
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } else { $($otherwise:tt)* } $($rest:tt)* ) => {
+        $($done)* if $($cond)* {
+            $crate::ui_children!($el, $($then)*);
+        } else {
+            $crate::ui_children!($el, $($otherwise)*);
+        }
+        $crate::ui_children!($el, $($rest)*);
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } $($rest:tt)* ) => {
+        $($done)* if $($cond)* {
+            $crate::ui_children!($el, $($then)*);
+        }
+        $crate::ui_children!($el, $($rest)*);
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] $next:tt $($rest:tt)* ) => {
+        $crate::ui_children!(@if $el, [$($done)*], [$($cond)* $next] $($rest)*);
+    };
+
     // Base case - no more children
     ($el:ident, ) => {};
 
@@ -149,23 +1145,52 @@ macro_rules! ui_children {
         $crate::ui_children!($el, $($rest)*);
     };
 
-    // div with children
+    // div with children (literal classes stay literals for the cached path)
+    ($el:ident, div [ $styles:literal ] { $($body:tt)* } $($rest:tt)* ) => {
+        $el = $el.child($crate::ui! { div [ $styles ] { $($body)* } });
+        $crate::ui_children!($el, $($rest)*);
+    };
     ($el:ident, div [ $styles:expr ] { $($body:tt)* } $($rest:tt)* ) => {
         $el = $el.child($crate::ui! { div [ $styles ] { $($body)* } });
         $crate::ui_children!($el, $($rest)*);
     };
 
     // div without children
+    ($el:ident, div [ $styles:literal ] $($rest:tt)* ) => {
+        $el = $el.child($crate::ui! { div [ $styles ] });
+        $crate::ui_children!($el, $($rest)*);
+    };
     ($el:ident, div [ $styles:expr ] $($rest:tt)* ) => {
         $el = $el.child($crate::ui! { div [ $styles ] });
         $crate::ui_children!($el, $($rest)*);
     };
 
-    // for loop - iterate and add children
-    ($el:ident, for $item:ident in $iter:expr => { $($body:tt)* } $($rest:tt)* ) => {
-        for $item in $iter {
-            $el = $el.child($crate::ui! { $($body)* });
-        }
+    // for item in iter { children... } - children may use `item`
+    // (the older `for item in iter => { div[...] }` form is still accepted)
+    ($el:ident, for $item:pat in $($rest:tt)* ) => {
+        $crate::ui_children!(@for $el, $item, [] $($rest)*);
+    };
+
+    // if cond { children... } [else if ... ] [else { children... }] - `if let` works too
+    ($el:ident, if $($rest:tt)* ) => {
+        $crate::ui_children!(@if $el, [], [] $($rest)*);
+    };
+
+    // maybe(option) - adds the element only when it is Some
+    ($el:ident, maybe ( $child:expr ) $($rest:tt)* ) => {
+        $el = $el.children($child);
+        $crate::ui_children!($el, $($rest)*);
+    };
+
+    // icon[Gear] or icon[Gear, "size-12 text-dim"]
+    ($el:ident, icon [ $name:ident $(, $styles:expr)? ] $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <icon name={ $crate::icons::Icon::$name } $(class={ $styles })? /> });
+        $crate::ui_children!($el, $($rest)*);
+    };
+
+    // checkbox[checked = .., label = .., disabled = .., on_toggle = ..] - setters in any order
+    ($el:ident, checkbox [ $($name:ident = $value:expr),* $(,)? ] $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <checkbox $($name = { $value })* /> });
         $crate::ui_children!($el, $($rest)*);
     };
 
@@ -174,6 +1199,12 @@ macro_rules! ui_children {
         $el = $el.child($child);
         $crate::ui_children!($el, $($rest)*);
     };
+
+    // Arbitrary expression in parentheses ( expr )
+    ($el:ident, ( $child:expr ) $($rest:tt)* ) => {
+        $el = $el.child($child);
+        $crate::ui_children!($el, $($rest)*);
+    };
 }
 
 /// JSX-like macro syntax
@@ -183,37 +1214,118 @@ macro_rules! ui_children {
 /// jsx! {
 ///     <div class={"flex col bg-dark"}> {
 ///         <div class={"text-white"}> { "Hello" } </div>
+///         <div class={"flex col gap-4"}> {
+///             for m in &metrics { (build_card(m)) }
+///         } </div>
 ///     } </div>
 /// }
 /// ```
 #[macro_export]
 macro_rules! jsx {
-    // div with class and children
-    ( <div class={ $styles:expr }> { $($body:tt)* } </div> ) => {
+    // div attributes, in any order: class, id or key, and event handlers
+    //
+    // Applied in passes - styles, then the id, then handlers - since the id turns
+    // the Div into a Stateful<Div> and handlers report it in their event.
+    // Keys are element ids: gpui keeps element state by id, so a stable key (e.g.
+    // from the row's data) is all it needs.
+    //
+    // Handlers get `(&<event>, &mut Window, &mut App)`: on_click, on_double_click
+    // and on_mouse_up a ClickEvent, on_hover a HoverEvent (needs an id or key) and
+    // on_scroll a ScrollEvent. `onclick` takes a closure without arguments for
+    // handlers that only touch captured state. `tooltip` (needs an id or key) takes
+    // the text to show.
+    (@div $($name:ident = $value:tt)* ) => {
         {
-            let mut el = $crate::declarative_ui::styled_div($styles);
-            $crate::jsx_children!(el, $($body)*);
+            let el = $crate::declarative_ui::styled_div("");
+            $( $crate::jsx!(@styles el, $name, $value); )*
+            $( $crate::jsx!(@id el, $name, $value); )*
+            $( $crate::jsx!(@events el, $name, $value); )*
             el
         }
     };
+    (@styles $el:ident, class, { $styles:literal }) => {
+        let $el = $crate::declarative_ui::apply_static_styles_at($el, $styles, file!(), line!());
+    };
+    (@styles $el:ident, class, { $styles:expr }) => {
+        let $el = $crate::declarative_ui::apply_styles_at($el, $styles, file!(), line!());
+    };
+    (@styles $el:ident, id, $value:tt) => {};
+    (@styles $el:ident, key, $value:tt) => {};
+    (@styles $el:ident, on_click, $value:tt) => {};
+    (@styles $el:ident, on_double_click, $value:tt) => {};
+    (@styles $el:ident, on_mouse_up, $value:tt) => {};
+    (@styles $el:ident, on_hover, $value:tt) => {};
+    (@styles $el:ident, on_scroll, $value:tt) => {};
+    (@styles $el:ident, onclick, $value:tt) => {};
+    (@styles $el:ident, tooltip, $value:tt) => {};
+    (@styles $el:ident, $other:ident, $value:tt) => {
+        compile_error!(concat!("unknown jsx attribute `", stringify!($other), "`"));
+    };
+    (@id $el:ident, id, { $id:expr }) => {
+        let $el = $el.id($id);
+    };
+    (@id $el:ident, key, { $key:expr }) => {
+        let $el = $el.id($key);
+    };
+    (@id $el:ident, $other:ident, $value:tt) => {};
+    (@events $el:ident, on_click, { $handler:expr }) => {
+        let $el = $crate::declarative_ui::on_click($el.cursor_pointer(), $handler);
+    };
+    (@events $el:ident, on_double_click, { $handler:expr }) => {
+        let $el = $crate::declarative_ui::on_double_click($el, $handler);
+    };
+    (@events $el:ident, on_mouse_up, { $handler:expr }) => {
+        let $el = $crate::declarative_ui::on_mouse_up($el, $handler);
+    };
+    (@events $el:ident, on_hover, { $handler:expr }) => {
+        let $el = $crate::declarative_ui::on_hover($el, $handler);
+    };
+    (@events $el:ident, on_scroll, { $handler:expr }) => {
+        let $el = $crate::declarative_ui::on_scroll($el, $handler);
+    };
+    (@events $el:ident, onclick, { $handler:expr }) => {
+        let handler = $handler;
+        let $el = $crate::declarative_ui::on_click($el.cursor_pointer(), move |_event, _window, _cx| {
+            handler()
+        });
+    };
+    (@events $el:ident, tooltip, { $text:expr }) => {
+        let $el = $crate::declarative_ui::tooltip($el, $text);
+    };
+    (@events $el:ident, $other:ident, $value:tt) => {};
 
-    // div with class, onclick and children
-    ( <div class={ $styles:expr } onclick={ $handler:expr }> { $($body:tt)* } </div> ) => {
+    // div with attributes and children
+    ( <div $($name:ident = { $($value:tt)* })* > { $($body:tt)* } </div> ) => {
         {
-            let mut el = $crate::declarative_ui::styled_div($styles);
-            let handler = $handler;
-            el = el.cursor_pointer().on_mouse_down(gpui::MouseButton::Left, move |_ev, _window, cx| {
-                handler();
-                cx.refresh_windows();
-            });
+            let mut el = $crate::jsx!(@div $($name = { $($value)* })*);
             $crate::jsx_children!(el, $($body)*);
             el
         }
     };
 
     // Self-closing div
-    ( <div class={ $styles:expr } /> ) => {
-        $crate::declarative_ui::styled_div($styles)
+    ( <div $($name:ident = { $($value:tt)* })* /> ) => {
+        $crate::jsx!(@div $($name = { $($value)* })*)
+    };
+
+    // Checkbox - attributes are Checkbox setters, in any order
+    ( <checkbox $($name:ident = { $value:expr })* /> ) => {
+        {
+            let checkbox = $crate::declarative_ui::Checkbox::default();
+            $( let checkbox = checkbox.$name($value); )*
+            checkbox
+        }
+    };
+
+    // Button - the id comes first, the other attributes are Button setters
+    ( <button id={ $id:expr } $($name:ident = { $value:expr })* > { $($body:tt)* } </button> ) => {
+        {
+            let el = $crate::declarative_ui::button($id);
+            $( let el = el.$name($value); )*
+            let mut el = el;
+            $crate::jsx_children!(el, $($body)*);
+            el
+        }
     };
 
     // Text element
@@ -221,15 +1333,68 @@ macro_rules! jsx {
         $content
     };
 
+    // Icon - class sets its size and color
+    ( <icon name={ $name:expr } /> ) => {
+        $crate::declarative_ui::icon($name, "")
+    };
+    ( <icon name={ $name:expr } class={ $styles:expr } /> ) => {
+        $crate::declarative_ui::icon($name, $styles)
+    };
+
     // List element
     ( <list id={ $id:expr } count={ $count:expr } class={ $styles:expr } render={ $renderer:expr } /> ) => {
-        $crate::declarative_ui::styled_list($id, $count, $styles, $renderer)
+        $crate::declarative_ui::styled_list_with(
+            $id,
+            $count,
+            $styles,
+            $renderer,
+            $crate::declarative_ui::ListOptions::default(),
+        )
     };
 }
 
 /// Helper macro to collect JSX children
 #[macro_export]
 macro_rules! jsx_children {
+    // for loop internals: collect the iterator tokens up to the body braces
+    (@for $el:ident, $item:pat, [$($iter:tt)*] { $($body:tt)* } $($rest:tt)* ) => {
+        for $item in $($iter)* {
+            $crate::jsx_children!($el, $($body)*);
+        }
+        $crate::jsx_children!($el, $($rest)*);
+    };
+    (@for $el:ident, $item:pat, [$($iter:tt)*] $next:tt $($rest:tt)* ) => {
+        $crate::jsx_children!(@for $el, $item, [$($iter)* $next] $($rest)*);
+    };
+
+    // if internals: collect the condition tokens up to the branch braces, then
+    // follow any else-if chain so the whole chain becomes one if expression
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } else if $($rest:tt)* ) => {
+        $crate::jsx_children!(
+            @if $el,
+            [$($done)* if $($cond)* { $crate::jsx_children!($el, $($then)*); } else],
+            []
+            $($rest)*
+        );
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } else { $($otherwise:tt)* } $($rest:tt)* ) => {
+        $($done)* if $($cond)* {
+            $crate::jsx_children!($el, $($then)*);
+        } else {
+            $crate::jsx_children!($el, $($otherwise)*);
+        }
+        $crate::jsx_children!($el, $($rest)*);
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] { $($then:tt)* } $($rest:tt)* ) => {
+        $($done)* if $($cond)* {
+            $crate::jsx_children!($el, $($then)*);
+        }
+        $crate::jsx_children!($el, $($rest)*);
+    };
+    (@if $el:ident, [$($done:tt)*], [$($cond:tt)*] $next:tt $($rest:tt)* ) => {
+        $crate::jsx_children!(@if $el, [$($done)*], [$($cond)* $next] $($rest)*);
+    };
+
     // Base case
     ($el:ident, ) => {};
 
@@ -239,27 +1404,805 @@ macro_rules! jsx_children {
         $crate::jsx_children!($el, $($rest)*);
     };
 
+    // Button
+    ($el:ident, <button id={ $id:expr } $($name:ident = { $value:expr })* > { $($body:tt)* } </button> $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <button id={ $id } $($name = { $value })* > { $($body)* } </button> });
+        $crate::jsx_children!($el, $($rest)*);
+    };
+
+    // Icon
+    ($el:ident, <icon name={ $name:expr } $(class={ $styles:expr })? /> $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <icon name={ $name } $(class={ $styles })? /> });
+        $crate::jsx_children!($el, $($rest)*);
+    };
+
+    // Checkbox
+    ($el:ident, <checkbox $($name:ident = { $value:expr })* /> $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <checkbox $($name = { $value })* /> });
+        $crate::jsx_children!($el, $($rest)*);
+    };
+
     // Self-closing list
     ($el:ident, <list id={ $id:expr } count={ $count:expr } class={ $styles:expr } render={ $renderer:expr } /> $($rest:tt)* ) => {
-        $el = $el.child($crate::declarative_ui::styled_list($id, $count, $styles, $renderer));
+        $el = $el.child($crate::declarative_ui::styled_list_with(
+            $id,
+            $count,
+            $styles,
+            $renderer,
+            $crate::declarative_ui::ListOptions::default(),
+        ));
         $crate::jsx_children!($el, $($rest)*);
     };
 
     // Self-closing div
-    ($el:ident, <div class={ $styles:expr } /> $($rest:tt)* ) => {
-        $el = $el.child($crate::declarative_ui::styled_div($styles));
+    ($el:ident, <div $($name:ident = { $($value:tt)* })* /> $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <div $($name = { $($value)* })* /> });
         $crate::jsx_children!($el, $($rest)*);
     };
 
     // div with children
-    ($el:ident, <div class={ $styles:expr }> { $($body:tt)* } </div> $($rest:tt)* ) => {
-        $el = $el.child($crate::jsx! { <div class={ $styles }> { $($body)* } </div> });
+    ($el:ident, <div $($name:ident = { $($value:tt)* })* > { $($body:tt)* } </div> $($rest:tt)* ) => {
+        $el = $el.child($crate::jsx! { <div $($name = { $($value)* })* > { $($body)* } </div> });
         $crate::jsx_children!($el, $($rest)*);
     };
 
-    // div with onclick and children
-    ($el:ident, <div class={ $styles:expr } onclick={ $handler:expr }> { $($body:tt)* } </div> $($rest:tt)* ) => {
-        $el = $el.child($crate::jsx! { <div class={ $styles } onclick={ $handler }> { $($body)* } </div> });
+    // for item in iter { children... }
+    ($el:ident, for $item:pat in $($rest:tt)* ) => {
+        $crate::jsx_children!(@for $el, $item, [] $($rest)*);
+    };
+
+    // if cond { children... } [else if ... ] [else { children... }] - `if let` works too
+    ($el:ident, if $($rest:tt)* ) => {
+        $crate::jsx_children!(@if $el, [], [] $($rest)*);
+    };
+
+    // maybe(option) - adds the element only when it is Some
+    ($el:ident, maybe ( $child:expr ) $($rest:tt)* ) => {
+        $el = $el.children($child);
         $crate::jsx_children!($el, $($rest)*);
     };
-}
\ No newline at end of file
+
+    // Arbitrary expression in parentheses ( expr )
+    ($el:ident, ( $child:expr ) $($rest:tt)* ) => {
+        $el = $el.child($child);
+        $crate::jsx_children!($el, $($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{
+        point, size, FlexWrap, Modifiers, MouseButton, MouseDownEvent, MouseUpEvent, ScrollDelta,
+        ScrollWheelEvent, TestAppContext, TouchPhase, VisualTestContext,
+    };
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    /// The palette is process-wide: tests that register one hold this meanwhile
+    static PALETTE_LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock_palette() -> std::sync::MutexGuard<'static, ()> {
+        PALETTE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// What `apply_styles` does to an element outside of hover
+    fn style(styles: &str) -> StyleRefinement {
+        classes(styles).fold(StyleRefinement::default(), apply_style)
+    }
+
+    /// What the macros' cached path does to an element outside of hover
+    fn static_style(styles: &'static str) -> StyleRefinement {
+        let mut el = apply_static_styles_at(div(), styles, file!(), line!());
+        el.style().clone()
+    }
+
+    /// Root view of a test window, showing whatever `build` returns
+    struct Harness(Box<dyn Fn() -> AnyElement>);
+
+    impl Render for Harness {
+        fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+            div().size_full().child((self.0)())
+        }
+    }
+
+    /// Open a window showing `build`, already laid out and painted
+    fn open(
+        cx: &mut TestAppContext,
+        build: impl Fn() -> AnyElement + 'static,
+    ) -> &mut VisualTestContext {
+        cx.add_window_view(|_, _| Harness(Box::new(build))).1
+    }
+
+    /// Click the middle of the element tagged `selector` with `debug_selector`
+    fn click(cx: &mut VisualTestContext, selector: &'static str) {
+        let bounds = cx.debug_bounds(selector).expect(selector);
+        cx.simulate_click(bounds.center(), Modifiers::none());
+    }
+
+    /// An empty child that records `label` in `log` when the macro adds it
+    fn mark(log: &RefCell<Vec<&'static str>>, label: &'static str) -> Div {
+        log.borrow_mut().push(label);
+        div()
+    }
+
+    #[test]
+    fn margin_classes_set_their_sides() {
+        assert_eq!(style("m-4"), StyleRefinement::default().m(px(4.0)));
+        assert_eq!(style("mx-4"), StyleRefinement::default().mx(px(4.0)));
+        assert_eq!(style("my-4"), StyleRefinement::default().my(px(4.0)));
+        assert_eq!(style("mt-4"), StyleRefinement::default().mt(px(4.0)));
+        assert_eq!(style("mb-4"), StyleRefinement::default().mb(px(4.0)));
+        assert_eq!(style("ml-4"), StyleRefinement::default().ml(px(4.0)));
+        assert_eq!(style("mr-4"), StyleRefinement::default().mr(px(4.0)));
+        assert_eq!(style("m-2.5"), StyleRefinement::default().m(px(2.5)));
+        // A later side overrides only that side
+        assert_eq!(
+            style("m-4 mt-0"),
+            StyleRefinement::default().m(px(4.0)).mt(px(0.0))
+        );
+    }
+
+    #[test]
+    fn unknown_margin_prefixes_fall_through() {
+        assert_eq!(
+            unknown_classes("mz-4 margin-2 m4"),
+            ["mz-4", "margin-2", "m4"]
+        );
+        assert_eq!(style("mz-4 margin-2 m4"), StyleRefinement::default());
+        // A known prefix with a bad value is dropped without touching the rest
+        assert_eq!(style("m-auto mt-2"), StyleRefinement::default().mt(px(2.0)));
+    }
+
+    #[test]
+    fn rounded_and_border_take_a_width_or_a_color() {
+        let blue = rgb(0x4a90e2);
+        assert_eq!(
+            style("rounded"),
+            StyleRefinement::default().rounded(px(4.0))
+        );
+        assert_eq!(
+            style("rounded-8"),
+            StyleRefinement::default().rounded(px(8.0))
+        );
+        assert_eq!(style("border"), StyleRefinement::default().border(px(1.0)));
+        assert_eq!(
+            style("border-2"),
+            StyleRefinement::default().border(px(2.0))
+        );
+        assert_eq!(
+            style("border-blue"),
+            StyleRefinement::default().border_color(blue)
+        );
+        assert_eq!(
+            style("border-[#f80]"),
+            StyleRefinement::default().border_color(rgb(0xff8800))
+        );
+        assert_eq!(
+            style("border border-blue"),
+            StyleRefinement::default()
+                .border(px(1.0))
+                .border_color(blue)
+        );
+        // Neither a number nor a color: flagged in debug builds
+        assert_eq!(
+            style("border-nope rounded-x"),
+            StyleRefinement::default().border_color(rgb(MISSING_COLOR))
+        );
+    }
+
+    #[gpui::test]
+    fn styled_elements_render(cx: &mut TestAppContext) {
+        let cx = open(cx, || {
+            ui! {
+                div["flex col size-full bg-dark p-4 gap-2"] {
+                    div["rounded border border-blue px-2 py-1 text-white text-sm"] { text["thin"] }
+                    div["rounded-8 border-2 border-[#f80] m-2 hover:bg-blue"] { text["thick"] }
+                }
+            }
+            .debug_selector(|| "root".into())
+            .into_any_element()
+        });
+        assert!(cx.debug_bounds("root").is_some());
+    }
+
+    #[test]
+    fn min_and_max_sizes_combine_with_width_and_height_in_any_order() {
+        assert_eq!(
+            style("min-w-100"),
+            StyleRefinement::default().min_w(px(100.0))
+        );
+        assert_eq!(
+            style("max-h-80"),
+            StyleRefinement::default().max_h(px(80.0))
+        );
+
+        let expected = StyleRefinement::default()
+            .w(px(200.0))
+            .min_w(px(100.0))
+            .max_w(px(300.0))
+            .h(px(40.0))
+            .min_h(px(20.0))
+            .max_h(px(80.0));
+        assert_eq!(
+            style("w-200 min-w-100 max-w-300 h-40 min-h-20 max-h-80"),
+            expected
+        );
+        assert_eq!(
+            style("max-h-80 min-h-20 h-40 max-w-300 min-w-100 w-200"),
+            expected
+        );
+    }
+
+    #[test]
+    fn flex_classes_reach_the_builder() {
+        assert_eq!(style("flex-wrap").flex_wrap, Some(FlexWrap::Wrap));
+        assert_eq!(
+            style("flex col"),
+            StyleRefinement::default().flex().flex_col()
+        );
+        assert_eq!(style("flex-row"), StyleRefinement::default().flex_row());
+        assert_eq!(style("row"), style("flex-row"));
+        assert_eq!(style("flex-1"), StyleRefinement::default().flex_1());
+        assert_eq!(style("flex-grow"), StyleRefinement::default().flex_grow());
+        assert_eq!(
+            style("flex-shrink-0"),
+            StyleRefinement::default().flex_shrink_0()
+        );
+        assert_eq!(
+            style("justify-between items-center"),
+            StyleRefinement::default().justify_between().items_center()
+        );
+        assert_eq!(
+            style("items-stretch").align_items,
+            Some(AlignItems::Stretch)
+        );
+    }
+
+    #[test]
+    fn opacity_is_a_percentage_clamped_to_0_100() {
+        assert_eq!(style("opacity-50").opacity, Some(0.5));
+        assert_eq!(style("opacity-0").opacity, Some(0.0));
+        assert_eq!(style("opacity-100").opacity, Some(1.0));
+        assert_eq!(style("opacity-150").opacity, Some(1.0));
+        assert_eq!(style("opacity--20").opacity, Some(0.0));
+        assert_eq!(style("opacity-half").opacity, None);
+    }
+
+    #[test]
+    fn bracketed_colors_accept_hex_and_rgb() {
+        let orange = Some(rgb(0xff8800));
+        assert_eq!(color_value("[#f80]"), orange);
+        assert_eq!(color_value("[#ff8800]"), orange);
+        assert_eq!(color_value("[#FF8800]"), orange);
+        assert_eq!(color_value("[rgb(255,136,0)]"), orange);
+        assert_eq!(color_value("[rgb( 255 , 136 , 0 )]"), orange);
+        assert_eq!(
+            style("bg-[rgb(255, 136, 0)]"),
+            StyleRefinement::default().bg(rgb(0xff8800))
+        );
+        assert_eq!(
+            style("text-[#f80]"),
+            StyleRefinement::default().text_color(rgb(0xff8800))
+        );
+    }
+
+    #[test]
+    fn malformed_colors_are_rejected() {
+        let malformed = [
+            "[#f8]",
+            "[#ff88]",
+            "[#ff88000]",
+            "[#g80]",
+            "[f80]",
+            "[#]",
+            "[]",
+            "[#f80",
+            "[rgb(256, 0, 0)]",
+            "[rgb(-1, 0, 0)]",
+            "[rgb(1, 2)]",
+            "[rgb(1, 2, 3, 4)]",
+            "[rgb()]",
+            "[rgb(a, b, c)]",
+            "[rgba(1, 2, 3)]",
+            "[rgb(1, 2, 3]",
+        ];
+        for value in malformed {
+            assert_eq!(color_value(value), None, "{}", value);
+        }
+        assert_eq!(
+            unknown_classes("bg-[#ff88] text-[rgb(1, 2)]"),
+            ["bg-[#ff88]", "text-[rgb(1, 2)]"]
+        );
+    }
+
+    #[test]
+    fn if_without_else_adds_its_children_only_when_taken() {
+        let log = RefCell::new(Vec::new());
+        for shown in [true, false] {
+            let _ = ui! {
+                div["flex"] {
+                    if shown { (mark(&log, "then")) }
+                    (mark(&log, "after"))
+                }
+            };
+            let _ = jsx! {
+                <div class={"flex"}> {
+                    if shown { (mark(&log, "jsx then")) }
+                    (mark(&log, "jsx after"))
+                } </div>
+            };
+        }
+        assert_eq!(
+            *log.borrow(),
+            [
+                "then",
+                "after",
+                "jsx then",
+                "jsx after",
+                "after",
+                "jsx after"
+            ]
+        );
+    }
+
+    #[test]
+    fn else_branches_pick_exactly_one_arm() {
+        let log = RefCell::new(Vec::new());
+        for n in [0, 1, 2] {
+            let _ = ui! {
+                div["flex"] {
+                    if n == 0 {
+                        (mark(&log, "zero"))
+                    } else if n == 1 {
+                        (mark(&log, "one"))
+                    } else {
+                        (mark(&log, "many"))
+                    }
+                }
+            };
+        }
+        for name in [Some("eth0"), None] {
+            let _ = jsx! {
+                <div class={"flex"}> {
+                    if let Some(_name) = name { (mark(&log, "named")) } else { (mark(&log, "unnamed")) }
+                } </div>
+            };
+        }
+        assert_eq!(*log.borrow(), ["zero", "one", "many", "named", "unnamed"]);
+    }
+
+    #[test]
+    fn conditions_nest_inside_loops() {
+        let log = RefCell::new(Vec::new());
+        let _ = ui! {
+            div["flex col"] {
+                for n in 1..=4 {
+                    if n % 2 == 0 { (mark(&log, "even")) } else { (mark(&log, "odd")) }
+                    if n == 4 { (mark(&log, "last")) }
+                }
+            }
+        };
+        let _ = jsx! {
+            <div class={"flex col"}> {
+                for n in 1..=2 {
+                    if n == 1 { (mark(&log, "first")) } else { (mark(&log, "second")) }
+                }
+            } </div>
+        };
+        assert_eq!(
+            *log.borrow(),
+            ["odd", "even", "odd", "even", "last", "first", "second"]
+        );
+    }
+
+    #[gpui::test]
+    fn each_event_handler_fires_once_per_event(cx: &mut TestAppContext) {
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let log = fired.clone();
+        let cx = open(cx, move || {
+            let (click, double, up) = (log.clone(), log.clone(), log.clone());
+            let (hover, scroll) = (log.clone(), log.clone());
+            let target = ElementId::from("target");
+            jsx! {
+                <div key={"target"} class={"size-100"}
+                    on_click={ move |ev: &ClickEvent, _, _| {
+                        assert_eq!(ev.element_id, Some(target.clone()));
+                        click.borrow_mut().push("click");
+                    } }
+                    on_double_click={ move |_, _, _| double.borrow_mut().push("double") }
+                    on_mouse_up={ move |_, _, _| up.borrow_mut().push("up") }
+                    on_hover={ move |ev: &HoverEvent, _, _| {
+                        hover.borrow_mut().push(if ev.hovered { "enter" } else { "leave" })
+                    } }
+                    on_scroll={ move |ev: &ScrollEvent, _, _| {
+                        assert_eq!(ev.delta, point(px(0.0), px(-30.0)));
+                        scroll.borrow_mut().push("scroll");
+                    } }
+                />
+            }
+            .debug_selector(|| "target".into())
+            .into_any_element()
+        });
+
+        let target = cx.debug_bounds("target").unwrap();
+        let center = target.center();
+        // Handlers for the same event may run in any order
+        let take = || {
+            let mut fired = fired.take();
+            fired.sort();
+            fired
+        };
+
+        cx.simulate_mouse_move(center, None, Modifiers::none());
+        assert_eq!(take(), ["enter"]);
+        cx.simulate_click(center, Modifiers::none());
+        assert_eq!(take(), ["click", "up"]);
+        // The second mouse-down of a double click is a click too
+        cx.simulate_event(MouseDownEvent {
+            position: center,
+            modifiers: Modifiers::none(),
+            button: MouseButton::Left,
+            click_count: 2,
+            first_mouse: false,
+        });
+        cx.simulate_event(MouseUpEvent {
+            position: center,
+            modifiers: Modifiers::none(),
+            button: MouseButton::Left,
+            click_count: 2,
+        });
+        assert_eq!(take(), ["click", "double", "up"]);
+        cx.simulate_event(ScrollWheelEvent {
+            position: center,
+            delta: ScrollDelta::Pixels(point(px(0.0), px(-30.0))),
+            modifiers: Modifiers::none(),
+            touch_phase: TouchPhase::Moved,
+        });
+        assert_eq!(take(), ["scroll"]);
+        let outside = target.bottom_right() + point(px(10.0), px(10.0));
+        cx.simulate_mouse_move(outside, None, Modifiers::none());
+        assert_eq!(take(), ["leave"]);
+    }
+
+    #[gpui::test]
+    fn checkbox_toggle_receives_the_flipped_value(cx: &mut TestAppContext) {
+        for checked in [false, true] {
+            let received = Rc::new(RefCell::new(Vec::new()));
+            let log = received.clone();
+            let cx = open(cx, move || {
+                let log = log.clone();
+                let el = checkbox(checked, "eth0")
+                    .on_toggle(move |value, _, _| log.borrow_mut().push(*value));
+                assert_eq!(el.is_checked(), checked);
+                el.into_element()
+                    .debug_selector(|| "checkbox".into())
+                    .into_any_element()
+            });
+            click(cx, "checkbox");
+            assert_eq!(*received.borrow(), [!checked]);
+        }
+    }
+
+    #[gpui::test]
+    fn disabled_checkbox_ignores_clicks(cx: &mut TestAppContext) {
+        let toggles = Rc::new(Cell::new(0));
+        let count = toggles.clone();
+        let cx = open(cx, move || {
+            let count = count.clone();
+            checkbox(false, "eth0")
+                .disabled(true)
+                .on_toggle(move |_, _, _| count.set(count.get() + 1))
+                .into_element()
+                .debug_selector(|| "checkbox".into())
+                .into_any_element()
+        });
+        click(cx, "checkbox");
+        assert_eq!(toggles.get(), 0);
+    }
+
+    #[gpui::test]
+    fn disabled_button_never_calls_on_click(cx: &mut TestAppContext) {
+        for disabled in [false, true] {
+            let clicks = Rc::new(Cell::new(0));
+            let count = clicks.clone();
+            let cx = open(cx, move || {
+                let count = count.clone();
+                button("save")
+                    .disabled(disabled)
+                    .on_click(move |_, _, _| count.set(count.get() + 1))
+                    .child("Save")
+                    .into_element()
+                    .debug_selector(|| "button".into())
+                    .into_any_element()
+            });
+            click(cx, "button");
+            assert_eq!(clicks.get(), if disabled { 0 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn button_variants_select_their_own_styles() {
+        let _palette = lock_palette();
+        set_palette(HashMap::from([
+            ("surface", rgb(0x333333)),
+            ("surface-alt", rgb(0x444444)),
+            ("primary", rgb(0xffffff)),
+            ("accent", rgb(0x4a90e2)),
+        ]));
+        let background = |variant| {
+            button("b")
+                .variant(variant)
+                .into_element()
+                .style()
+                .background
+                .clone()
+        };
+
+        assert_eq!(ButtonVariant::default(), ButtonVariant::Primary);
+        assert_eq!(
+            background(ButtonVariant::Primary),
+            Some(rgb(0x4a90e2).into())
+        );
+        assert_eq!(
+            background(ButtonVariant::Secondary),
+            Some(rgb(0x333333).into())
+        );
+        assert_eq!(background(ButtonVariant::Ghost), None);
+
+        for variant in [
+            ButtonVariant::Primary,
+            ButtonVariant::Secondary,
+            ButtonVariant::Ghost,
+        ] {
+            let (base, hover, active) = variant.styles();
+            for styles in [base, hover, active] {
+                assert!(
+                    unknown_classes(styles).is_empty(),
+                    "{:?}: {}",
+                    variant,
+                    styles
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_classes_lists_the_typos_in_order() {
+        assert_eq!(
+            unknown_classes("flex flex-coll bg-drak p-4 hover:text-whte"),
+            ["flex-coll", "bg-drak", "hover:text-whte"]
+        );
+        assert!(unknown_classes("").is_empty());
+    }
+
+    #[test]
+    fn valid_classes_are_never_reported() {
+        let valid = r#"
+            flex flex-col col flex-row row flex-grow flex-1 flex-shrink-0 flex-wrap
+            justify-start justify-center justify-end justify-between justify-around
+            items-start items-center items-end items-stretch
+            font-bold bold font-mono font-["JetBrains Mono"] italic
+            cursor-pointer size-full relative absolute
+            overflow-hidden overflow-x-hidden overflow-y-hidden
+            rounded rounded-4 border border-2 border-blue border-[#f80]
+            bg-dark bg-[rgb(255, 136, 0)]
+            text-white text-gray text-dim text-blue text-red text-[#abc]
+            text-xs text-sm text-lg text-xl text-2xl text-14
+            text-left text-center text-right truncate whitespace-nowrap
+            gap-2 p-4 px-4 py-4 pt-4 pb-4 pl-4 pr-4 m-4 mx-4 my-4 mt-4 mb-4 ml-4 mr-4
+            opacity-50 w-10 h-10 min-w-10 max-w-10 min-h-10 max-h-10 size-16
+            inset-0 top-2 right-2 bottom-2 left-2
+            hover:bg-blue hover:opacity-90
+        "#;
+        assert_eq!(unknown_classes(valid), Vec::<&str>::new());
+    }
+
+    #[gpui::test]
+    fn rerendering_a_list_does_not_hold_on_to_its_id(cx: &mut TestAppContext) {
+        let id: Arc<str> = Arc::from(format!("interfaces-{}", 1));
+        let renders = Rc::new(Cell::new(0));
+        let (list_id, count) = (id.clone(), renders.clone());
+        let (view, cx) = cx.add_window_view(move |_, _| {
+            Harness(Box::new(move || {
+                count.set(count.get() + 1);
+                let render = |ix| div().child(format!("row {}", ix)).into_any_element();
+                jsx! {
+                    <list
+                        id={SharedString::new(list_id.clone())}
+                        count={3}
+                        class={"size-full"}
+                        render={render}
+                    />
+                }
+            }))
+        });
+
+        let after_first = Arc::strong_count(&id);
+        for _ in 0..1000 {
+            view.update(cx, |_, cx| cx.notify());
+        }
+        assert!(renders.get() > 1000);
+        assert!(Arc::strong_count(&id) <= after_first);
+    }
+
+    #[test]
+    fn positioning_classes_set_position_and_offsets() {
+        assert_eq!(
+            style("absolute top-2 right-4 bottom-6 left-8"),
+            StyleRefinement::default()
+                .absolute()
+                .top(px(2.0))
+                .right(px(4.0))
+                .bottom(px(6.0))
+                .left(px(8.0))
+        );
+        assert_eq!(
+            style("relative inset-0"),
+            StyleRefinement::default().relative().inset(px(0.0))
+        );
+        // gpui has no z-index
+        assert_eq!(unknown_classes("z-10 top-2"), ["z-10"]);
+    }
+
+    #[gpui::test]
+    fn overlay_floats_inside_its_relative_parent(cx: &mut TestAppContext) {
+        let cx = open(cx, || {
+            overlay(
+                styled_div("size-200"),
+                styled_div("size-20").debug_selector(|| "floating".into()),
+                "bottom-4 right-4",
+            )
+            .debug_selector(|| "overlay".into())
+            .into_any_element()
+        });
+        let parent = cx.debug_bounds("overlay").unwrap();
+        let floating = cx.debug_bounds("floating").unwrap();
+        assert_eq!(floating.size, size(px(20.0), px(20.0)));
+        assert_eq!(
+            floating.bottom_right(),
+            parent.bottom_right() - point(px(4.0), px(4.0))
+        );
+    }
+
+    #[test]
+    fn font_classes_set_weight_and_family() {
+        assert_eq!(
+            style("font-bold"),
+            StyleRefinement::default().font_weight(FontWeight::BOLD)
+        );
+        assert_eq!(style("bold"), style("font-bold"));
+        assert_eq!(style("italic"), StyleRefinement::default().italic());
+        assert_eq!(
+            style("font-mono"),
+            StyleRefinement::default().font_family(MONO_FONT)
+        );
+
+        let jetbrains = StyleRefinement::default().font_family("JetBrains Mono");
+        assert_eq!(style(r#"font-["JetBrains Mono"]"#), jetbrains);
+        assert_eq!(style("font-['JetBrains Mono']"), jetbrains);
+        assert_eq!(
+            style("font-[Inter]"),
+            StyleRefinement::default().font_family("Inter")
+        );
+        // The family's space doesn't split the class list
+        assert_eq!(
+            style(r#"bold font-["JetBrains Mono"] italic"#),
+            StyleRefinement::default()
+                .font_weight(FontWeight::BOLD)
+                .font_family("JetBrains Mono")
+                .italic()
+        );
+        assert_eq!(
+            unknown_classes(r#"font-[] font-[""] font-["  "] font-serif"#),
+            ["font-[]", r#"font-[""]"#, r#"font-["  "]"#, "font-serif"]
+        );
+    }
+
+    /// gpui's hover delay before a tooltip shows, which it doesn't export
+    const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+
+    #[gpui::test]
+    fn tooltip_shows_its_text_once_the_hover_delay_passes(cx: &mut TestAppContext) {
+        let cx = open(cx, || {
+            let name = styled_div("size-40")
+                .id("name")
+                .debug_selector(|| "name".into());
+            tooltip(name, "enp3s0f0u2u1").into_any_element()
+        });
+        let name = cx.debug_bounds("name").unwrap();
+        cx.simulate_mouse_move(name.center(), None, Modifiers::none());
+
+        cx.executor()
+            .advance_clock(TOOLTIP_DELAY - Duration::from_millis(100));
+        cx.run_until_parked();
+        assert!(cx.debug_bounds("tooltip: enp3s0f0u2u1").is_none());
+
+        cx.executor().advance_clock(Duration::from_millis(100));
+        cx.run_until_parked();
+        assert!(cx.debug_bounds("tooltip: enp3s0f0u2u1").is_some());
+    }
+
+    #[test]
+    fn cached_styles_match_a_fresh_parse() {
+        let styles = "flex col gap-4 p-2 bg-blue text-white rounded-8 border-[#f80] hover:bg-gray";
+        let fresh = style(styles);
+        assert_eq!(static_style(styles), fresh);
+
+        let cached = PARSED_STYLES.with(|cache| cache.borrow().1.get(styles).cloned());
+        let cached = cached.expect("parsed styles are cached");
+        assert_eq!(cached.hover, Some(style("bg-gray")));
+        // Served from the cache this time
+        assert_eq!(static_style(styles), fresh);
+    }
+
+    #[test]
+    fn cached_styles_follow_a_palette_switch() {
+        let _palette = lock_palette();
+        set_palette(HashMap::from([("cache-test", rgb(0x112233))]));
+        assert_eq!(
+            static_style("bg-cache-test").background,
+            Some(rgb(0x112233).into())
+        );
+
+        set_palette(HashMap::from([("cache-test", rgb(0x445566))]));
+        assert_eq!(
+            static_style("bg-cache-test").background,
+            Some(rgb(0x445566).into())
+        );
+    }
+
+    #[test]
+    fn registered_colors_resolve_by_name() {
+        let _palette = lock_palette();
+        set_palette(HashMap::from([("brand", rgb(0x123456))]));
+        assert_eq!(palette_color("brand"), Some(rgb(0x123456)));
+        assert_eq!(
+            style("bg-brand text-brand"),
+            StyleRefinement::default()
+                .bg(rgb(0x123456))
+                .text_color(rgb(0x123456))
+        );
+    }
+
+    #[test]
+    fn built_in_colors_remain_behind_a_palette() {
+        let _palette = lock_palette();
+        set_palette(HashMap::from([("brand", rgb(0x123456))]));
+        assert_eq!(palette_color("blue"), Some(rgb(0x4a90e2)));
+        assert_eq!(style("bg-wan"), StyleRefinement::default().bg(rgb(0x2d5a3d)));
+
+        // Registered names take precedence
+        set_palette(HashMap::from([("footer", rgb(0x0000ff))]));
+        assert_eq!(palette_color("footer"), Some(rgb(0x0000ff)));
+    }
+
+    #[test]
+    fn unknown_color_names_show_up_in_debug_builds() {
+        let magenta = rgb(MISSING_COLOR);
+        assert_eq!(palette_color("nope"), None);
+        assert_eq!(style("bg-nope"), StyleRefinement::default().bg(magenta));
+        assert_eq!(
+            style("text-nope"),
+            StyleRefinement::default().text_color(magenta)
+        );
+        assert_eq!(unknown_classes("bg-nope text-nope"), ["bg-nope", "text-nope"]);
+        // Malformed values are warned about instead
+        assert_eq!(style("bg-[#ff88]"), StyleRefinement::default());
+    }
+
+    #[test]
+    fn registering_again_replaces_the_whole_palette() {
+        let _palette = lock_palette();
+        set_palette(HashMap::from([
+            ("surface", rgb(0x111111)),
+            ("light-only", rgb(0xeeeeee)),
+        ]));
+        assert_eq!(palette_color("surface"), Some(rgb(0x111111)));
+
+        set_palette(HashMap::from([("surface", rgb(0x222222))]));
+        assert_eq!(palette_color("surface"), Some(rgb(0x222222)));
+        assert_eq!(palette_color("light-only"), None);
+        assert_eq!(unknown_classes("bg-surface bg-light-only"), ["bg-light-only"]);
+    }
+}
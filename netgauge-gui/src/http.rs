@@ -0,0 +1,163 @@
+//! Minimal HTTP status endpoint (enabled with the `http` feature)
+//!
+//! Serves the latest `NetGaugeState` snapshot so other screens can poll it:
+//! - `GET /stats`   - JSON
+//! - `GET /metrics` - Prometheus text format
+
+use crate::{InterfaceMetric, NetGaugeState};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// Snapshot shared between the poll loop (writer) and the server thread (reader)
+pub type SharedSnapshot = Arc<RwLock<NetGaugeState>>;
+
+/// Bind `addr` and serve snapshots on a background thread
+/// Returns the snapshot handle the poll loop should keep up to date
+pub fn serve(addr: &str, initial: NetGaugeState) -> std::io::Result<SharedSnapshot> {
+    let listener = TcpListener::bind(addr)?;
+    let snapshot = Arc::new(RwLock::new(initial));
+
+    let shared = snapshot.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // One bad client must not take the endpoint down
+            if let Err(e) = handle(stream, &shared) {
+                eprintln!("HTTP status request failed: {}", e);
+            }
+        }
+    });
+
+    Ok(snapshot)
+}
+
+fn handle(mut stream: TcpStream, snapshot: &SharedSnapshot) -> std::io::Result<()> {
+    // example: "GET /stats HTTP/1.1"
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let (status, content_type, body) = {
+        let state = snapshot.read().unwrap();
+        match (method, path) {
+            ("GET", "/stats") => ("200 OK", "application/json", to_json(&state)),
+            ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4", to_prometheus(&state)),
+            _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        }
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+// ============================================================================
+// JSON
+// ============================================================================
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_option<T>(value: Option<T>, f: impl FnOnce(T) -> String) -> String {
+    value.map(f).unwrap_or_else(|| "null".to_string())
+}
+
+fn interface_json(m: &InterfaceMetric) -> String {
+    format!(
+        "{{\"name\":{},\"rx_bytes_per_sec\":{},\"tx_bytes_per_sec\":{},\"rx\":{},\"tx\":{},\"is_wan\":{},\"is_up\":{},\"link_speed_bps\":{}}}",
+        json_string(&m.name),
+        m.rx_rate,
+        m.tx_rate,
+        json_string(&m.rx_speed),
+        json_string(&m.tx_speed),
+        m.is_wan,
+        json_option(m.is_up, |up| up.to_string()),
+        json_option(m.link_speed_bps, |bps| bps.to_string()),
+    )
+}
+
+fn to_json(state: &NetGaugeState) -> String {
+    let interfaces: Vec<String> = state
+        .interfaces
+        .iter()
+        .filter(|m| !m.is_skeleton)
+        .map(interface_json)
+        .collect();
+
+    format!(
+        "{{\"snmp_available\":{},\"poll_error\":{},\"default_route\":{},\"interfaces\":[{}]}}\n",
+        state.snmp_available,
+        json_option(state.poll_error.as_deref(), json_string),
+        json_option(state.default_route.as_deref(), json_string),
+        interfaces.join(",")
+    )
+}
+
+// ============================================================================
+// Prometheus
+// ============================================================================
+
+fn label_value(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Metric name, HELP text and the per-interface value of a gauge
+type Gauge = (&'static str, &'static str, fn(&InterfaceMetric) -> u64);
+
+fn to_prometheus(state: &NetGaugeState) -> String {
+    let mut out = String::new();
+    let interfaces: Vec<&InterfaceMetric> =
+        state.interfaces.iter().filter(|m| !m.is_skeleton).collect();
+
+    let gauges: [Gauge; 2] = [
+        ("netgauge_rx_bytes_per_second", "Receive rate", |m| m.rx_rate),
+        ("netgauge_tx_bytes_per_second", "Transmit rate", |m| m.tx_rate),
+    ];
+
+    for (name, help, value) in gauges {
+        out.push_str(&format!("# HELP {} {} in bytes per second\n", name, help));
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for m in &interfaces {
+            let kind = if m.is_wan { "wan" } else { "net" };
+            out.push_str(&format!(
+                "{}{{interface=\"{}\",kind=\"{}\"}} {}\n",
+                name,
+                label_value(&m.name),
+                kind,
+                value(m)
+            ));
+        }
+    }
+
+    out.push_str("# HELP netgauge_snmp_available Whether the router answers SNMP\n");
+    out.push_str("# TYPE netgauge_snmp_available gauge\n");
+    out.push_str(&format!(
+        "netgauge_snmp_available {}\n",
+        state.snmp_available as u8
+    ));
+
+    out
+}
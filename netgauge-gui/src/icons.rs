@@ -0,0 +1,196 @@
+//! Built-in vector icons
+//!
+//! Small 16×16 SVGs embedded in the binary and served to gpui as assets under
+//! `icons/<name>.svg`. gpui paints an SVG as a mask in the element's text color,
+//! so the stroke/fill colors below don't matter - icons follow the theme.
+
+use gpui::{AssetSource, Result, SharedString};
+use std::borrow::Cow;
+
+const ASSET_PREFIX: &str = "icons/";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    Gear,
+    Globe,
+    ArrowUp,
+    ArrowDown,
+    Pin,
+    Pause,
+    Play,
+    Warning,
+    Check,
+    X,
+    Wifi,
+    Link,
+    Lock,
+}
+
+impl Icon {
+    pub const ALL: [Icon; 13] = [
+        Icon::Gear,
+        Icon::Globe,
+        Icon::ArrowUp,
+        Icon::ArrowDown,
+        Icon::Pin,
+        Icon::Pause,
+        Icon::Play,
+        Icon::Warning,
+        Icon::Check,
+        Icon::X,
+        Icon::Wifi,
+        Icon::Link,
+        Icon::Lock,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Icon::Gear => "gear",
+            Icon::Globe => "globe",
+            Icon::ArrowUp => "arrow-up",
+            Icon::ArrowDown => "arrow-down",
+            Icon::Pin => "pin",
+            Icon::Pause => "pause",
+            Icon::Play => "play",
+            Icon::Warning => "warning",
+            Icon::Check => "check",
+            Icon::X => "x",
+            Icon::Wifi => "wifi",
+            Icon::Link => "link",
+            Icon::Lock => "lock",
+        }
+    }
+
+    /// Asset path for `gpui::svg().path(..)`
+    pub fn path(self) -> SharedString {
+        format!("{}{}.svg", ASSET_PREFIX, self.name()).into()
+    }
+
+    /// SVG body, drawn in a 16×16 view box
+    fn shapes(self) -> &'static str {
+        match self {
+            Icon::Gear => {
+                r#"<circle cx="8" cy="8" r="4.5"/><circle cx="8" cy="8" r="1.75"/>
+                <path d="M8 1v2M8 13v2M1 8h2M13 8h2M3.05 3.05l1.4 1.4M11.55 11.55l1.4 1.4M3.05 12.95l1.4-1.4M11.55 4.45l1.4-1.4"/>"#
+            }
+            Icon::Globe => {
+                r#"<circle cx="8" cy="8" r="6.5"/><ellipse cx="8" cy="8" rx="2.75" ry="6.5"/>
+                <path d="M1.5 8h13M2.5 4.75h11M2.5 11.25h11"/>"#
+            }
+            Icon::ArrowUp => r#"<path d="M8 13.5V2.5M3.5 7L8 2.5 12.5 7"/>"#,
+            Icon::ArrowDown => r#"<path d="M8 2.5v11M3.5 9L8 13.5 12.5 9"/>"#,
+            Icon::Pin => {
+                r#"<path d="M8 14.5S3.5 10.1 3.5 6.5a4.5 4.5 0 0 1 9 0c0 3.6-4.5 8-4.5 8z"/>
+                <circle cx="8" cy="6.5" r="1.5"/>"#
+            }
+            Icon::Pause => {
+                r##"<rect x="3.5" y="2.5" width="3" height="11" rx="1" fill="#000" stroke="none"/>
+                <rect x="9.5" y="2.5" width="3" height="11" rx="1" fill="#000" stroke="none"/>"##
+            }
+            Icon::Play => r##"<path d="M4.5 2.5l9 5.5-9 5.5z" fill="#000"/>"##,
+            Icon::Warning => {
+                r##"<path d="M8 1.75L15 14.25H1z"/><path d="M8 6v4"/>
+                <circle cx="8" cy="12" r="0.75" fill="#000" stroke="none"/>"##
+            }
+            Icon::Check => r#"<path d="M2.5 8.5l3.5 3.5 7.5-7.5"/>"#,
+            Icon::X => r#"<path d="M3.5 3.5l9 9M12.5 3.5l-9 9"/>"#,
+            Icon::Wifi => {
+                r##"<path d="M1.5 6.25a9.25 9.25 0 0 1 13 0M3.75 8.75a6 6 0 0 1 8.5 0M6 11.25a2.85 2.85 0 0 1 4 0"/>
+                <circle cx="8" cy="13.25" r="0.75" fill="#000" stroke="none"/>"##
+            }
+            Icon::Link => {
+                r#"<path d="M6.75 9.25a2.75 2.75 0 0 0 3.9 0l2.6-2.6a2.75 2.75 0 0 0-3.9-3.9l-1 1"/>
+                <path d="M9.25 6.75a2.75 2.75 0 0 0-3.9 0l-2.6 2.6a2.75 2.75 0 0 0 3.9 3.9l1-1"/>"#
+            }
+            Icon::Lock => {
+                r#"<rect x="3" y="7" width="10" height="7.5" rx="1.5"/>
+                <path d="M5.25 7V5a2.75 2.75 0 0 1 5.5 0v2"/>"#
+            }
+        }
+    }
+
+    /// The complete SVG document
+    pub fn svg(self) -> String {
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 16 16" fill="none" stroke="#000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round">{}</svg>"##,
+            self.shapes()
+        )
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        let name = path.strip_prefix(ASSET_PREFIX)?.strip_suffix(".svg")?;
+        Self::ALL.into_iter().find(|icon| icon.name() == name)
+    }
+}
+
+/// Asset source for `Application::with_assets`, serving the icons above
+pub struct Assets;
+
+impl AssetSource for Assets {
+    fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
+        Ok(Icon::from_path(path).map(|icon| Cow::Owned(icon.svg().into_bytes())))
+    }
+
+    fn list(&self, path: &str) -> Result<Vec<SharedString>> {
+        Ok(Icon::ALL
+            .into_iter()
+            .map(|icon| icon.path())
+            .filter(|asset| asset.starts_with(path))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::declarative_ui::icon;
+    use gpui::{div, prelude::*, px, size, Context, TestAppContext, Window};
+
+    #[test]
+    fn every_icon_has_path_data() {
+        for icon in Icon::ALL {
+            let shapes = icon.shapes();
+            let drawn = ["<path d=\"", "<circle ", "<rect ", "<ellipse "];
+            assert!(drawn.iter().any(|tag| shapes.contains(tag)), "{:?}", icon);
+            assert!(!shapes.contains("d=\"\""), "{:?} has an empty path", icon);
+
+            let served = Assets.load(&icon.path()).unwrap();
+            assert_eq!(served.as_deref(), Some(icon.svg().as_bytes()), "{:?}", icon);
+            assert_eq!(Icon::from_path(&icon.path()), Some(icon));
+        }
+    }
+
+    #[test]
+    fn assets_list_each_icon_once() {
+        let mut names: Vec<_> = Icon::ALL.iter().map(|icon| icon.name()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), Icon::ALL.len());
+        assert_eq!(Assets.list(ASSET_PREFIX).unwrap().len(), Icon::ALL.len());
+        assert_eq!(Assets.load("icons/nope.svg").unwrap(), None);
+    }
+
+    struct AllIcons;
+
+    impl Render for AllIcons {
+        fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+            div().flex().children(
+                Icon::ALL
+                    .map(|i| icon(i, "size-24 text-blue").debug_selector(|| i.name().to_string())),
+            )
+        }
+    }
+
+    // The test app has no asset source, so this covers layout and painting up to
+    // the SVG lookup
+    #[gpui::test]
+    fn every_icon_renders(cx: &mut TestAppContext) {
+        let (_view, cx) = cx.add_window_view(|_, _| AllIcons);
+        for i in Icon::ALL {
+            let bounds = cx
+                .debug_bounds(i.name())
+                .unwrap_or_else(|| panic!("{:?} wasn't drawn", i));
+            assert_eq!(bounds.size, size(px(24.0), px(24.0)), "{:?}", i);
+        }
+    }
+}
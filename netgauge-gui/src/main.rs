@@ -1,134 +1,1709 @@
 #[macro_use]
 mod declarative_ui;
+mod accent;
+mod autostart;
+mod config;
+#[cfg(feature = "http")]
+mod http;
+mod icons;
 
-use declarative_ui::styled_div;
+use config::{Config, SnmpInterface, SnmpTarget, SnmpV3Credentials, SortMode, SparklineScale};
+use declarative_ui::{
+    background_color, button, checkbox, checkbox_list, icon, overlay, popup_menu, scrollable_div,
+    styled_div, tooltip, ButtonVariant, Checkbox, ListOptions, MenuItem, Selection,
+};
 use gpui::{
-    prelude::*, px, size, App, Application, AnyElement, AsyncApp, Bounds,
-    Context, Global, Timer, Window, WindowBounds, WindowOptions,
+    prelude::*, pulsating_between, px, relative, rgb, size, Animation, AnimationExt, AnyElement,
+    App, Application, AsyncApp, Bounds, ClipboardItem, Context, ElementId, FocusHandle, Global,
+    Hsla, KeyDownEvent, MouseButton, MouseDownEvent, Pixels, Point, Rgba, SharedString, Stateful,
+    Timer, Window, WindowBackgroundAppearance, WindowBounds, WindowOptions,
 };
+use icons::Icon;
 use netgauge::{
-    detect_interface_index, fetch_net_stats, fetch_wan_stats, format, is_snmp_available,
-    list_interfaces, DeltaTracker, InterfaceSet, InterfaceType,
+    default_route_interface, fetch_net_stats, format, list_interesting_interfaces, list_interfaces,
+    physical_interfaces, tcp_connect_rtt, DeltaTracker, FirstSample, InterfaceSet, InterfaceStats,
+    InterfaceType, NetDelta, SnmpAuth, SnmpClient, SnmpConfig, WanCounterSource, WanInfo,
+};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// SNMP Configuration (customize for your router)
+// ============================================================================
+
+// Used when the config file has no `snmp.<label>` targets
+const SNMP_TARGET: &str = "192.168.1.1:161";
+const SNMP_COMMUNITY: &[u8] = b"public";
+const SNMP_IF_PATTERN: &str = "ppp"; // Pattern to search for WAN interface (e.g., "ppp", "wan")
+const SNMP_HC_COUNTERS: bool = true; // 64-bit counters when available; false = always 32-bit
+
+// Applies to every target
+const SNMP_TIMEOUT: Duration = Duration::from_secs(2); // Per request
+const SNMP_RETRIES: u32 = 1; // Resend an unanswered request before the card shows "--"
+
+// SNMPv3, used instead of SNMP_COMMUNITY when SNMP_V3_USER is set (needs the `snmpv3` feature)
+const SNMP_V3_USER: Option<&str> = None; // e.g. Some("netgauge")
+const SNMP_V3_AUTH_PROTOCOL: &str = "SHA"; // MD5, SHA, SHA-224, SHA-256, SHA-384, SHA-512
+const SNMP_V3_AUTH_PASSWORD: &str = "";
+const SNMP_V3_PRIV_PROTOCOL: &str = "AES"; // DES, AES, AES-192, AES-256
+const SNMP_V3_PRIV_PASSWORD: Option<&str> = None; // None = authNoPriv
+
+// ============================================================================
+// Latency Probe Configuration
+// ============================================================================
+
+const LATENCY_PROBE_TARGET: &str = "1.1.1.1:443"; // TCP connect, so no raw-socket privileges needed
+const LATENCY_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+const LATENCY_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const LATENCY_WARN_THRESHOLD: Duration = Duration::from_millis(100); // Shown in red above this
+
+// ============================================================================
+// HTTP Status Endpoint (requires the `http` feature)
+// ============================================================================
+
+#[cfg(feature = "http")]
+const HTTP_STATUS_ADDR: Option<&str> = None; // e.g. Some("127.0.0.1:9090"), off by default
+
+// ============================================================================
+// Polling Configuration
+// ============================================================================
+
+const POLL_SECS: u64 = 1; // Local interface counters (cheap)
+const WAN_POLL_SECS: u64 = 5; // Router counters over SNMP (slow to update, costs the router)
+const WAN_INFO_POLLS: u32 = 12; // WAN link state and speed, read every this many WAN polls
+const WATCHDOG_RETRY_DELAY: Duration = Duration::from_secs(3); // Wait before restarting a crashed poll
+const SELECTION_CHECK_INTERVAL: Duration = Duration::from_millis(100); // Selector changes apply this fast
+const UTILIZATION_LERP: f32 = 0.5; // Fraction of the gap the bar closes each poll
+const RATE_SMOOTHING: Option<f64> = Some(0.3); // Moving-average weight of a new rate; None = raw
+const BASELINE_MAX_AGE: Duration = Duration::from_secs(60); // Counters older than this aren't diffed against
+const RECONNECT_HIGHLIGHT: Duration = Duration::from_secs(2); // Highlight after a down→up transition
+const STALE_AFTER_INTERVALS: u32 = 3; // Card age turns red after this many missed polls
+const HIDE_NOISY_INTERFACES: bool = true; // Default for the selector's loopback/container toggle
+
+// ============================================================================
+// Sparkline Configuration
+// ============================================================================
+
+const SPARKLINE_SAMPLES: usize = 30; // Number of polls kept per interface
+const SPARKLINE_HEIGHT: f32 = 24.0;
+
+// ============================================================================
+// Animation Configuration
+// ============================================================================
+
+const ANIMATE_TRANSITIONS: bool = true; // Default for the settings toggle (off saves battery)
+const TRANSITION_DURATION: Duration = Duration::from_millis(300); // Tween toward each new sample
+const FLASH_JUMP_FACTOR: f64 = 4.0; // Flash a card when its rate grows by this factor in one poll
+const FLASH_MIN_RATE: u64 = 64 * 1024; // bytes/sec; smaller jumps are idle noise, not "got busy"
+const FLASH_DURATION: Duration = Duration::from_millis(500);
+const COPIED_TOAST_DURATION: Duration = Duration::from_millis(1500); // "Copied ✓" after Copy stats
+
+// ============================================================================
+// Window Opacity Configuration
+// ============================================================================
+
+const MIN_WINDOW_OPACITY: u8 = 40; // Percent; lower and the cards stop being readable
+const WINDOW_OPACITY_STEP: u8 = 10;
+// X11 only shows through with a compositing window manager, which gpui can't report
+const TRANSPARENCY_CAVEAT: Option<&str> = if cfg!(target_os = "linux") {
+    Some("Without a compositor the background is dimmed, not see-through")
+} else {
+    None
 };
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
 
-// ============================================================================
-// SNMP Configuration (customize for your router)
-// ============================================================================
+// ============================================================================
+// Theme
+// ============================================================================
+
+/// Colors behind the semantic classes (`bg-surface`, `text-muted`, ...)
+fn dark_palette() -> HashMap<&'static str, Rgba> {
+    HashMap::from([
+        ("surface", rgb(0x333333)),
+        ("surface-alt", rgb(0x444444)),
+        ("primary", rgb(0xffffff)),
+        ("muted", rgb(0x666666)),
+        ("accent", rgb(0x4a90e2)),
+    ])
+}
+
+// ============================================================================
+// Global State
+// ============================================================================
+
+#[derive(Clone, Debug, PartialEq)]
+struct InterfaceMetric {
+    name: String,
+    rx_speed: String,
+    tx_speed: String,
+    rx_rate: u64, // bytes/sec
+    tx_rate: u64, // bytes/sec
+    is_wan: bool,
+    kind: InterfaceType,
+    link_speed_bps: Option<u64>, // None when the link capacity is unknown
+    utilization: Option<f32>,    // Displayed bar fill (0.0-1.0), eased between polls
+    is_up: Option<bool>,         // None when the backend can't report link state
+    highlight_until: Option<Instant>, // Set when the interface just came back up
+    flash_started: Option<Instant>, // Set when the rate just jumped (see FLASH_JUMP_FACTOR)
+    is_skeleton: bool,           // No real sample yet - render a shimmer card
+    is_unreachable: bool,        // Last SNMP fetch failed - this is the previous card, kept
+    peak: Option<(u64, u64)>,    // Highest (rx, tx) bytes/sec this session
+}
+
+impl InterfaceMetric {
+    /// A named card waiting for its first real delta
+    fn skeleton(name: &str, kind: InterfaceType) -> Self {
+        Self {
+            name: name.to_string(),
+            rx_speed: "-- B/s".to_string(),
+            tx_speed: "-- B/s".to_string(),
+            rx_rate: 0,
+            tx_rate: 0,
+            is_wan: kind.is_wan(),
+            kind,
+            link_speed_bps: None,
+            utilization: None,
+            is_up: None,
+            highlight_until: None,
+            flash_started: None,
+            is_skeleton: true,
+            is_unreachable: false,
+            peak: None,
+        }
+    }
+
+    fn from_delta(d: &NetDelta) -> Self {
+        // Smoothed per RATE_SMOOTHING; the raw rates stay on the NetDelta
+        let rx_rate = d.smoothed_rx.round() as u64;
+        let tx_rate = d.smoothed_tx.round() as u64;
+
+        Self {
+            name: d.interface.to_string(),
+            rx_speed: format::human_bytes_per_sec(rx_rate),
+            tx_speed: format::human_bytes_per_sec(tx_rate),
+            rx_rate,
+            tx_rate,
+            is_wan: d.kind.is_wan(),
+            kind: d.kind.clone(),
+            link_speed_bps: None,
+            utilization: None,
+            is_up: None,
+            highlight_until: None,
+            flash_started: None,
+            is_skeleton: false,
+            is_unreachable: false,
+            peak: None,
+        }
+    }
+
+    /// This card again, marked as not refreshed because its router didn't answer
+    fn unreachable(&self) -> Self {
+        Self {
+            is_unreachable: true,
+            ..self.clone()
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        self.is_up == Some(false)
+    }
+
+    fn is_highlighted(&self) -> bool {
+        self.highlight_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Carry render state over from the previous poll and flag down→up transitions
+    fn track_link_state(&mut self, previous: Option<&InterfaceMetric>) {
+        let Some(previous) = previous else { return };
+        self.highlight_until = if previous.is_down() && self.is_up == Some(true) {
+            Some(Instant::now() + RECONNECT_HIGHLIGHT)
+        } else {
+            previous
+                .highlight_until
+                .filter(|_| previous.is_highlighted())
+        };
+    }
+
+    /// Start a flash when the busier direction jumps, otherwise carry a running one over
+    fn track_rate_jump(&mut self, previous: Option<&InterfaceMetric>) {
+        let Some(previous) = previous.filter(|p| !p.is_skeleton) else {
+            return;
+        };
+        let before = previous.rx_rate.max(previous.tx_rate);
+        let after = self.rx_rate.max(self.tx_rate);
+
+        self.flash_started =
+            if after >= FLASH_MIN_RATE && after as f64 > before as f64 * FLASH_JUMP_FACTOR {
+                Some(Instant::now())
+            } else {
+                previous
+                    .flash_started
+                    .filter(|_| previous.flash_intensity().is_some())
+            };
+    }
+
+    /// Strength of the jump flash right now: 1.0 when it starts, fading out to None
+    fn flash_intensity(&self) -> Option<f32> {
+        let elapsed = self.flash_started?.elapsed();
+        (elapsed < FLASH_DURATION)
+            .then(|| 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32())
+    }
+
+    /// Target utilization: the busier direction over link capacity, clamped to 100%
+    fn target_utilization(&self) -> Option<f32> {
+        let capacity = self.link_speed_bps.filter(|&bps| bps > 0)?;
+        let busiest_bps = self.rx_rate.max(self.tx_rate) as f64 * 8.0;
+        Some((busiest_bps / capacity as f64).min(1.0) as f32)
+    }
+
+    /// Ease the displayed utilization from the previous poll toward the new target
+    fn ease_utilization(&mut self, previous: Option<&InterfaceMetric>) {
+        let target = self.target_utilization();
+        self.utilization = match (target, previous.and_then(|p| p.utilization)) {
+            (Some(target), Some(shown)) => Some(shown + (target - shown) * UTILIZATION_LERP),
+            (target, _) => target,
+        };
+    }
+}
+
+#[derive(Clone)]
+struct NetGaugeState {
+    interfaces: Vec<InterfaceMetric>,
+    snmp_available: bool,
+    snmp_info: String, // What the startup probe found, for the header's SNMP tooltip
+    available_interfaces: Vec<String>,
+    selection: Selection<String>, // Interfaces to poll, shared with the poll task
+    poll_error: Option<String>,   // Set by the watchdog when a poll cycle panics
+    local_error: Option<String>,  // The OS refused to give local interface counters
+    history: HashMap<String, VecDeque<u64>>, // Recent max(rx, tx) rates per interface, for sparklines
+    default_route: Option<String>,           // Interface the OS sends internet traffic through
+    router_rtt: HashMap<String, Rtt>,        // LAN → router per WAN card, from the SNMP request
+    internet_rtt: Rtt,                       // Router → internet, from the TCP connect probe
+    animate_transitions: bool,               // Ease numbers and bars between polls
+    interesting_interfaces: Vec<String>,     // Available ones minus loopback and container links
+    hide_noise: bool,                        // Selector lists only the above (and selected ones)
+    aliases: HashMap<String, String>,        // User-chosen card names, keyed by interface
+    window_opacity: u8,                      // Main window background, percent (text stays opaque)
+    sort_mode: SortMode,                     // Manual lets the user drag cards into place
+    sparkline_scale: SparklineScale,         // Shared puts every sparkline on one axis
+    card_order: Vec<String>,                 // Manual order, including cards not currently shown
+    // When each card's source last delivered data; written every poll without a redraw
+    last_updated: Arc<RwLock<HashMap<String, Instant>>>,
+    // Cards whose peak "Reset stats" cleared, for the poll task to forget in its tracker
+    peak_resets: Arc<Mutex<Vec<String>>>,
+}
+
+/// Round-trip time of one latency leg
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rtt {
+    Pending,
+    Timeout,
+    Measured(Duration),
+}
+
+impl Rtt {
+    fn label(&self) -> String {
+        match self {
+            Rtt::Pending => "--".to_string(),
+            Rtt::Timeout => "timeout".to_string(),
+            Rtt::Measured(d) => format!("{} ms", d.as_millis()),
+        }
+    }
+
+    fn is_slow(&self) -> bool {
+        match self {
+            Rtt::Pending => false,
+            Rtt::Timeout => true,
+            Rtt::Measured(d) => *d > LATENCY_WARN_THRESHOLD,
+        }
+    }
+}
+
+impl Global for NetGaugeState {}
+
+impl NetGaugeState {
+    /// Append the latest rates to each interface's history, dropping vanished interfaces
+    fn record_history(&mut self) {
+        let history = &mut self.history;
+        history.retain(|name, _| self.interfaces.iter().any(|m| &m.name == name));
+
+        for m in self.interfaces.iter().filter(|m| !m.is_skeleton) {
+            let samples = history.entry(m.name.clone()).or_default();
+            if samples.len() == SPARKLINE_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(m.rx_rate.max(m.tx_rate));
+        }
+    }
+
+    /// Name shown on cards: the user's alias if set, otherwise the raw interface name
+    fn display_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// Age indicator per card: ("just now" / "12s ago (stale)", is_stale)
+    ///
+    /// A card is judged against its own source's cadence, so the slower WAN poll
+    /// doesn't read as stale between SNMP fetches.
+    fn age_labels(&self) -> HashMap<String, (String, bool)> {
+        let last_updated = self.last_updated.read().unwrap();
+
+        self.interfaces
+            .iter()
+            .filter(|m| !m.is_skeleton)
+            .filter_map(|m| {
+                let age = last_updated.get(&m.name)?.elapsed();
+                let poll_secs = if m.is_wan { WAN_POLL_SECS } else { POLL_SECS };
+                let interval = Duration::from_secs(poll_secs);
+                let stale = age >= interval * STALE_AFTER_INTERVALS;
+
+                let label = if age < interval * 2 {
+                    "just now".to_string()
+                } else if stale {
+                    format!("{}s ago (stale)", age.as_secs())
+                } else {
+                    format!("{}s ago", age.as_secs())
+                };
+                Some((m.name.clone(), (label, stale)))
+            })
+            .collect()
+    }
+
+    /// Apply the saved manual order; cards not in it keep their relative order at the end
+    fn sort_manually(&self, interfaces: &mut [InterfaceMetric]) {
+        interfaces.sort_by_key(|m| {
+            self.card_order
+                .iter()
+                .position(|name| name == &m.name)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Move the `dragged` card to where `target` is on screen
+    fn move_card(&mut self, dragged: &str, target: &str) {
+        if dragged == target {
+            return;
+        }
+
+        // Pin down cards that were never placed first, so they don't jump around
+        let mut shown = self.interfaces.clone();
+        self.sort_manually(&mut shown);
+        for m in shown {
+            if !self.card_order.contains(&m.name) {
+                self.card_order.push(m.name);
+            }
+        }
+
+        let position = |name: &str| self.card_order.iter().position(|n| n == name);
+        let (Some(from), Some(to)) = (position(dragged), position(target)) else {
+            return;
+        };
+        // Inserting at the target's old index lands before it when moving up and,
+        // since the removal shifted it, after it when moving down
+        let name = self.card_order.remove(from);
+        self.card_order.insert(to, name);
+    }
+
+    /// Persist the user-editable parts of the state, keeping hand-edited settings
+    fn save_config(&self) {
+        let config = Config {
+            selected: Some(self.selection.snapshot().into_iter().collect()),
+            aliases: self.aliases.clone(),
+            animate: Some(self.animate_transitions),
+            hide_noise: Some(self.hide_noise),
+            opacity: Some(self.window_opacity),
+            sort: Some(self.sort_mode),
+            sparkline_scale: Some(self.sparkline_scale),
+            order: self.card_order.clone(),
+            ..Config::load()
+        };
+        if let Err(e) = config.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// Whether applying a poll result would change anything on screen
+    ///
+    /// Highlighted cards and sparklines with traffic still in their window keep
+    /// animating, so they count as changes even when the numbers are identical.
+    fn needs_redraw(
+        &self,
+        metrics: Option<&[InterfaceMetric]>,
+        poll_error: &Option<String>,
+        local_error: &Option<String>,
+        router_rtt: &HashMap<String, Rtt>,
+    ) -> bool {
+        let metrics_changed = metrics.is_some_and(|metrics| {
+            metrics != self.interfaces.as_slice()
+                || metrics.iter().any(|m| m.is_highlighted())
+                || self.history.values().flatten().any(|&sample| sample > 0)
+        });
+        let rtt_changed = router_rtt
+            .iter()
+            .any(|(name, rtt)| self.router_rtt.get(name).map(Rtt::label) != Some(rtt.label()));
+
+        metrics_changed
+            || rtt_changed
+            || *poll_error != self.poll_error
+            || *local_error != self.local_error
+    }
+
+    /// Top of the sparkline axis for an interface under the configured scaling mode
+    fn sparkline_max(&self, name: &str) -> u64 {
+        match self.sparkline_scale {
+            SparklineScale::Shared => self.history.values().flatten().copied().max(),
+            SparklineScale::Independent => self
+                .history
+                .get(name)
+                .and_then(|samples| samples.iter().copied().max()),
+        }
+        .unwrap_or(0)
+    }
+}
+
+// ============================================================================
+// Polling
+// ============================================================================
+
+/// An SNMP interface to poll, resolved from the configured targets at startup
+#[derive(Clone, Debug)]
+struct WanSource {
+    config: SnmpConfig,
+    hc_counters: bool,
+    if_index: u32,
+    card_name: String, // Prefixed with the target label when there are several targets
+}
+
+/// Resolve each reachable target's interfaces, in config order
+fn resolve_wan_sources(targets: &[SnmpTarget]) -> Vec<WanSource> {
+    let prefix_labels = targets.len() > 1;
+    let mut sources = Vec::new();
+
+    for target in targets {
+        let config = match snmp_auth(target) {
+            Ok(auth) => SnmpConfig::new(&target.address, auth)
+                .with_timeout(SNMP_TIMEOUT)
+                .with_retries(SNMP_RETRIES),
+            Err(e) => {
+                println!("{}: {}", target.label, e);
+                continue;
+            }
+        };
+        // Say why a session couldn't be set up - for v3 that's often engine discovery
+        let mut client = match SnmpClient::new(&config) {
+            Ok(client) => client,
+            Err(e) => {
+                println!(
+                    "SNMP unavailable on {} ({}): {}",
+                    target.label, target.address, e
+                );
+                continue;
+            }
+        };
+        if !client.is_available() {
+            println!("SNMP unavailable on {} ({})", target.label, target.address);
+            continue;
+        }
+
+        for interface in &target.interfaces {
+            let resolved = match interface {
+                SnmpInterface::Index(if_index) => client
+                    .interface_name(*if_index)
+                    .map(|name| (*if_index, name)),
+                SnmpInterface::Pattern(pattern) => {
+                    let candidates = client.detect_interface_indices(pattern);
+                    let ranked = client.rank_candidates(candidates);
+                    // Show every candidate so it's clear why the first one was picked
+                    for (index, name, status) in &ranked {
+                        let status = status.map_or("unknown".to_string(), |s| s.to_string());
+                        println!(
+                            "{}: {:?} matches {} (index {}, {})",
+                            target.label, pattern, name, index, status
+                        );
+                    }
+                    ranked
+                        .into_iter()
+                        .next()
+                        .map(|(index, name, _)| (index, name))
+                }
+            };
+            let Some((if_index, name)) = resolved else {
+                println!("{}: no interface matching {:?}", target.label, interface);
+                continue;
+            };
+            println!("{}: monitoring {} (index {})", target.label, name, if_index);
+
+            let card_name = if prefix_labels {
+                format!("{}: {} (WAN)", target.label, name)
+            } else {
+                format!("{} (WAN)", name)
+            };
+            sources.push(WanSource {
+                config: config.clone(),
+                hc_counters: target.hc_counters.unwrap_or(true),
+                if_index,
+                card_name,
+            });
+        }
+    }
+
+    sources
+}
+
+/// Credentials for a target: its v3 user if it has one, else the community
+fn snmp_auth(target: &SnmpTarget) -> Result<SnmpAuth, String> {
+    let Some(v3) = &target.v3 else {
+        let community = target.community.as_deref().unwrap_or("public");
+        return Ok(SnmpAuth::V2c {
+            community: community.as_bytes().to_vec(),
+        });
+    };
+
+    let auth_protocol = v3.auth_protocol.as_deref().unwrap_or("SHA").parse()?;
+    let priv_protocol = match v3.priv_password {
+        Some(_) => Some(v3.priv_protocol.as_deref().unwrap_or("AES").parse()?),
+        None => None,
+    };
+    Ok(SnmpAuth::V3 {
+        user: v3.user.clone(),
+        auth_protocol,
+        auth_password: v3.auth_password.clone(),
+        priv_protocol,
+        priv_password: v3.priv_password.clone().unwrap_or_default(),
+    })
+}
+
+/// SNMP side of polling, sent to a background thread for every WAN fetch
+///
+/// A router that doesn't answer blocks for the whole SNMP timeout, so the poll
+/// loop hands this off and keeps ticking the local cards; it comes back along
+/// with the fetched stats once every target has answered or timed out.
+struct WanPoller {
+    sources: Vec<WanSource>,
+    snmp_clients: HashMap<String, SnmpClient>, // One session per target address, kept open
+    wan_counters: WanCounterSource,            // Remembers which routers lie about HC counters
+    polls: u32,                                // WAN polls so far, for WAN_INFO_POLLS
+}
+
+/// What one WAN fetch brought back
+struct WanFetch {
+    stats: Vec<InterfaceStats>,
+    info: Vec<(String, WanInfo)>,     // Link state and speed, when they were due
+    router_rtt: HashMap<String, Rtt>, // How long each SNMP request took, by card name
+    unreachable: Vec<String>,         // Cards whose target didn't answer
+}
+
+/// Where a finished WAN fetch waits for the poll loop to pick it up
+type WanSlot = Arc<Mutex<Option<(WanPoller, WanFetch)>>>;
+
+impl WanPoller {
+    fn new(sources: Vec<WanSource>) -> Self {
+        Self {
+            sources,
+            snmp_clients: HashMap::new(),
+            wan_counters: WanCounterSource::new(),
+            polls: 0,
+        }
+    }
+
+    /// Fetch WAN stats via SNMP from every target (blocking)
+    fn fetch(&mut self) -> WanFetch {
+        let info_due = self.polls.is_multiple_of(WAN_INFO_POLLS);
+        self.polls = self.polls.wrapping_add(1);
+
+        let mut fetch = WanFetch {
+            stats: Vec::new(),
+            info: Vec::new(),
+            router_rtt: HashMap::new(),
+            unreachable: Vec::new(),
+        };
+        for source in &self.sources {
+            let started = Instant::now();
+            // A session that couldn't be opened is retried on the next poll
+            let client = match self.snmp_clients.entry(source.config.target.clone()) {
+                Entry::Occupied(entry) => Ok(entry.into_mut()),
+                Entry::Vacant(entry) => SnmpClient::new(&source.config)
+                    .map(|client| entry.insert(client.with_hc_counters(source.hc_counters))),
+            };
+            let fetched = client.and_then(|client| {
+                let was_fallback = self.wan_counters.is_fallback(client.target(), source.if_index);
+                let wan_stats =
+                    self.wan_counters
+                        .fetch(client, source.if_index, &source.card_name)?;
+                if !was_fallback && self.wan_counters.is_fallback(client.target(), source.if_index) {
+                    eprintln!(
+                        "SNMP: {} has no working 64-bit counters, using ifInOctets/ifOutOctets",
+                        source.card_name
+                    );
+                }
+                let rtt = started.elapsed();
+                // Link state and speed rarely change; a router that can't report
+                // them still gets its throughput polled
+                if info_due {
+                    match client.wan_info(source.if_index) {
+                        Ok(info) => fetch.info.push((source.card_name.clone(), info)),
+                        Err(e) => {
+                            eprintln!("SNMP: no link info for {}: {}", source.card_name, e)
+                        }
+                    }
+                }
+                Ok((wan_stats, rtt))
+            });
+            match fetched {
+                Ok((wan_stats, rtt)) => {
+                    fetch
+                        .router_rtt
+                        .insert(source.card_name.clone(), Rtt::Measured(rtt));
+                    fetch.stats.push(wan_stats);
+                }
+                Err(e) => {
+                    eprintln!("SNMP: {} not updated: {}", source.card_name, e);
+                    fetch.router_rtt.insert(source.card_name.clone(), Rtt::Timeout);
+                    fetch.unreachable.push(source.card_name.clone());
+                }
+            }
+        }
+        fetch
+    }
+
+    /// Fetch, then leave the poller and the result in `slot` for the poll loop
+    fn fetch_into(mut self, slot: &Mutex<Option<(WanPoller, WanFetch)>>) {
+        let fetch = match panic::catch_unwind(AssertUnwindSafe(|| self.fetch())) {
+            Ok(fetch) => fetch,
+            Err(payload) => {
+                eprintln!("WAN fetch panicked: {}", panic_message(payload.as_ref()));
+                // A session may have been left mid-request; open fresh ones next time
+                self = WanPoller::new(std::mem::take(&mut self.sources));
+                let cards: Vec<String> = self.sources.iter().map(|s| s.card_name.clone()).collect();
+                WanFetch {
+                    stats: Vec::new(),
+                    info: Vec::new(),
+                    router_rtt: cards.iter().map(|c| (c.clone(), Rtt::Timeout)).collect(),
+                    unreachable: cards,
+                }
+            }
+        };
+        *slot.lock().unwrap() = Some((self, fetch));
+    }
+}
+
+/// Polls local and WAN counters on their own cadences
+///
+/// Each source has a "next due" time and is only fetched when due; the tracker
+/// divides each delta by the time since that interface's previous sample, so the
+/// slower WAN cadence still reports bytes per second. Between fetches the last cards of
+/// the source that wasn't due are carried over unchanged. WAN fetches run in the
+/// background (see `WanPoller`) and are folded in on the first poll after they finish.
+struct Poller {
+    tracker: DeltaTracker,
+    deltas: Vec<NetDelta>, // Reused by every poll
+    wan_sources: Vec<WanSource>,
+    wan: Option<WanPoller>, // None while a WAN fetch is running
+    wan_slot: WanSlot,
+    wan_info: HashMap<String, WanInfo>, // Link state and speed per WAN card
+    next_local: Instant,
+    next_wan: Instant,
+    last_local: Option<Instant>,
+    last_wan: Option<Instant>,
+    local_metrics: Vec<InterfaceMetric>,
+    wan_metrics: Vec<InterfaceMetric>,
+    local_error: Option<String>, // Why the last local fetch failed, shown as an error card
+}
+
+impl Poller {
+    fn new(wan_sources: Vec<WanSource>) -> Self {
+        let now = Instant::now();
+        Self {
+            // Skip first samples so the first numbers on screen are real rates
+            tracker: match RATE_SMOOTHING {
+                Some(alpha) => DeltaTracker::new().with_smoothing(alpha),
+                None => DeltaTracker::new(),
+            }
+            .with_first_sample(FirstSample::Skip)
+            .with_max_age(BASELINE_MAX_AGE),
+            deltas: Vec::new(),
+            wan: Some(WanPoller::new(wan_sources.clone())),
+            wan_sources,
+            wan_slot: Arc::new(Mutex::new(None)),
+            wan_info: HashMap::new(),
+            next_local: now,
+            next_wan: now,
+            last_local: None,
+            last_wan: None,
+            local_metrics: Vec::new(),
+            wan_metrics: Vec::new(),
+            local_error: None,
+        }
+    }
+
+    /// Fetch local interfaces on the next poll instead of waiting out the interval
+    fn poll_local_now(&mut self) {
+        self.next_local = Instant::now();
+    }
+
+    /// Whether a background WAN fetch has finished and waits to be polled in
+    fn wan_fetched(&self) -> bool {
+        self.wan_slot.lock().unwrap().is_some()
+    }
+
+    /// Time until the next source is due
+    ///
+    /// A running WAN fetch isn't waited for here; `wait_for_poll` wakes up for it.
+    fn until_next_due(&self) -> Duration {
+        let next = if self.wan_sources.is_empty() || self.wan.is_none() {
+            self.next_local
+        } else {
+            self.next_local.min(self.next_wan)
+        };
+        next.saturating_duration_since(Instant::now())
+    }
+
+    /// Hand the WAN poller out when a fetch is due, to be run off the poll loop
+    /// with `WanPoller::fetch_into(&slot)`; the result is picked up by `poll`
+    fn start_wan_fetch(&mut self) -> Option<(WanPoller, WanSlot)> {
+        let now = Instant::now();
+        if self.wan_sources.is_empty() || now < self.next_wan {
+            return None;
+        }
+        let wan = self.wan.take()?;
+        self.next_wan = now + Duration::from_secs(WAN_POLL_SECS);
+        Some((wan, self.wan_slot.clone()))
+    }
+
+    /// Fetch local interfaces when due, fold in a finished WAN fetch, and turn
+    /// them into metrics
+    ///
+    /// Also returns how long each SNMP request took (LAN → router latency), keyed
+    /// by card name, when a WAN fetch came in.
+    fn poll(&mut self, selected: &InterfaceSet) -> (Vec<InterfaceMetric>, HashMap<String, Rtt>) {
+        let now = Instant::now();
+        let local_due = now >= self.next_local;
+
+        // An empty InterfaceSet matches everything, but here it means nothing is selected
+        let mut stats = Vec::new();
+        if local_due {
+            self.local_error = None;
+            if !selected.is_empty() {
+                match fetch_net_stats(selected) {
+                    Ok(local) => stats = local,
+                    Err(e) => self.local_error = Some(e.to_string()),
+                }
+            }
+            // Deselected or vanished interfaces start from a fresh baseline if they return
+            if self.local_error.is_none() {
+                let live: Vec<&str> = stats
+                    .iter()
+                    .map(|s| s.interface.as_str())
+                    .chain(self.wan_sources.iter().map(|s| s.card_name.as_str()))
+                    .collect();
+                self.tracker.prune(&live);
+            }
+        }
+
+        // Fold in a WAN fetch that finished since the last poll
+        let fetched = self.wan_slot.lock().unwrap().take();
+        let wan_fetched = fetched.is_some();
+        let mut router_rtt = HashMap::new();
+        let mut unreachable = Vec::new();
+        if let Some((wan, fetch)) = fetched {
+            self.wan = Some(wan);
+            stats.extend(fetch.stats);
+            self.wan_info.extend(fetch.info);
+            router_rtt = fetch.router_rtt;
+            unreachable = fetch.unreachable;
+        }
+
+        // Interfaces whose first sample was skipped stay skeletons until the next poll
+        self.tracker.update_into(&stats, &mut self.deltas);
+        let mut local_metrics = Vec::with_capacity(stats.len());
+        let mut wan_metrics = Vec::with_capacity(self.wan_sources.len());
+        for s in &stats {
+            let is_wan = s.kind.is_wan();
+            let mut metric = self
+                .deltas
+                .iter()
+                .find(|d| {
+                    *d.interface == s.interface
+                        && d.kind == s.kind
+                        && d.target.as_deref() == s.target.as_deref()
+                })
+                .map(InterfaceMetric::from_delta)
+                .unwrap_or_else(|| InterfaceMetric::skeleton(&s.interface, s.kind.clone()));
+
+            metric.peak = self.tracker.peak(&s.interface);
+            metric.is_up = s.is_up;
+            metric.link_speed_bps = s.link_speed_bps;
+            // Routers report link state and speed through a separate, less frequent query
+            if let Some(info) = self.wan_info.get(&s.interface).filter(|_| is_wan) {
+                metric.is_up = Some(info.oper_up);
+                metric.link_speed_bps = info.speed_mbps.map(|mbps| mbps * 1_000_000);
+            }
+            if is_wan {
+                wan_metrics.push(metric);
+            } else {
+                local_metrics.push(metric);
+            }
+        }
+
+        if local_due {
+            // A failed fetch keeps the last cards, which age into stale under the error card
+            if self.local_error.is_none() {
+                self.local_metrics = local_metrics;
+                self.last_local = Some(now);
+            }
+            self.next_local = now + Duration::from_secs(POLL_SECS);
+        }
+        if wan_fetched {
+            // Keep a failed source's last card in its place; it's retried on the next poll
+            for (position, source) in self.wan_sources.iter().enumerate() {
+                if !unreachable.contains(&source.card_name) {
+                    continue;
+                }
+                let card = self
+                    .wan_metrics
+                    .iter()
+                    .find(|m| m.name == source.card_name && !m.is_skeleton)
+                    .map(InterfaceMetric::unreachable)
+                    .unwrap_or_else(|| {
+                        InterfaceMetric::skeleton(&source.card_name, InterfaceType::Wan)
+                    });
+                wan_metrics.insert(position, card);
+            }
+            self.wan_metrics = wan_metrics;
+            self.last_wan = Some(now);
+        }
+
+        let mut metrics = self.local_metrics.clone();
+        metrics.extend(self.wan_metrics.iter().cloned());
+        (metrics, router_rtt)
+    }
+
+    /// Forget the peaks of the cards queued in `resets` by "Reset stats"
+    fn reset_peaks(&mut self, resets: &Mutex<Vec<String>>) {
+        for name in resets.lock().unwrap().drain(..) {
+            self.tracker.reset_peak(&name);
+        }
+    }
+
+    /// Stamp each card with when its source was last fetched
+    fn record_updates(
+        &self,
+        metrics: &[InterfaceMetric],
+        last_updated: &RwLock<HashMap<String, Instant>>,
+    ) {
+        let mut last_updated = last_updated.write().unwrap();
+        last_updated.retain(|name, _| metrics.iter().any(|m| &m.name == name));
+
+        // Unreachable cards keep their old stamp, so they age into "stale"
+        let fresh = metrics.iter().filter(|m| !m.is_skeleton && !m.is_unreachable);
+        for m in fresh {
+            let fetched = if m.is_wan { self.last_wan } else { self.last_local };
+            if let Some(at) = fetched {
+                last_updated.insert(m.name.clone(), at);
+            }
+        }
+    }
+}
+
+/// Sleep until the next poll is due, until a background WAN fetch finishes, or
+/// until the selection moves on from `version` - then local interfaces are
+/// fetched right away, so the selector's changes show up without waiting out
+/// the interval
+async fn wait_for_poll(
+    poller: &mut Poller,
+    selection: &Selection<String>,
+    version: u64,
+    delay: Duration,
+) {
+    let due = Instant::now() + delay;
+    loop {
+        if selection.changed_since(version) {
+            poller.poll_local_now();
+            return;
+        }
+        if poller.wan_fetched() {
+            return;
+        }
+        let left = due.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        Timer::after(left.min(SELECTION_CHECK_INTERVAL)).await;
+    }
+}
+
+/// Extract a readable message from a caught panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// ============================================================================
+// Transitions
+// ============================================================================
+
+/// Displayed (rx, tx, utilization) of one card, easing toward its latest sample
+///
+/// Only what is drawn is tweened; `rx_rate`/`tx_rate` on the metric stay the raw
+/// sample so totals and thresholds never see an in-between value.
+struct Transition {
+    from: (f64, f64, Option<f32>),
+    to: (u64, u64, Option<f32>),
+    started: Instant,
+}
+
+impl Transition {
+    /// A transition that has already finished at `to`
+    fn settled(to: (u64, u64, Option<f32>)) -> Self {
+        Self {
+            from: (to.0 as f64, to.1 as f64, to.2),
+            to,
+            started: Instant::now() - TRANSITION_DURATION,
+        }
+    }
+
+    /// Eased progress in 0..=1 (ease-out cubic)
+    fn progress(&self) -> f32 {
+        let t = self.started.elapsed().as_secs_f32() / TRANSITION_DURATION.as_secs_f32();
+        1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+    }
+
+    fn shown(&self) -> (f64, f64, Option<f32>) {
+        let t = self.progress();
+        let lerp = |from: f64, to: u64| from + (to as f64 - from) * t as f64;
+        let utilization = match (self.from.2, self.to.2) {
+            (Some(from), Some(to)) => Some(from + (to - from) * t),
+            (_, to) => to,
+        };
+        (lerp(self.from.0, self.to.0), lerp(self.from.1, self.to.1), utilization)
+    }
+}
+
+// ============================================================================
+// App View
+// ============================================================================
+
+struct AppView {
+    transitions: HashMap<String, Transition>,
+    focus_handle: FocusHandle,
+    menu: Option<(String, Point<Pixels>)>, // Card context menu: interface and click position
+    renaming: Option<(String, String)>,    // Interface being renamed and the edit buffer
+    age_labels: HashMap<String, (String, bool)>, // As last drawn, so the ticker knows when to redraw
+    hovered: bool,                               // Pointer over the window: show it fully opaque
+    background_alpha: f32,                       // Alpha for this frame's backgrounds
+    copied_at: Option<Instant>,                  // Last "Copy stats", shows the toast for a moment
+    renders: u64,
+    log_renders: bool, // NETGAUGE_LOG_RENDERS is set; debug builds then print each frame
+}
+
+impl AppView {
+    /// The metric as it should be drawn right now, plus how far its transition is
+    fn tweened(&mut self, metric: &InterfaceMetric, animate: bool) -> (InterfaceMetric, f32) {
+        let target = (metric.rx_rate, metric.tx_rate, metric.utilization);
+        if !animate {
+            self.transitions.remove(&metric.name);
+            return (metric.clone(), 1.0);
+        }
+
+        let transition = self
+            .transitions
+            .entry(metric.name.clone())
+            .or_insert_with(|| Transition::settled(target));
+        if transition.to != target {
+            // Start from whatever is on screen, even mid-transition
+            *transition = Transition {
+                from: transition.shown(),
+                to: target,
+                started: Instant::now(),
+            };
+        }
+
+        let (rx, tx, utilization) = transition.shown();
+        let mut shown = metric.clone();
+        shown.rx_speed = format::human_bytes_per_sec(rx.round() as u64);
+        shown.tx_speed = format::human_bytes_per_sec(tx.round() as u64);
+        shown.utilization = utilization;
+        (shown, transition.progress())
+    }
+
+    fn new(cx: &mut Context<Self>) -> Self {
+        // Redraw only when the shared state actually changes
+        cx.observe_global::<NetGaugeState>(|_this, cx| cx.notify()).detach();
+
+        // Ages move without the state changing: redraw only when a label would change
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            Timer::after(Duration::from_secs(1)).await;
+            let ticked = this.update(cx, |this, cx| {
+                if this.age_labels != cx.global::<NetGaugeState>().age_labels() {
+                    cx.notify();
+                }
+            });
+            if ticked.is_err() {
+                break;
+            }
+        })
+        .detach();
+
+        Self {
+            transitions: HashMap::new(),
+            focus_handle: cx.focus_handle(),
+            menu: None,
+            renaming: None,
+            age_labels: HashMap::new(),
+            hovered: false,
+            background_alpha: 1.0,
+            copied_at: None,
+            renders: 0,
+            log_renders: std::env::var_os("NETGAUGE_LOG_RENDERS").is_some(),
+        }
+    }
+
+    /// A `bg-*` class color at this frame's window opacity
+    fn background(&self, class: &str) -> Hsla {
+        Hsla::from(background_color(class).unwrap_or_default()).opacity(self.background_alpha)
+    }
+
+    /// Let a card be dragged onto another to take its place (manual order only)
+    fn make_draggable(
+        &self,
+        card: Stateful<gpui::Div>,
+        name: &str,
+        label: &str,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let dragged = DraggedCard {
+            name: name.to_string(),
+            label: label.to_string(),
+        };
+        let drop_highlight = self.background("bg-blue");
+        let target = name.to_string();
+
+        card.on_drag(dragged, |dragged, _offset, _window, cx| {
+                cx.new(|_cx| dragged.clone())
+            })
+            .drag_over::<DraggedCard>(move |style, _dragged, _window, _cx| style.bg(drop_highlight))
+            .on_drop(
+                cx.listener(move |_this, dragged: &DraggedCard, _window, cx| {
+                    cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                        state.move_card(&dragged.name, &target);
+                        state.save_config();
+                    });
+                }),
+            )
+            .into_any_element()
+    }
+
+    // ------------------------------------------------------------------------
+    // Card context menu
+    // ------------------------------------------------------------------------
+
+    fn build_card_menu(
+        &self,
+        name: &str,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let view = cx.entity().downgrade();
+        let item = |label: &str, action: fn(&mut Self, &str, &mut Window, &mut Context<Self>)| {
+            let view = view.clone();
+            let name = name.to_string();
+            MenuItem::new(label.to_string(), move |window, cx| {
+                let _ = view.update(cx, |this, cx| action(this, &name, window, cx));
+            })
+        };
+
+        let items = vec![
+            item("Rename…", Self::start_rename),
+            item("Hide", Self::hide_interface),
+            item("Reset stats", Self::reset_stats),
+            item("Copy stats", Self::copy_stats),
+            item("Open details", |_this, name, _window, cx| open_details_window(name, cx)),
+        ];
+
+        let view = view.clone();
+        popup_menu(position, items, move |_window, cx| {
+            let _ = view.update(cx, |this, cx| {
+                this.menu = None;
+                cx.notify();
+            });
+        })
+    }
+
+    fn start_rename(&mut self, name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let current = cx.global::<NetGaugeState>().display_name(name).to_string();
+        self.renaming = Some((name.to_string(), current));
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    /// Store the edited name as an alias; an empty name (or the raw one) clears it
+    fn commit_rename(&mut self, cx: &mut Context<Self>) {
+        let Some((name, buffer)) = self.renaming.take() else {
+            return;
+        };
+        let alias = buffer.trim().to_string();
+
+        cx.update_global::<NetGaugeState, _>(|state, _cx| {
+            if alias.is_empty() || alias == name {
+                state.aliases.remove(&name);
+            } else {
+                state.aliases.insert(name, alias);
+            }
+            state.save_config();
+        });
+        cx.notify();
+    }
+
+    /// Remove the card and drop the interface from the saved selection
+    fn hide_interface(&mut self, name: &str, _window: &mut Window, cx: &mut Context<Self>) {
+        self.transitions.remove(name);
+        cx.update_global::<NetGaugeState, _>(|state, _cx| {
+            state.selection.set(name.to_string(), false);
+            state.interfaces.retain(|m| m.name != name);
+            state.history.remove(name);
+            state.save_config();
+        });
+    }
+
+    fn reset_stats(&mut self, name: &str, _window: &mut Window, cx: &mut Context<Self>) {
+        self.transitions.remove(name);
+        cx.update_global::<NetGaugeState, _>(|state, _cx| {
+            state.history.remove(name);
+            // Cleared now rather than on the next poll, which reads the tracker again
+            if let Some(m) = state.interfaces.iter_mut().find(|m| m.name == name) {
+                m.peak = None;
+            }
+            state.peak_resets.lock().unwrap().push(name.to_string());
+        });
+    }
+
+    fn copy_stats(&mut self, name: &str, _window: &mut Window, cx: &mut Context<Self>) {
+        let state = cx.global::<NetGaugeState>();
+        let Some(m) = state.interfaces.iter().find(|m| m.name == name) else {
+            return;
+        };
+
+        let text = format!(
+            "{}: ↓ {} ({}) ↑ {} ({})",
+            state.display_name(name),
+            format::human_bytes_per_sec(m.rx_rate),
+            format::human_bits_per_sec(m.rx_rate),
+            format::human_bytes_per_sec(m.tx_rate),
+            format::human_bits_per_sec(m.tx_rate),
+        );
+        cx.write_to_clipboard(ClipboardItem::new_string(text));
+
+        self.copied_at = Some(Instant::now());
+        cx.notify();
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            Timer::after(COPIED_TOAST_DURATION).await;
+            let _ = this.update(cx, |this, cx| {
+                // A later copy restarts the countdown
+                let expired = this
+                    .copied_at
+                    .is_some_and(|at| at.elapsed() >= COPIED_TOAST_DURATION);
+                if expired {
+                    this.copied_at = None;
+                    cx.notify();
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Keyboard input: edits the rename buffer, Escape closes the menu
+    fn on_key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+
+        let Some((_, buffer)) = self.renaming.as_mut() else {
+            if keystroke.key == "escape" && self.menu.take().is_some() {
+                cx.notify();
+            }
+            return;
+        };
+
+        match keystroke.key.as_str() {
+            "escape" => self.renaming = None,
+            "enter" => self.commit_rename(cx),
+            "backspace" => {
+                buffer.pop();
+            }
+            _ => {
+                let shortcut = keystroke.modifiers.control || keystroke.modifiers.platform;
+                if let (Some(text), false) = (&keystroke.key_char, shortcut) {
+                    buffer.push_str(text);
+                }
+            }
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn build_utilization_bar(&self, metric: &InterfaceMetric, accent: Hsla) -> gpui::Div {
+        match metric.utilization {
+            Some(fill) => {
+                let pct = format!("{:.0}%", fill * 100.0);
+                ui! {
+                    div["flex row items-center gap-2"] {
+                        { styled_div("flex-grow h-4 bg-light-gray")
+                            .child(styled_div("h-4").w(relative(fill)).bg(accent)) }
+                        div["text-xs text-dim w-32"] { text[pct] }
+                    }
+                }
+            }
+            // Unknown capacity: show that we don't know instead of a misleading 0%
+            None => ui! {
+                div["flex row items-center gap-2"] {
+                    div["flex-grow h-4 bg-footer"]
+                    div["text-xs text-dim w-32"] { text["?"] }
+                }
+            },
+        }
+    }
 
-const SNMP_TARGET: &str = "192.168.1.1:161";
-const SNMP_COMMUNITY: &[u8] = b"public";
-const SNMP_IF_PATTERN: &str = "ppp"; // Pattern to search for WAN interface (e.g., "ppp", "wan")
+    /// `newest_progress` grows the newest bar out of the previous one while a transition runs
+    fn build_sparkline(
+        &self,
+        samples: &VecDeque<u64>,
+        axis_max: u64,
+        accent: Hsla,
+        newest_progress: f32,
+    ) -> gpui::Div {
+        let mut bars = styled_div("flex row items-end flex-grow gap-1").h(px(SPARKLINE_HEIGHT));
+        let newest = samples.len().saturating_sub(1);
+        for (i, &value) in samples.iter().enumerate() {
+            let value = match i.checked_sub(1).map(|prev| samples[prev]) {
+                Some(prev) if i == newest => {
+                    prev as f32 + (value as f32 - prev as f32) * newest_progress
+                }
+                _ => value as f32,
+            };
+            // An all-zero axis draws a flat baseline instead of dividing by zero
+            let fraction = if axis_max == 0 {
+                0.0
+            } else {
+                value / axis_max as f32
+            };
+            bars = bars.child(
+                styled_div("w-3")
+                    .h(px((fraction * SPARKLINE_HEIGHT).max(1.0)))
+                    .bg(accent),
+            );
+        }
 
-// ============================================================================
-// Global State
-// ============================================================================
+        // Label the axis in both bytes and bits so the scale is unambiguous
+        let axis_label = format!(
+            "{} / {}",
+            format::human_bytes_per_sec(axis_max),
+            format::human_bits_per_sec(axis_max)
+        );
 
-#[derive(Clone, Debug)]
-struct InterfaceMetric {
-    name: String,
-    rx_speed: String,
-    tx_speed: String,
-    is_wan: bool,
-}
+        ui! {
+            div["flex row items-end gap-2"] {
+                { bars }
+                div["text-xs text-dim"] { text[axis_label] }
+            }
+        }
+    }
 
-#[derive(Clone)]
-struct NetGaugeState {
-    interfaces: Vec<InterfaceMetric>,
-    snmp_available: bool,
-    available_interfaces: Vec<String>,
-    selected_interfaces: Arc<RwLock<InterfaceSet>>,
-}
+    fn build_latency(&self, router: Rtt, internet: Rtt) -> gpui::Div {
+        let leg = |rtt: Rtt| {
+            let style = if rtt.is_slow() {
+                "text-xs text-red"
+            } else {
+                "text-xs text-gray"
+            };
+            styled_div(style).child(rtt.label())
+        };
 
-impl Global for NetGaugeState {}
+        ui! {
+            div["flex row items-center gap-1"] {
+                div["text-xs text-dim"] { text["RTT"] }
+                { leg(router) }
+                div["text-xs text-dim"] { text["/"] }
+                { leg(internet) }
+            }
+        }
+    }
 
-// ============================================================================
-// App View
-// ============================================================================
+    fn build_skeleton_card(&self, metric: &InterfaceMetric, accent: Hsla) -> AnyElement {
+        let label = metric.name.clone();
+        let shimmer = ui! {
+            div["flex col gap-1"] {
+                div["w-64 h-8 bg-light-gray"]
+                div["w-64 h-8 bg-light-gray"]
+            }
+        }
+        .with_animation(
+            SharedString::from(format!("skeleton-{}", metric.name)),
+            Animation::new(Duration::from_millis(1200))
+                .repeat()
+                .with_easing(pulsating_between(0.3, 0.8)),
+            |el, delta| el.opacity(delta),
+        );
 
-struct AppView;
+        ui! {
+            div["flex row items-center justify-between p-4 gap-4 rounded-6"] {
+                div["bold text-dim"] { text[label] }
+                { shimmer }
+            }
+        }
+        .bg(self.background("bg-gray"))
+        .border_l(px(4.))
+        .border_color(accent)
+        .into_any_element()
+    }
 
-impl AppView {
-    fn build_interface_card(&self, metric: &InterfaceMetric) -> gpui::Div {
-        let bg_style = if metric.is_wan {
-            "flex row items-center justify-between bg-wan p-4 gap-4"
+    fn build_empty_state(&self) -> gpui::Div {
+        let open_btn = button("choose-interfaces")
+            .child(icon(Icon::Gear, "size-14 text-primary"))
+            .child("Choose interfaces")
+            .on_click(|_event, _window, cx| open_interface_selector(cx));
+
+        ui! {
+            div["flex col items-center justify-center gap-4 p-4 flex-grow"] {
+                div["text-lg bold text-white"] { text["No interfaces found"] }
+                div["text-sm text-dim"] { text["None of the selected interfaces are reporting."] }
+                { open_btn }
+            }
+        }
+    }
+
+    fn build_interface_card(
+        &self,
+        metric: &InterfaceMetric,
+        display_name: &str,
+        accent: Hsla,
+        sparkline: Option<gpui::Div>,
+        is_default_route: bool,
+        flash: Option<f32>,
+    ) -> gpui::Div {
+        let background = if metric.is_highlighted() {
+            self.background("bg-light-gray")
+        } else if metric.is_wan {
+            self.background("bg-wan")
         } else {
-            "flex row items-center justify-between bg-gray p-4 gap-4"
+            self.background("bg-gray")
         };
+        // A rate jump washes the card in its accent color, fading back over FLASH_DURATION
+        let background = match flash {
+            Some(intensity) => background.blend(accent.opacity(0.5 * intensity)),
+            None => background,
+        };
+
+        let label = display_name.to_string();
 
-        let label = if metric.is_wan {
-            format!("🌐 {}", metric.name)
+        // A down interface shows a badge instead of a live-looking 0 B/s, and an
+        // unreachable router shows no rates rather than its last ones
+        let (rx, tx) = if metric.is_down() || metric.is_unreachable {
+            ("--".to_string(), "--".to_string())
         } else {
-            metric.name.clone()
+            (metric.rx_speed.clone(), metric.tx_speed.clone())
         };
+        // Highest rate this session, whichever direction, for spotting bursts
+        let peak = metric
+            .peak
+            .filter(|_| !metric.is_down() && !metric.is_unreachable)
+            .map(|(rx, tx)| format!("peak {}", format::human_bytes_per_sec(rx.max(tx))));
+        let bar = self.build_utilization_bar(metric, accent);
 
-        let rx = metric.rx_speed.clone();
-        let tx = metric.tx_speed.clone();
+        // Long names (e.g. Hyper-V adapters) are cut short rather than pushing the rates
+        // out; the tooltip has the full name
+        let name = styled_div("bold text-white truncate")
+            .id(SharedString::from(format!("title-{}", metric.name)));
+        let name = tooltip(name, label.clone()).child(label);
+        let mut title = styled_div("flex row flex-wrap flex-1 overflow-hidden items-center gap-2");
+        let kind_icon = match metric.kind {
+            InterfaceType::Wan => Some(Icon::Globe),
+            InterfaceType::Wireless => Some(Icon::Wifi),
+            InterfaceType::Vpn => Some(Icon::Lock),
+            InterfaceType::Virtual | InterfaceType::Bridge => Some(Icon::Link),
+            InterfaceType::Net | InterfaceType::Loopback => None,
+        };
+        if let Some(kind_icon) = kind_icon {
+            title = title.child(icon(kind_icon, "size-14 text-gray"));
+        }
+        title = title.child(name);
+        if is_default_route {
+            title = title.child(ui! {
+                div["text-xs bold text-white bg-blue px-4 py-1"] { text["default"] }
+            });
+        }
+        if metric.is_down() {
+            title = title.child(ui! {
+                div["text-xs bold text-white bg-error px-4 py-1"] { text["down"] }
+            });
+        }
+
+        // Down cards are dimmed, but readable again under the pointer
+        let card_style = if metric.is_down() {
+            "flex col p-4 gap-2 rounded-6 opacity-50 hover:opacity-100"
+        } else {
+            "flex col p-4 gap-2 rounded-6"
+        };
 
         ui! {
-            div[bg_style] {
-                div["bold text-white"] { text[label] }
-                div["flex col gap-1"] {
-                    div["flex row gap-2 text-white"] {
-                        text["↓"]
-                        text[rx]
-                    }
-                    div["flex row gap-2 text-white"] {
-                        text["↑"]
-                        text[tx]
+            div[card_style] {
+                div["flex row items-center justify-between gap-4"] {
+                    { title }
+                    div["flex col flex-shrink-0 gap-1"] {
+                        // Fixed minimum so "999 KiB/s" → "1.02 MiB/s" doesn't resize the card
+                        div["flex row gap-2 text-white"] {
+                            icon[ArrowDown]
+                            div["min-w-80 font-mono"] { text[rx] }
+                        }
+                        div["flex row gap-2 text-white"] {
+                            icon[ArrowUp]
+                            div["min-w-80 font-mono"] { text[tx] }
+                        }
+                        if let Some(peak) = peak {
+                            div["text-xs text-gray font-mono"] { text[peak] }
+                        }
                     }
                 }
+                { bar }
             }
         }
+        .children(sparkline)
+        .bg(background)
+        .border_l(px(4.))
+        .border_color(accent)
     }
 }
 
 impl Render for AppView {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.renders += 1;
+        if cfg!(debug_assertions) && self.log_renders {
+            eprintln!("Main view render #{}", self.renders);
+        }
+
         let state = cx.global::<NetGaugeState>();
-        let interfaces = state.interfaces.clone();
+        let mut interfaces = state.interfaces.clone();
+        let manual_order = state.sort_mode == SortMode::Manual;
+        if manual_order {
+            state.sort_manually(&mut interfaces);
+        }
         let snmp_available = state.snmp_available;
+        let snmp_info = state.snmp_info.clone();
+
+        // Only the backgrounds fade; the pointer brings the window back to full opacity.
+        // Hover ends are missed when the pointer leaves the window directly, so
+        // Linux and Windows also check the window's own hover state (on macOS that
+        // tracks focus instead, so it can't be used there).
+        let hovered = self.hovered && (cfg!(target_os = "macos") || window.is_window_hovered());
+        self.background_alpha = if hovered {
+            1.0
+        } else {
+            state.window_opacity as f32 / 100.0
+        };
+        let poll_error = state.poll_error.clone();
+        let local_error = state.local_error.clone();
 
-        let snmp_status = if snmp_available { "SNMP: ✓" } else { "SNMP: ✗" };
+        // Build interface cards, each with its stable accent color
+        let accents = accent::assign_colors(interfaces.iter().map(|m| m.name.as_str()));
+        self.transitions.retain(|name, _| interfaces.iter().any(|m| &m.name == name));
+        self.age_labels = state.age_labels();
+        let mut animating = false;
+        let cards: Vec<AnyElement> = interfaces
+            .iter()
+            .map(|m| {
+                let accent = accents[&m.name];
+                if m.is_skeleton {
+                    return self.build_skeleton_card(m, accent);
+                }
+                let (shown, progress) = self.tweened(m, state.animate_transitions);
+                let flash = m.flash_intensity().filter(|_| state.animate_transitions);
+                animating |= progress < 1.0 || flash.is_some();
 
-        // Build interface cards
-        let cards: Vec<_> = interfaces.iter().map(|m| self.build_interface_card(m)).collect();
+                let sparkline = state.history.get(&m.name).map(|samples| {
+                    self.build_sparkline(samples, state.sparkline_max(&m.name), accent, progress)
+                });
+                let is_default_route = state.default_route.as_ref() == Some(&m.name);
+                let display_name = match &self.renaming {
+                    Some((name, buffer)) if name == &m.name => format!("{}▏", buffer),
+                    _ => state.display_name(&m.name).to_string(),
+                };
 
-        // Settings button with click handler
-        let settings_btn = styled_div("text-sm text-blue cursor-pointer")
-            .child("⚙ Interfaces")
-            .on_mouse_down(gpui::MouseButton::Left, |_ev, _window, cx| {
-                let bounds = Bounds::centered(None, size(px(450.), px(400.)), cx);
-                cx.open_window(
-                    WindowOptions {
-                        window_bounds: Some(WindowBounds::Windowed(bounds)),
-                        is_resizable: true,
-                        ..Default::default()
-                    },
-                    |_window, cx| cx.new(|_cx| InterfaceSelectorView),
-                )
-                .expect("Failed to open interface selector window");
-            });
+                let name = m.name.clone();
+                let card = self
+                    .build_interface_card(
+                        &shown,
+                        &display_name,
+                        accent,
+                        sparkline,
+                        is_default_route,
+                        flash,
+                    )
+                    .on_mouse_down(
+                        MouseButton::Right,
+                        cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                            this.menu = Some((name.clone(), event.position));
+                            window.focus(&this.focus_handle);
+                            cx.notify();
+                        }),
+                    );
+                // Double-click expands a card into its details window
+                let card = card.id(SharedString::from(format!("card-{}", m.name)));
+                let card = declarative_ui::on_double_click(card, |event, _window, cx| {
+                    if let Some(ElementId::Name(id)) = &event.element_id
+                        && let Some(name) = id.strip_prefix("card-")
+                    {
+                        open_details_window(name, cx)
+                    }
+                });
+                let card = match self.age_labels.get(&m.name) {
+                    Some((label, stale)) => {
+                        let color = if *stale { "text-red" } else { "text-dim" };
+                        card.child(styled_div(&format!("text-xs {}", color)).child(label.clone()))
+                    }
+                    None => card,
+                };
+                let card = if m.is_wan {
+                    let router_rtt = state.router_rtt.get(&m.name).copied().unwrap_or(Rtt::Pending);
+                    card.child(self.build_latency(router_rtt, state.internet_rtt))
+                } else {
+                    card
+                };
+                if manual_order {
+                    self.make_draggable(card, &m.name, &display_name, cx)
+                } else {
+                    card.into_any_element()
+                }
+            })
+            .collect();
 
-        // Build content with cards
-        let mut content = styled_div("flex col gap-4 p-4");
-        for card in cards {
-            content = content.child(card);
+        // Keep drawing frames only while a transition is running
+        if animating {
+            window.request_animation_frame();
         }
 
-        ui! {
-            div["flex col bg-dark size-full"] {
-                div["flex row items-center justify-between p-4 bg-gray"] {
-                    div["text-xl bold text-white"] { text["NetGauge"] }
-                    div["flex row gap-4"] {
-                        div["text-sm text-dim"] { text[snmp_status] }
-                        { settings_btn }
+        // Settings button with click handler
+        let settings_btn = jsx! {
+            <button
+                id={"interfaces"}
+                variant={ButtonVariant::Ghost}
+                on_click={|_event, _window, cx| open_interface_selector(cx)}
+            > {
+                <icon name={Icon::Gear} class={"size-14 text-accent"} />
+                <text>{"Interfaces"}</text>
+            } </button>
+        };
+
+        // Build content with cards, prefixed by the watchdog banner if polling crashed
+        // Scrolls under the fixed header once the cards outgrow the window
+        let content = scrollable_div("cards", "flex col flex-grow p-4").child(ui! {
+            div["flex col gap-4"] {
+                if let Some(err) = poll_error {
+                    div["flex col gap-1 p-2 bg-error text-white text-sm"] {
+                        div["flex row items-center gap-2 bold"] {
+                            icon[Warning, "size-14"]
+                            text["Polling stopped — retrying"]
+                        }
+                        div["text-xs"] { text[err] }
                     }
                 }
+                if let Some(err) = local_error {
+                    div["flex col gap-1 p-4 rounded-6 bg-gray text-white text-sm"] {
+                        div["flex row items-center gap-2 bold"] {
+                            icon[Warning, "size-14"]
+                            text["Can't read network interfaces"]
+                        }
+                        div["text-xs text-dim"] { text[err] }
+                    }
+                } else if cards.is_empty() {
+                    (self.build_empty_state())
+                }
+                for card in cards { (card) }
+            }
+        });
+
+        let menu = self
+            .menu
+            .clone()
+            .map(|(name, position)| self.build_card_menu(&name, position, cx));
+
+        let snmp_status = jsx! {
+            <div
+                class={"flex row items-center gap-1 text-sm text-dim"}
+                id={"snmp-status"}
+                tooltip={snmp_info}
+            > {
+                <text>{"SNMP"}</text>
+                <icon
+                    name={if snmp_available { Icon::Check } else { Icon::X }}
+                    class={"size-12 text-dim"}
+                />
+            } </div>
+        };
+
+        let header = ui! {
+            div["flex row items-center justify-between p-4"] {
+                div["text-xl bold text-white"] { text["NetGauge"] }
+                div["flex row gap-4"] {
+                    { snmp_status }
+                    { settings_btn }
+                }
+            }
+        }
+        .bg(self.background("bg-gray"));
+
+        let body = ui! {
+            div["flex col size-full"] {
+                { header }
                 { content }
             }
+        };
+        let body = if self.copied_at.is_some() {
+            let toast = ui! {
+                div["flex row items-center gap-1 p-2 rounded bg-surface-alt text-white text-sm"] {
+                    icon[Check, "size-14"]
+                    text["Copied"]
+                }
+            };
+            overlay(body, toast, "bottom-4 right-4").size_full()
+        } else {
+            body
+        };
+
+        let body = body
+            .bg(self.background("bg-dark"))
+            .children(menu)
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::on_key_down))
+            .id("main-view");
+        let view = cx.entity().downgrade();
+        declarative_ui::on_hover(body, move |event, _window, cx| {
+            let _ = view.update(cx, |this, cx| {
+                this.hovered = event.hovered;
+                cx.notify();
+            });
+        })
+    }
+}
+
+/// Payload and drag preview of a card being reordered
+#[derive(Clone)]
+struct DraggedCard {
+    name: String,
+    label: String,
+}
+
+impl Render for DraggedCard {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        ui! {
+            div["text-sm bold text-white bg-light-gray p-2"] {
+                text[self.label.clone()]
+            }
         }
+        .opacity(0.8)
     }
 }
 
@@ -136,50 +1711,263 @@ impl Render for AppView {
 // Interface Selector View
 // ============================================================================
 
-struct InterfaceSelectorView;
+struct InterfaceSelectorView {
+    /// Autostart state, read back from disk each time the window opens
+    launch_at_login: bool,
+    /// Cards on screen when last drawn, and whether each link is down - the accent
+    /// dots and grayed-out rows depend on them, the rates don't
+    cards: Vec<(String, bool)>,
+    /// Scrolling over the opacity value not yet worth a whole step, in lines
+    opacity_scroll: f32,
+}
+
+impl InterfaceSelectorView {
+    fn new(cx: &mut Context<Self>) -> Self {
+        cx.observe_global::<NetGaugeState>(|this, cx| {
+            let cards = Self::current_cards(cx);
+            if cards != this.cards {
+                this.cards = cards;
+                cx.notify();
+            }
+        })
+        .detach();
+
+        Self {
+            launch_at_login: autostart::is_enabled(),
+            cards: Self::current_cards(cx),
+            opacity_scroll: 0.0,
+        }
+    }
+
+    fn current_cards(cx: &App) -> Vec<(String, bool)> {
+        let state = cx.global::<NetGaugeState>();
+        state
+            .interfaces
+            .iter()
+            .map(|m| (m.name.clone(), m.is_down()))
+            .collect()
+    }
+}
+
+/// Change the window opacity by `delta` percent, within the allowed range
+fn step_window_opacity(delta: i16, cx: &mut App) {
+    cx.update_global::<NetGaugeState, _>(|state, _cx| {
+        let opacity =
+            (state.window_opacity as i16 + delta).clamp(MIN_WINDOW_OPACITY as i16, 100);
+        state.window_opacity = opacity as u8;
+        state.save_config();
+    });
+}
+
+fn open_interface_selector(cx: &mut App) {
+    let bounds = Bounds::centered(None, size(px(450.), px(400.)), cx);
+    cx.open_window(
+        WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            is_resizable: true,
+            ..Default::default()
+        },
+        |_window, cx| cx.new(InterfaceSelectorView::new),
+    )
+    .expect("Failed to open interface selector window");
+}
 
 impl Render for InterfaceSelectorView {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let state = cx.global::<NetGaugeState>();
-        let available = state.available_interfaces.clone();
-        let selected_lock = state.selected_interfaces.clone();
-
-        // Create the item renderer for the list
-        let selected_for_render = selected_lock.clone();
-        let available_for_render = available.clone();
-        let item_renderer = move |ix: usize| -> AnyElement {
-            let available = available_for_render.clone();
-            let selected_lock = selected_for_render.clone();
-            let selected = selected_lock.read().unwrap().clone();
-
-            let iface = available[ix].clone();
-            let is_selected = selected.contains(&iface);
-            let checkbox = if is_selected { "☑" } else { "☐" };
-            let label = format!("{} {}", checkbox, iface);
-
-            let iface_clone = iface.clone();
-            let selected_clone = selected_lock.clone();
-
-            styled_div("flex row items-center gap-2 p-2 bg-gray text-white cursor-pointer")
-                .child(label)
-                .on_mouse_down(gpui::MouseButton::Left, move |_ev, _window, cx| {
-                    let mut sel = selected_clone.write().unwrap();
-                    if sel.contains(&iface_clone) {
-                        sel.remove(&iface_clone);
-                    } else {
-                        sel.insert(iface_clone.clone());
-                    }
-                    cx.refresh_windows();
-                })
-                .into_any_element()
+        let selection = state.selection.clone();
+        let hide_noise = state.hide_noise;
+        // Hidden interfaces that are selected stay listed, so they can be deselected
+        let available: Vec<String> = state
+            .available_interfaces
+            .iter()
+            .filter(|name| {
+                !hide_noise
+                    || state.interesting_interfaces.contains(*name)
+                    || selection.contains(*name)
+            })
+            .cloned()
+            .collect();
+        let animate_transitions = state.animate_transitions;
+        let manual_order = state.sort_mode == SortMode::Manual;
+        let independent_sparklines = state.sparkline_scale == SparklineScale::Independent;
+        let window_opacity = state.window_opacity;
+
+        // Match the dot colors to the cards currently on screen
+        let accents = Arc::new(accent::assign_colors(
+            state.interfaces.iter().map(|m| m.name.as_str()),
+        ));
+        // Only selected interfaces are polled, so only those can be known to be down
+        let down: HashSet<String> = self
+            .cards
+            .iter()
+            .filter(|(_, down)| *down)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        // One row per interface; toggling updates the shared selection, which the
+        // poll task picks up (and saves) right away
+        let row = move |iface: &String, checkbox: Checkbox| -> AnyElement {
+            let dot_color = accents
+                .get(iface)
+                .copied()
+                .unwrap_or_else(|| accent::color_for_interface(iface));
+
+            // Selected rows get a blue outline
+            let border = if checkbox.is_checked() { "border-blue" } else { "border-light-gray" };
+            // Down links are grayed out, but stay selectable
+            let dim = if down.contains(iface) { "opacity-50" } else { "" };
+            let row_style = format!(
+                "flex row items-center gap-2 p-2 bg-gray text-white rounded border \
+                 hover:bg-light-gray {} {}",
+                border, dim
+            );
+            // Keyed by interface name so a row keeps its identity as the list changes
+            jsx! {
+                <div key={SharedString::from(iface.clone())} class={&row_style}> {
+                    (styled_div("size-8 flex-shrink-0").rounded_full().bg(dot_color))
+                    (checkbox.label(iface.clone()))
+                } </div>
+            }
+            .into_any_element()
         };
 
-        // Build the list element
-        let list = declarative_ui::styled_list(
+        // Build the list element; an empty list usually means enumeration was refused
+        let empty_state = ui! {
+            div["flex-grow p-4 text-sm text-dim"] {
+                text["No interfaces found — check permissions"]
+            }
+        };
+        let list = checkbox_list(
             "interface-list",
-            available.len(),
-            "flex-grow p-4",
-            item_renderer,
+            available,
+            &selection,
+            "flex-grow p-4 max-h-240",
+            row,
+            ListOptions {
+                empty: Some(empty_state.into_any_element()),
+                ..Default::default()
+            },
+        );
+
+        // Settings rows below the list share one look
+        const TOGGLE_ROW_STYLE: &str =
+            "flex row items-center p-4 bg-surface text-primary hover:bg-surface-alt";
+
+        // Card order - manual enables drag and drop on the main window
+        let order_toggle = styled_div(TOGGLE_ROW_STYLE).child(
+            checkbox(manual_order, "Arrange cards by dragging").on_toggle(cx.listener(
+                |_this, manual: &bool, _window, cx| {
+                    cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                        state.sort_mode =
+                            if *manual { SortMode::Manual } else { SortMode::Automatic };
+                        state.save_config();
+                    });
+                    cx.notify();
+                },
+            )),
+        );
+
+        // Window opacity stepper, with a caveat where the window can't really be see-through
+        let step_button = |label: &'static str, delta: i16| {
+            let at_limit = if delta < 0 {
+                window_opacity <= MIN_WINDOW_OPACITY
+            } else {
+                window_opacity >= 100
+            };
+            button(label)
+                .variant(ButtonVariant::Secondary)
+                .disabled(at_limit)
+                .child(label)
+                .on_click(move |_event, _window, cx| step_window_opacity(delta, cx))
+        };
+        let step = WINDOW_OPACITY_STEP as i16;
+        // Scrolling over the value steps it too, one step per line scrolled
+        let view = cx.entity().downgrade();
+        let opacity_value = declarative_ui::on_scroll(
+            styled_div("text-center min-w-40").child(format!("{}%", window_opacity)),
+            move |event, window, cx| {
+                let _ = view.update(cx, |this, cx| {
+                    this.opacity_scroll += event.delta.y / window.line_height();
+                    let lines = this.opacity_scroll.trunc();
+                    this.opacity_scroll -= lines;
+                    if lines != 0.0 {
+                        step_window_opacity(lines as i16 * step, cx);
+                    }
+                });
+            },
+        );
+        let opacity_setting = ui! {
+            div["flex col gap-1 p-4 bg-gray text-white"] {
+                div["flex row items-center justify-between"] {
+                    text["Window opacity"]
+                    div["flex row items-center gap-2"] {
+                        { step_button("−", -step) }
+                        { opacity_value }
+                        { step_button("+", step) }
+                    }
+                }
+            }
+        }
+        .children(TRANSPARENCY_CAVEAT.map(|note| styled_div("text-xs text-dim").child(note)));
+
+        // Launch-at-login toggle, greyed out where there is no autostart mechanism
+        let autostart_toggle = styled_div(TOGGLE_ROW_STYLE).child(
+            checkbox(self.launch_at_login, "Start netgauge when I log in")
+                .disabled(!autostart::is_supported())
+                .on_toggle(cx.listener(|this, enabled: &bool, _window, cx| {
+                    if let Err(e) = autostart::set_enabled(*enabled) {
+                        eprintln!("Failed to update launch at login: {}", e);
+                    }
+                    // Reflect what actually landed on disk
+                    this.launch_at_login = autostart::is_enabled();
+                    cx.notify();
+                })),
+        );
+
+        // Animation toggle - off snaps values instead of easing (saves battery)
+        let animation_toggle = styled_div(TOGGLE_ROW_STYLE).child(
+            checkbox(animate_transitions, "Animate transitions").on_toggle(cx.listener(
+                |_this, animate: &bool, _window, cx| {
+                    cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                        state.animate_transitions = *animate;
+                        state.save_config();
+                    });
+                    cx.notify();
+                },
+            )),
+        );
+
+        // Sparkline scaling - shared compares cards, independent shows each one's shape
+        let sparkline_toggle = styled_div(TOGGLE_ROW_STYLE).child(
+            checkbox(
+                independent_sparklines,
+                "Scale each sparkline to its own peak",
+            )
+            .on_toggle(cx.listener(|_this, independent: &bool, _window, cx| {
+                cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                    state.sparkline_scale = if *independent {
+                        SparklineScale::Independent
+                    } else {
+                        SparklineScale::Shared
+                    };
+                    state.save_config();
+                });
+                cx.notify();
+            })),
+        );
+
+        // Noise toggle - loopback, veth/docker links and unused down interfaces
+        let noise_toggle = styled_div(TOGGLE_ROW_STYLE).child(
+            checkbox(hide_noise, "Hide loopback and container interfaces").on_toggle(cx.listener(
+                |_this, hide: &bool, _window, cx| {
+                    cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                        state.hide_noise = *hide;
+                        state.save_config();
+                    });
+                    cx.notify();
+                },
+            )),
         );
 
         ui! {
@@ -188,6 +1976,131 @@ impl Render for InterfaceSelectorView {
                     text["Select Interfaces"]
                 }
                 { list }
+                { noise_toggle }
+                { animation_toggle }
+                { sparkline_toggle }
+                { order_toggle }
+                { opacity_setting }
+                { autostart_toggle }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Details View
+// ============================================================================
+
+/// Everything known about one interface, opened from the card context menu
+struct DetailsView {
+    name: String,
+}
+
+fn open_details_window(name: &str, cx: &mut App) {
+    let name = name.to_string();
+    let bounds = Bounds::centered(None, size(px(360.), px(300.)), cx);
+    cx.open_window(
+        WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            is_resizable: true,
+            ..Default::default()
+        },
+        |_window, cx| cx.new(|cx| DetailsView::new(name, cx)),
+    )
+    .expect("Failed to open details window");
+}
+
+impl DetailsView {
+    fn new(name: String, cx: &mut Context<Self>) -> Self {
+        cx.observe_global::<NetGaugeState>(|_this, cx| cx.notify()).detach();
+        Self { name }
+    }
+}
+
+impl Render for DetailsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let state = cx.global::<NetGaugeState>();
+        let metric = state.interfaces.iter().find(|m| m.name == self.name);
+
+        let row = |label: &str, value: String| {
+            ui! {
+                div["flex row justify-between gap-4 text-sm"] {
+                    div["text-dim"] { text[label.to_string()] }
+                    div["text-white"] { text[value] }
+                }
+            }
+        };
+        let rate = |bytes: u64| {
+            format!(
+                "{} ({})",
+                format::human_bytes_per_sec(bytes),
+                format::human_bits_per_sec(bytes)
+            )
+        };
+
+        let mut rows = styled_div("flex col gap-2 p-4");
+        rows = rows.child(row("Interface", self.name.clone()));
+        rows = rows.child(row("Name", state.display_name(&self.name).to_string()));
+
+        match metric {
+            Some(m) => {
+                let kind = if m.is_wan { "WAN (SNMP)" } else { "Local" };
+                let utilization = m
+                    .utilization
+                    .map(|u| format!("{:.0}%", u * 100.0))
+                    .unwrap_or_else(|| "unknown".to_string());
+                let link_speed = m
+                    .link_speed_bps
+                    .map(|bps| format::human_bits_per_sec(bps / 8))
+                    .unwrap_or_else(|| "unknown".to_string());
+                let peak = state
+                    .history
+                    .get(&m.name)
+                    .and_then(|samples| samples.iter().copied().max())
+                    .unwrap_or(0);
+                let peak_label = jsx! {
+                    <div
+                        class={"text-dim"}
+                        id={"peak"}
+                        tooltip={format!(
+                            "Highest download or upload rate over the last {} polls",
+                            SPARKLINE_SAMPLES
+                        )}
+                    > {
+                        <text>{format!("Peak ({}s)", SPARKLINE_SAMPLES)}</text>
+                    } </div>
+                };
+
+                rows = rows
+                    .child(row("Kind", kind.to_string()))
+                    .child(row("Download", rate(m.rx_rate)))
+                    .child(row("Upload", rate(m.tx_rate)))
+                    .child(row("Utilization", utilization))
+                    .child(row("Link speed", link_speed))
+                    .child(row(
+                        "Default route",
+                        (state.default_route.as_ref() == Some(&m.name)).to_string(),
+                    ))
+                    .child(ui! {
+                        div["flex row justify-between gap-4 text-sm"] {
+                            { peak_label }
+                            div["text-white"] { text[rate(peak)] }
+                        }
+                    });
+            }
+            None => {
+                rows = rows.child(ui! {
+                    div["text-sm text-dim"] { text["Not currently monitored"] }
+                });
+            }
+        }
+
+        ui! {
+            div["flex col bg-dark size-full"] {
+                div["flex row items-center p-4 bg-gray bold text-white"] {
+                    text[format!("Details — {}", state.display_name(&self.name))]
+                }
+                { rows }
             }
         }
     }
@@ -198,114 +2111,265 @@ impl Render for InterfaceSelectorView {
 // ============================================================================
 
 fn main() {
-    Application::new().run(|cx: &mut App| {
-        // Check SNMP availability and auto-detect ppp interface
-        let snmp_available = is_snmp_available(SNMP_TARGET, SNMP_COMMUNITY);
-        let wan_interface = if snmp_available {
-            detect_interface_index(SNMP_TARGET, SNMP_COMMUNITY, SNMP_IF_PATTERN)
+    let app = Application::new().with_assets(icons::Assets);
+    app.run(|cx: &mut App| {
+        declarative_ui::set_palette(dark_palette());
+
+        let config = Config::load();
+
+        // Check SNMP availability and resolve each target's interfaces,
+        // auto-detecting the ppp interface on the built-in router by default
+        let snmp_targets = if config.snmp_targets.is_empty() {
+            vec![SnmpTarget {
+                label: "router".to_string(),
+                address: SNMP_TARGET.to_string(),
+                community: Some(String::from_utf8_lossy(SNMP_COMMUNITY).to_string()),
+                hc_counters: Some(SNMP_HC_COUNTERS),
+                v3: SNMP_V3_USER.map(|user| SnmpV3Credentials {
+                    user: user.to_string(),
+                    auth_protocol: Some(SNMP_V3_AUTH_PROTOCOL.to_string()),
+                    auth_password: SNMP_V3_AUTH_PASSWORD.to_string(),
+                    priv_protocol: Some(SNMP_V3_PRIV_PROTOCOL.to_string()),
+                    priv_password: SNMP_V3_PRIV_PASSWORD.map(str::to_string),
+                }),
+                interfaces: vec![SnmpInterface::Pattern(SNMP_IF_PATTERN.to_string())],
+            }]
         } else {
-            None
+            config.snmp_targets.clone()
+        };
+        let wan_sources = resolve_wan_sources(&snmp_targets);
+        let snmp_available = !wan_sources.is_empty();
+        let snmp_info = if snmp_available {
+            wan_sources
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{} — ifIndex {} on {}",
+                        s.card_name, s.if_index, s.config.target
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            let addresses: Vec<_> = snmp_targets.iter().map(|t| t.address.as_str()).collect();
+            format!("No SNMP agent answered at {}", addresses.join(", "))
         };
-
-        // Log detected interface
-        if let Some((idx, name)) = &wan_interface {
-            println!("Auto-detected WAN interface: {} (index {})", name, idx);
-        } else if snmp_available {
-            println!("SNMP available but no '{}' interface found", SNMP_IF_PATTERN);
-        }
 
         // Get available interfaces
         let available_interfaces = list_interfaces();
+        let interesting_interfaces = list_interesting_interfaces();
+        let default_route = default_route_interface();
 
-        // Default selected interfaces
-        let default_selected: InterfaceSet = ["eth0", "wlan0", "en0", "WiFi", "Ethernet"]
+        // Use the saved selection; on first run default to the physical NICs that
+        // are up, falling back to the default route
+        let selected = match config.selected {
+            Some(saved) => saved.into_iter().collect(),
+            None => {
+                let mut selected: InterfaceSet = physical_interfaces().into_iter().collect();
+                if selected.is_empty() {
+                    selected.extend(default_route.clone());
+                }
+                selected
+            }
+        };
+
+        // Named skeleton cards for the selection, shown until the first poll lands
+        let initial_cards = available_interfaces
             .iter()
-            .map(|s| s.to_string())
+            .filter(|name| selected.contains(name))
+            .map(|name| InterfaceMetric::skeleton(name, InterfaceType::Net))
             .collect();
-        let selected_interfaces = Arc::new(RwLock::new(default_selected));
+        let selection = Selection::new(selected);
+        let last_updated = Arc::new(RwLock::new(HashMap::new()));
+        let peak_resets = Arc::new(Mutex::new(Vec::new()));
 
         // Initialize global state
         cx.set_global(NetGaugeState {
-            interfaces: vec![InterfaceMetric {
-                name: "Loading...".to_string(),
-                rx_speed: "-- B/s".to_string(),
-                tx_speed: "-- B/s".to_string(),
-                is_wan: false,
-            }],
+            interfaces: initial_cards,
             snmp_available,
+            snmp_info,
             available_interfaces,
-            selected_interfaces: selected_interfaces.clone(),
+            interesting_interfaces,
+            hide_noise: config.hide_noise.unwrap_or(HIDE_NOISY_INTERFACES),
+            selection: selection.clone(),
+            poll_error: None,
+            local_error: None,
+            history: HashMap::new(),
+            default_route,
+            router_rtt: HashMap::new(),
+            internet_rtt: Rtt::Pending,
+            animate_transitions: config.animate.unwrap_or(ANIMATE_TRANSITIONS),
+            aliases: config.aliases,
+            window_opacity: config.opacity.unwrap_or(100).clamp(MIN_WINDOW_OPACITY, 100),
+            sort_mode: config.sort.unwrap_or_default(),
+            sparkline_scale: config.sparkline_scale.unwrap_or_default(),
+            card_order: config.order,
+            last_updated: last_updated.clone(),
+            peak_resets: peak_resets.clone(),
+        });
+
+        let has_wan = !wan_sources.is_empty();
+
+        // Optional HTTP endpoint serving the latest state snapshot
+        #[cfg(feature = "http")]
+        let http_snapshot = HTTP_STATUS_ADDR.and_then(|addr| {
+            match http::serve(addr, cx.global::<NetGaugeState>().clone()) {
+                Ok(snapshot) => {
+                    println!("Serving stats on http://{}/stats", addr);
+                    Some(snapshot)
+                }
+                Err(e) => {
+                    eprintln!("Failed to start HTTP status endpoint on {}: {}", addr, e);
+                    None
+                }
+            }
         });
 
         // Spawn background polling task
-        let selected_for_task = selected_interfaces.clone();
+        let last_updated_for_task = last_updated.clone();
         cx.spawn(async move |cx: &mut AsyncApp| {
-            let mut tracker = DeltaTracker::new();
+            let mut poller = Poller::new(wan_sources);
+            let mut saved_version = selection.version();
 
             loop {
-                // Get current selected interfaces
-                let selected = selected_for_task.read().unwrap().clone();
+                // Read before the snapshot, so the snapshot is at least this new
+                let version = selection.version();
+                poller.reset_peaks(&peak_resets);
+                // Watchdog: a panic anywhere in the cycle must not kill the task
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    poller.poll(&selection.snapshot().into_iter().collect())
+                }));
 
-                // Fetch local interface stats
-                let stats = fetch_net_stats(&selected);
-                let deltas = tracker.update(&stats);
-
-                let mut metrics: Vec<InterfaceMetric> = deltas
-                    .iter()
-                    .map(|d| InterfaceMetric {
-                        name: d.interface.clone(),
-                        rx_speed: format::human_bytes_per_sec(d.rx_delta),
-                        tx_speed: format::human_bytes_per_sec(d.tx_delta),
-                        is_wan: d.kind == InterfaceType::Wan,
-                    })
-                    .collect();
+                // Persist selector changes once they are in effect
+                if version != saved_version {
+                    saved_version = version;
+                    let _ = cx.read_global::<NetGaugeState, _>(|state, _cx| state.save_config());
+                }
 
-                // Fetch WAN stats via SNMP if available and interface detected
-                if let Some((if_index, ref if_name)) = wan_interface {
-                    let display_name = format!("{} (WAN)", if_name);
-                    let wan_stats =
-                        fetch_wan_stats(SNMP_TARGET, SNMP_COMMUNITY, if_index, &display_name);
-                    let wan_deltas = tracker.update(&[wan_stats]);
-                    for d in wan_deltas {
-                        metrics.push(InterfaceMetric {
-                            name: d.interface.clone(),
-                            rx_speed: format::human_bytes_per_sec(d.rx_delta),
-                            tx_speed: format::human_bytes_per_sec(d.tx_delta),
-                            is_wan: true,
-                        });
+                let (metrics, router_rtt, poll_error, delay) = match result {
+                    Ok((metrics, router_rtt)) => {
+                        poller.record_updates(&metrics, &last_updated_for_task);
+                        // SNMP runs off this task, so a silent router can't hold up local cards
+                        if let Some((wan, slot)) = poller.start_wan_fetch() {
+                            cx.background_executor()
+                                .spawn(async move { wan.fetch_into(&slot) })
+                                .detach();
+                        }
+                        (Some(metrics), router_rtt, None, poller.until_next_due())
                     }
-                }
+                    Err(payload) => {
+                        let msg = panic_message(payload.as_ref());
+                        eprintln!(
+                            "Poll cycle panicked: {} (retrying in {:?})",
+                            msg, WATCHDOG_RETRY_DELAY
+                        );
+                        // The tracker may have been left half-updated
+                        let wan_sources = std::mem::take(&mut poller.wan_sources);
+                        // An in-flight fetch reports to the old poller, so don't keep a stale RTT
+                        let router_rtt = wan_sources
+                            .iter()
+                            .map(|source| (source.card_name.clone(), Rtt::Timeout))
+                            .collect();
+                        poller = Poller::new(wan_sources);
+                        (None, router_rtt, Some(msg), WATCHDOG_RETRY_DELAY)
+                    }
+                };
 
-                // Update global state and refresh windows
-                let _ = cx.update_global::<NetGaugeState, _>(|state, cx| {
-                    if metrics.is_empty() {
-                        state.interfaces = vec![InterfaceMetric {
-                            name: "No interfaces found".to_string(),
-                            rx_speed: "-- B/s".to_string(),
-                            tx_speed: "-- B/s".to_string(),
-                            is_wan: false,
-                        }];
-                    } else {
+                // Carry render state over and skip the update entirely when nothing
+                // visible changed, so an idle system doesn't redraw every tick
+                let update = cx.read_global::<NetGaugeState, _>(|state, _cx| {
+                    let metrics = metrics.map(|mut metrics| {
+                        for m in &mut metrics {
+                            let previous = state.interfaces.iter().find(|p| p.name == m.name);
+                            m.ease_utilization(previous);
+                            m.track_link_state(previous);
+                            m.track_rate_jump(previous);
+                        }
+                        metrics
+                    });
+                    let changed = state.needs_redraw(
+                        metrics.as_deref(),
+                        &poll_error,
+                        &poller.local_error,
+                        &router_rtt,
+                    );
+                    (metrics, changed)
+                });
+                let Ok((metrics, true)) = update else {
+                    wait_for_poll(&mut poller, &selection, version, delay).await;
+                    continue;
+                };
+
+                // Update global state; views observing it redraw themselves
+                let _ = cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                    // On a crashed cycle keep the last known cards under the banner
+                    if let Some(metrics) = metrics {
                         state.interfaces = metrics;
+                        state.record_history();
+                    }
+                    state.poll_error = poll_error;
+                    state.local_error = poller.local_error.clone();
+                    // Only refreshed for the WAN cards actually polled this round
+                    state.router_rtt.extend(router_rtt);
+
+                    #[cfg(feature = "http")]
+                    if let Some(snapshot) = &http_snapshot {
+                        *snapshot.write().unwrap() = state.clone();
                     }
-                    // Trigger window redraw
-                    cx.refresh_windows();
                 });
 
-                Timer::after(Duration::from_secs(1)).await;
+                wait_for_poll(&mut poller, &selection, version, delay).await;
             }
         })
         .detach();
 
+        // Probe internet latency off the main poll loop, only when a WAN card exists
+        if has_wan {
+            cx.spawn(async move |cx: &mut AsyncApp| loop {
+                let rtt = cx
+                    .background_executor()
+                    .spawn(async { tcp_connect_rtt(LATENCY_PROBE_TARGET, LATENCY_PROBE_TIMEOUT) })
+                    .await;
+
+                let rtt = rtt.map_or(Rtt::Timeout, Rtt::Measured);
+                let changed = cx
+                    .read_global::<NetGaugeState, _>(|state, _cx| {
+                        rtt.label() != state.internet_rtt.label()
+                    })
+                    .unwrap_or(false);
+                if changed {
+                    let _ = cx.update_global::<NetGaugeState, _>(|state, _cx| {
+                        state.internet_rtt = rtt;
+                    });
+                }
+
+                Timer::after(LATENCY_PROBE_INTERVAL).await;
+            })
+            .detach();
+        }
+
+        // Keep the login entry pointing at this binary if it has moved since
+        if let Err(e) = autostart::refresh() {
+            eprintln!("Failed to refresh launch at login entry: {}", e);
+        }
+
         // Open window - compact height, non-resizable (disables maximize)
         let bounds = Bounds::centered(None, size(px(400.), px(300.)), cx);
         cx.open_window(
             WindowOptions {
                 window_bounds: Some(WindowBounds::Windowed(bounds)),
                 is_resizable: false,
+                // Lets the opacity setting show what's behind; at 100% the view paints opaque
+                window_background: WindowBackgroundAppearance::Transparent,
                 ..Default::default()
             },
-            |_window, cx| cx.new(|_cx| AppView),
+            |window, cx| {
+                // Started at login: stay out of the way until the user looks
+                if autostart::launched_at_login() {
+                    window.minimize_window();
+                }
+                cx.new(AppView::new)
+            },
         )
         .expect("Failed to open window");
     });
@@ -1,77 +1,100 @@
 //! SNMP interface discovery tool
 //! Run with: cargo run --bin snmp_discover
+//!
+//! For SNMPv3 (build with `--features snmpv3`):
+//! cargo run --features snmpv3 --bin snmp_discover -- --v3 --user <name> --auth-password <pw>
+//!     [--auth-protocol sha] [--priv-password <pw> [--priv-protocol aes]]
 
-use snmp2::{Oid, SyncSession, Value};
+use netgauge::{SnmpAuth, SnmpClient, SnmpConfig, SnmpError};
 use std::time::Duration;
 
 const SNMP_TARGET: &str = "192.168.1.1:161";
 const SNMP_COMMUNITY: &[u8] = b"public";
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let auth = match parse_auth(&args) {
+        Ok(auth) => auth,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
     println!("Discovering SNMP interfaces on {}...\n", SNMP_TARGET);
 
-    let timeout = Duration::from_secs(5);
-    let mut sess = match SyncSession::new_v2c(SNMP_TARGET, SNMP_COMMUNITY, Some(timeout), 0) {
-        Ok(s) => s,
+    // Discovery is a one-off, so wait longer than the monitor does
+    let config = SnmpConfig::new(SNMP_TARGET, auth).with_timeout(Duration::from_secs(5));
+    let mut client = match SnmpClient::new(&config) {
+        Ok(client) => client,
         Err(e) => {
-            println!("Failed to connect: {:?}", e);
+            println!("Failed to connect: {}", e);
             println!("\nMake sure:");
             println!("  1. SNMP is enabled on your router");
-            println!("  2. Community string is correct (try 'public' or 'private')");
+            if let SnmpError::Engine(_) = e {
+                println!("  2. User name, passwords and protocols match the router's SNMPv3 user");
+            } else {
+                println!("  2. Community string is correct (try 'public' or 'private')");
+            }
             println!("  3. Router IP is correct ({})", SNMP_TARGET);
             return;
         }
     };
+    let interfaces = match client.list_interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(e) => {
+            println!("Failed to list interfaces: {}", e);
+            return;
+        }
+    };
 
-    // Walk ifDescr (1.3.6.1.2.1.2.2.1.2) to get interface names
-    // Walk ifInOctets (1.3.6.1.2.1.2.2.1.10) to get RX bytes
-    // Walk ifOutOctets (1.3.6.1.2.1.2.2.1.16) to get TX bytes
-
-    println!("{:<6} {:<30} {:>15} {:>15}", "Index", "Interface Name", "RX Bytes", "TX Bytes");
-    println!("{}", "-".repeat(70));
-
-    // Try indexes 1-50 (most routers have fewer interfaces)
-    for idx in 1u64..=50 {
-        let descr_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 2, idx]).unwrap();
-        let rx_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, idx]).unwrap();
-        let tx_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 16, idx]).unwrap();
-
-        // Get interface description
-        let name = match sess.get(&descr_oid) {
-            Ok(resp) => match resp.varbinds.into_iter().next() {
-                Some((_, Value::OctetString(bytes))) => {
-                    String::from_utf8_lossy(&bytes).to_string()
-                }
-                _ => continue, // No interface at this index
-            },
-            Err(_) => continue,
-        };
-
-        // Get RX bytes
-        let rx = match sess.get(&rx_oid) {
-            Ok(resp) => match resp.varbinds.into_iter().next() {
-                Some((_, Value::Counter32(v))) => v as u64,
-                Some((_, Value::Counter64(v))) => v,
-                _ => 0,
-            },
-            Err(_) => 0,
-        };
-
-        // Get TX bytes
-        let tx = match sess.get(&tx_oid) {
-            Ok(resp) => match resp.varbinds.into_iter().next() {
-                Some((_, Value::Counter32(v))) => v as u64,
-                Some((_, Value::Counter64(v))) => v,
-                _ => 0,
-            },
-            Err(_) => 0,
-        };
+    println!(
+        "{:<6} {:<30} {:>15} {:>15} {:<10}",
+        "Index", "Interface Name", "RX Bytes", "TX Bytes", "Status"
+    );
+    println!("{}", "-".repeat(81));
 
-        // Print interface info
-        println!("{:<6} {:<30} {:>15} {:>15}", idx, name, rx, tx);
+    for iface in &interfaces {
+        let status = iface.oper_status.map(|s| s.to_string()).unwrap_or_default();
+        println!(
+            "{:<6} {:<30} {:>15} {:>15} {:<10}",
+            iface.index, iface.name, iface.rx_bytes, iface.tx_bytes, status
+        );
     }
 
     println!("\n** Look for WAN/Internet/ppp/eth interfaces with high byte counts **");
     println!("** Use that index number in SNMP_IF_INDEX in main.rs **");
 }
 
+/// v2c with SNMP_COMMUNITY, or v3 when `--v3` is given (SHA/AES unless overridden;
+/// authNoPriv without `--priv-password`)
+fn parse_auth(args: &[String]) -> Result<SnmpAuth, String> {
+    if !args.iter().any(|arg| arg == "--v3") {
+        return Ok(SnmpAuth::V2c {
+            community: SNMP_COMMUNITY.to_vec(),
+        });
+    }
+
+    let value = |flag: &str| {
+        let at = args.iter().position(|arg| arg == flag)?;
+        args.get(at + 1).cloned()
+    };
+    let protocol = |flag: &str, default: &str| value(flag).unwrap_or_else(|| default.to_string());
+
+    let user = value("--user").ok_or("--v3 needs --user <name>")?;
+    let auth_password = value("--auth-password").ok_or("--v3 needs --auth-password <pw>")?;
+    let auth_protocol = protocol("--auth-protocol", "sha").parse()?;
+    let priv_password = value("--priv-password");
+    let priv_protocol = match priv_password {
+        Some(_) => Some(protocol("--priv-protocol", "aes").parse()?),
+        None => None,
+    };
+
+    Ok(SnmpAuth::V3 {
+        user,
+        auth_protocol,
+        auth_password,
+        priv_protocol,
+        priv_password: priv_password.unwrap_or_default(),
+    })
+}
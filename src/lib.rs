@@ -1,9 +1,19 @@
 pub mod net;
 
+pub use net::default_route_interface;
 pub use net::fetch_net_stats;
+pub use net::filter;
+pub use net::filter::{exclude_noninteresting, list_interesting_interfaces};
 pub use net::format;
 pub use net::list_interfaces;
-pub use net::net::{InterfaceSet, InterfaceStats, InterfaceType};
-pub use net::tracker::{DeltaTracker, NetDelta};
-pub use net::wan::snmp::{detect_interface_index, fetch_wan_stats, is_snmp_available};
-
+pub use net::net::{CounterWidth, InterfaceSet, InterfaceStats, InterfaceType, NetStatsError};
+pub use net::physical_interfaces;
+pub use net::tracker::{DeltaTracker, FirstSample, InterfaceSummary, NetDelta, Sample};
+pub use net::wan::latency::tcp_connect_rtt;
+pub use net::wan::snmp::{
+    detect_interface_index, detect_interface_indices, detect_interfaces, fetch_wan_info,
+    fetch_wan_stats, fetch_wan_stats_async, fetch_wan_stats_many, interface_name,
+    is_snmp_available, is_snmp_available_async, list_snmp_interfaces, HcSupport, OperStatus,
+    SnmpAuth, SnmpAuthProtocol, SnmpClient, SnmpConfig, SnmpError, SnmpInterface, SnmpPrivProtocol,
+    WanCounterSource, WanInfo, WanTarget, SNMP_PORT, SNMP_RETRIES, SNMP_TIMEOUT,
+};
@@ -1,48 +1,173 @@
-mod net;
-
-use net::wan::snmp::{fetch_wan_stats, is_snmp_available};
-use net::{fetch_net_stats, format, net::InterfaceType, tracker::DeltaTracker};
-use std::collections::HashSet;
+use netgauge::{
+    DeltaTracker, InterfaceSet, InterfaceType, SnmpClient, SnmpConfig, WanCounterSource, WanTarget,
+    fetch_net_stats, format,
+};
 use std::thread::sleep;
 use std::time::Duration;
 
+/// Span of the average printed next to each rate
+const AVERAGE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Print session totals and peaks every this many polls
+const SUMMARY_EVERY: u32 = 60;
+
 fn main() {
-    let selected: HashSet<String> = ["eth0", "wlan0", "en0", "Wi-Fi"]
-        .iter()
-        .map(|s| s.to_string())
-        .collect();
+    // Interface names or patterns from the command line (e.g. `netgauge eth0 'wl*'`);
+    // none means every interface. `--wan host[:port]:index:label` adds a router
+    // interface and can be repeated
+    let mut patterns = Vec::new();
+    let mut wan_targets = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg != "--wan" {
+            patterns.push(arg);
+            continue;
+        }
+        match parse_wan(&args.next().unwrap_or_default()) {
+            Ok(target) => wan_targets.push(target),
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        }
+    }
+    let selected: InterfaceSet = patterns.into_iter().collect();
+    if wan_targets.is_empty() {
+        let config = SnmpConfig::v2c("192.168.1.1", b"public");
+        wan_targets.push(WanTarget::new(config, 42, "ppp0 (WAN)"));
+    }
 
-    let snmp_ok = is_snmp_available("192.168.1.1:161", b"public");
-    let mut tracker = DeltaTracker::new();
+    // One session per WAN for the whole run; None if its router didn't answer at startup
+    let mut sessions: Vec<Option<SnmpClient>> = Vec::with_capacity(wan_targets.len());
+    for target in &wan_targets {
+        let mut snmp = SnmpClient::new(&target.config).ok();
+        if !snmp.as_mut().is_some_and(|client| client.is_available()) {
+            snmp = None;
+        }
+        if let Some(client) = &mut snmp {
+            match client.wan_info(target.if_index) {
+                Ok(info) => println!(
+                    "{}: link {}, {}",
+                    target.label,
+                    if info.oper_up { "up" } else { "down" },
+                    info.speed_mbps
+                        .map_or("speed unknown".to_string(), |mbps| format!("{} Mbps", mbps))
+                ),
+                Err(e) => eprintln!("{}: can't read link info: {}", target.label, e),
+            }
+        }
+        sessions.push(snmp);
+    }
+    // One sample per second, enough for the average column
+    let mut tracker = DeltaTracker::new().with_history(AVERAGE_WINDOW.as_secs() as usize);
+    // Remembers whether the router has HC counters, so a fallback costs one request per poll
+    let mut wan_counters = WanCounterSource::new();
+    let mut polls: u32 = 0;
+    let mut deltas = Vec::new();
 
     loop {
-        let net_stats = fetch_net_stats(&selected);
-        let mut all_stats = net_stats;
-
-        if snmp_ok {
-            let wan_stats = fetch_wan_stats("192.168.1.1:161", b"public", 42, "ppp0 (WAN)");
-            all_stats.push(wan_stats);
-        } else {
-            println!("SNMP unavailable!");
+        let mut all_stats = match fetch_net_stats(&selected) {
+            Ok(stats) => {
+                // Interfaces that went away start from a fresh baseline if they return
+                let live: Vec<&str> = stats
+                    .iter()
+                    .map(|s| s.interface.as_str())
+                    .chain(wan_targets.iter().map(|t| t.label.as_str()))
+                    .collect();
+                tracker.prune(&live);
+                stats
+            }
+            Err(e) => {
+                eprintln!("Can't read network interfaces: {}", e);
+                Vec::new()
+            }
+        };
+
+        // A router that fails only costs its own card
+        for (target, snmp) in wan_targets.iter().zip(&mut sessions) {
+            let Some(client) = snmp else {
+                println!("{}: SNMP unavailable!", target.label);
+                continue;
+            };
+            let was_fallback = wan_counters.is_fallback(client.target(), target.if_index);
+            match wan_counters.fetch(client, target.if_index, &target.label) {
+                Ok(wan_stats) => {
+                    if !was_fallback && wan_counters.is_fallback(client.target(), target.if_index) {
+                        eprintln!(
+                            "{}: no working 64-bit counters, using ifInOctets/ifOutOctets",
+                            target.label
+                        );
+                    }
+                    all_stats.push(wan_stats)
+                }
+                Err(e) => eprintln!("{}: can't read WAN counters: {}", target.label, e),
+            }
         }
 
-        let deltas = tracker.update(&all_stats);
-        for d in deltas {
+        tracker.update_into(&all_stats, &mut deltas);
+        for d in &deltas {
+            if d.restarted {
+                println!("{}: router restarted, re-baselining", d.interface);
+            }
+            let (rx_rate, tx_rate) = (d.rx_bytes_per_sec() as u64, d.tx_bytes_per_sec() as u64);
             let label = match d.kind {
                 InterfaceType::Net => "Net interface",
                 InterfaceType::Wan => "WAN interface",
+                InterfaceType::Loopback => "Loopback",
+                InterfaceType::Wireless => "Wireless interface",
+                InterfaceType::Virtual => "Virtual interface",
+                InterfaceType::Vpn => "VPN",
+                InterfaceType::Bridge => "Bridge",
             };
+            let (rx_avg, tx_avg) = tracker
+                .avg_over(&d.interface, AVERAGE_WINDOW)
+                .unwrap_or((0.0, 0.0));
             println!(
-                "{} ({}) → RX: {} ({}) TX: {} ({})",
+                "{} ({}) → RX: {} ({}) TX: {} ({}) | {}s avg RX: {} TX: {}",
                 d.interface,
                 label,
-                format::human_bytes_per_sec(d.rx_delta),
-                format::human_bits_per_sec(d.rx_delta),
-                format::human_bytes_per_sec(d.tx_delta),
-                format::human_bits_per_sec(d.tx_delta),
+                format::human_bytes_per_sec_f64(d.rx_bytes_per_sec()),
+                format::human_bits_per_sec(rx_rate),
+                format::human_bytes_per_sec_f64(d.tx_bytes_per_sec()),
+                format::human_bits_per_sec(tx_rate),
+                AVERAGE_WINDOW.as_secs(),
+                format::human_bytes_per_sec_f64(rx_avg),
+                format::human_bytes_per_sec_f64(tx_avg),
             );
         }
 
+        polls = polls.wrapping_add(1);
+        if polls.is_multiple_of(SUMMARY_EVERY) {
+            for d in &deltas {
+                let Some(summary) = tracker.summary(&d.interface) else {
+                    continue;
+                };
+                println!(
+                    "{} this session: ↓ {} ↑ {}, peak ↓ {} ↑ {}",
+                    d.interface,
+                    format::human_bytes(summary.session_rx_total),
+                    format::human_bytes(summary.session_tx_total),
+                    format::human_bytes_per_sec(summary.peak_rx),
+                    format::human_bytes_per_sec(summary.peak_tx),
+                );
+            }
+        }
+
         sleep(Duration::from_secs(1));
     }
 }
+
+/// Parse `host[:port]:index:label`, using the `public` community
+fn parse_wan(spec: &str) -> Result<WanTarget, String> {
+    let usage = || format!("bad --wan {:?}, expected host[:port]:index:label", spec);
+    let mut parts = spec.rsplitn(3, ':');
+    let (Some(label), Some(index), Some(host)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(usage());
+    };
+    let if_index = index.parse().map_err(|_| usage())?;
+    if host.is_empty() || label.is_empty() {
+        return Err(usage());
+    }
+    let config = SnmpConfig::v2c(host, b"public");
+    Ok(WanTarget::new(config, if_index, label))
+}
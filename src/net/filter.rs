@@ -0,0 +1,159 @@
+use crate::net::fetch_net_stats;
+use crate::net::net::{InterfaceSet, InterfaceStats, InterfaceType};
+
+/// A keep-this-interface test; true keeps it, so a rule plugs straight into
+/// `Iterator::filter`, and `all_of` chains several
+pub type Predicate = fn(&InterfaceStats) -> bool;
+
+/// The rules `exclude_noninteresting` applies
+pub const DEFAULT_RULES: &[Predicate] = &[not_loopback, not_container_link, not_idle_down];
+
+/// Name prefixes of links container runtimes create per container or network
+const CONTAINER_PREFIXES: &[&str] = &["veth", "docker", "br-"];
+
+/// Keep everything but loopback
+pub fn not_loopback(s: &InterfaceStats) -> bool {
+    s.kind != InterfaceType::Loopback
+}
+
+/// Keep everything but container plumbing: `veth*` pairs, `docker*` and the
+/// `br-*` bridges Docker makes for user networks
+pub fn not_container_link(s: &InterfaceStats) -> bool {
+    !CONTAINER_PREFIXES
+        .iter()
+        .any(|prefix| s.interface.starts_with(prefix))
+}
+
+/// Keep everything but interfaces that are down and never moved a byte; one
+/// with unknown link state is kept
+pub fn not_idle_down(s: &InterfaceStats) -> bool {
+    !(s.is_up == Some(false) && s.rx_bytes == 0 && s.tx_bytes == 0)
+}
+
+/// A predicate that keeps what every one of `rules` keeps
+pub fn all_of(rules: &[Predicate]) -> impl Fn(&InterfaceStats) -> bool + '_ {
+    move |s| rules.iter().all(|rule| rule(s))
+}
+
+/// `stats` without loopback, container links and idle down interfaces
+pub fn exclude_noninteresting(stats: Vec<InterfaceStats>) -> Vec<InterfaceStats> {
+    let keep = all_of(DEFAULT_RULES);
+    stats.into_iter().filter(|s| keep(s)).collect()
+}
+
+/// Names of the interfaces `exclude_noninteresting` keeps, sorted; empty if
+/// the counters can't be read
+///
+/// Unlike `physical_interfaces` this keeps VPNs, bridges and links that are
+/// down but have carried traffic, for a selector that hides only the noise.
+pub fn list_interesting_interfaces() -> Vec<String> {
+    let Ok(stats) = fetch_net_stats(&InterfaceSet::new()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = exclude_noninteresting(stats)
+        .into_iter()
+        .map(|s| s.interface)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::net::CounterWidth;
+
+    fn stats(interface: &str, kind: InterfaceType) -> InterfaceStats {
+        InterfaceStats {
+            interface: interface.to_string(),
+            rx_bytes: 1000,
+            tx_bytes: 1000,
+            rx_packets: None,
+            tx_packets: None,
+            rx_errors: None,
+            tx_errors: None,
+            rx_dropped: None,
+            tx_dropped: None,
+            is_up: Some(true),
+            link_speed_bps: None,
+            kind,
+            target: None,
+            counter_width: CounterWidth::Bits64,
+            uptime: None,
+        }
+    }
+
+    #[test]
+    fn loopback_is_dropped_by_kind() {
+        assert!(!not_loopback(&stats("lo", InterfaceType::Loopback)));
+        assert!(not_loopback(&stats("eth0", InterfaceType::Net)));
+        // The name alone doesn't make it loopback
+        assert!(not_loopback(&stats("lo", InterfaceType::Net)));
+    }
+
+    #[test]
+    fn container_links_are_dropped_by_name() {
+        for name in ["veth1a2b3c", "docker0", "br-5f2c1d9e8a7b"] {
+            assert!(
+                !not_container_link(&stats(name, InterfaceType::Virtual)),
+                "{}",
+                name
+            );
+        }
+        for name in ["eth0", "br0", "wlan0", "virbr0"] {
+            assert!(
+                not_container_link(&stats(name, InterfaceType::Net)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn only_down_interfaces_without_traffic_are_idle() {
+        let down = |rx_bytes, tx_bytes| InterfaceStats {
+            is_up: Some(false),
+            rx_bytes,
+            tx_bytes,
+            ..stats("eth1", InterfaceType::Net)
+        };
+        assert!(!not_idle_down(&down(0, 0)));
+        assert!(not_idle_down(&down(1, 0)));
+        assert!(not_idle_down(&down(0, 1)));
+
+        let unknown = InterfaceStats {
+            is_up: None,
+            ..down(0, 0)
+        };
+        assert!(not_idle_down(&unknown));
+        let up = InterfaceStats {
+            is_up: Some(true),
+            ..down(0, 0)
+        };
+        assert!(not_idle_down(&up));
+    }
+
+    #[test]
+    fn all_of_keeps_what_every_rule_keeps() {
+        let keep = all_of(DEFAULT_RULES);
+        assert!(keep(&stats("eth0", InterfaceType::Net)));
+        assert!(!keep(&stats("lo", InterfaceType::Loopback)));
+        assert!(!keep(&stats("docker0", InterfaceType::Bridge)));
+
+        // No rules keep everything
+        assert!(all_of(&[])(&stats("lo", InterfaceType::Loopback)));
+
+        let stats = vec![
+            stats("eth0", InterfaceType::Net),
+            stats("lo", InterfaceType::Loopback),
+            stats("veth9", InterfaceType::Virtual),
+            stats("wlan0", InterfaceType::Wireless),
+        ];
+        let kept: Vec<_> = exclude_noninteresting(stats)
+            .into_iter()
+            .map(|s| s.interface)
+            .collect();
+        assert_eq!(kept, ["eth0", "wlan0"]);
+    }
+}
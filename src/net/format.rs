@@ -1,27 +1,249 @@
-/// Converts bytes per second to human-readable string (B/s, KB/s, MB/s, GB/s)
-pub fn human_bytes_per_sec(bytes: u64) -> String {
-    let b = bytes as f64;
-    if b < 1024.0 {
-        format!("{:.0} B/s", b)
-    } else if b < 1024.0 * 1024.0 {
-        format!("{:.2} KB/s", b / 1024.0)
-    } else if b < 1024.0 * 1024.0 * 1024.0 {
-        format!("{:.2} MB/s", b / 1024.0 / 1024.0)
-    } else {
-        format!("{:.2} GB/s", b / 1024.0 / 1024.0 / 1024.0)
+/// Unit prefixes for byte rates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnitStyle {
+    /// Powers of 1024: KiB/s, MiB/s, GiB/s
+    #[default]
+    Iec,
+    /// Powers of 1000: kB/s, MB/s, GB/s
+    Si,
+}
+
+/// A step on the byte unit ladder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateUnit {
+    Bytes,
+    Kilo,
+    Mega,
+    Giga,
+    Tera,
+}
+
+impl RateUnit {
+    /// Power of the style's base this unit stands for
+    fn exponent(self) -> i32 {
+        match self {
+            RateUnit::Bytes => 0,
+            RateUnit::Kilo => 1,
+            RateUnit::Mega => 2,
+            RateUnit::Giga => 3,
+            RateUnit::Tera => 4,
+        }
+    }
+}
+
+/// Byte rate (or total) formatter: decimals, unit style, and an optional fixed unit
+///
+/// The default (two decimals, IEC, unit picked per value) is what
+/// `human_bytes_per_sec` prints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateFormatter {
+    /// Decimals shown above B/s (whole bytes never get any)
+    pub precision: u8,
+    pub style: UnitStyle,
+    /// Always use this unit; None picks the largest one the value reaches
+    pub unit: Option<RateUnit>,
+}
+
+impl Default for RateFormatter {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            style: UnitStyle::Iec,
+            unit: None,
+        }
+    }
+}
+
+impl RateFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_style(mut self, style: UnitStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_unit(mut self, unit: RateUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    pub fn format(&self, bytes_per_sec: u64) -> String {
+        self.scaled(bytes_per_sec as f64, "/s")
     }
+
+    /// A fractional rate, such as `NetDelta::rx_bytes_per_sec`, without rounding it
+    /// to whole bytes first
+    pub fn format_f64(&self, bytes_per_sec: f64) -> String {
+        // Also maps NaN to zero
+        self.scaled(bytes_per_sec.max(0.0), "/s")
+    }
+
+    /// A byte count rather than a rate (no "/s"), e.g. a session total
+    pub fn format_total(&self, bytes: u64) -> String {
+        self.scaled(bytes as f64, "")
+    }
+
+    fn scaled(&self, b: f64, suffix: &str) -> String {
+        let (base, labels): (f64, [&str; 5]) = match self.style {
+            UnitStyle::Iec => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"]),
+            UnitStyle::Si => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+        };
+        let exponent = match self.unit {
+            Some(unit) => unit.exponent(),
+            // The largest unit the value reaches
+            None => (1..labels.len() as i32)
+                .take_while(|&e| b >= base.powi(e))
+                .count() as i32,
+        };
+
+        let precision = if exponent == 0 {
+            0
+        } else {
+            self.precision as usize
+        };
+        format!(
+            "{:.*} {}{}",
+            precision,
+            b / base.powi(exponent),
+            labels[exponent as usize],
+            suffix
+        )
+    }
+}
+
+/// Converts bytes per second to human-readable string (B/s, KiB/s, MiB/s, GiB/s)
+pub fn human_bytes_per_sec(bytes: u64) -> String {
+    RateFormatter::default().format(bytes)
+}
+
+/// Converts a fractional byte rate to human-readable string; see `human_bytes_per_sec`
+pub fn human_bytes_per_sec_f64(bytes_per_sec: f64) -> String {
+    RateFormatter::default().format_f64(bytes_per_sec)
+}
+
+/// Converts a byte count to human-readable string (B, KiB, MiB, GiB, TiB)
+pub fn human_bytes(bytes: u64) -> String {
+    RateFormatter::default().format_total(bytes)
+}
+
+/// Converts bytes per second to human-readable string in the given unit style
+pub fn human_bytes_per_sec_with(bytes: u64, style: UnitStyle) -> String {
+    RateFormatter::new().with_style(style).format(bytes)
 }
 
 /// Converts bytes per second to human-readable bits per second (bps, Kbps, Mbps, Gbps)
+///
+/// Decimal, unlike `human_bytes_per_sec`: link speeds are quoted in powers of 1000,
+/// so a saturated 100 Mbps link reads 100 Mbps.
 pub fn human_bits_per_sec(bytes: u64) -> String {
     let bps = bytes as f64 * 8.0;
-    if bps < 1024.0 {
+    if bps < 1e3 {
         format!("{:.0} bps", bps)
-    } else if bps < 1024.0 * 1024.0 {
-        format!("{:.2} Kbps", bps / 1024.0)
-    } else if bps < 1024.0 * 1024.0 * 1024.0 {
-        format!("{:.2} Mbps", bps / 1024.0 / 1024.0)
+    } else if bps < 1e6 {
+        format!("{:.2} Kbps", bps / 1e3)
+    } else if bps < 1e9 {
+        format!("{:.2} Mbps", bps / 1e6)
     } else {
-        format!("{:.2} Gbps", bps / 1024.0 / 1024.0 / 1024.0)
+        format!("{:.2} Gbps", bps / 1e9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn si_steps_up_at_exactly_1000() {
+        let si = |bytes| human_bytes_per_sec_with(bytes, UnitStyle::Si);
+        assert_eq!(si(999), "999 B/s");
+        assert_eq!(si(1000), "1.00 kB/s");
+        assert_eq!(si(1024), "1.02 kB/s");
+        assert_eq!(si(1_000_000), "1.00 MB/s");
+    }
+
+    #[test]
+    fn iec_steps_up_at_exactly_1024() {
+        let iec = |bytes| human_bytes_per_sec_with(bytes, UnitStyle::Iec);
+        assert_eq!(iec(1000), "1000 B/s");
+        assert_eq!(iec(1023), "1023 B/s");
+        assert_eq!(iec(1024), "1.00 KiB/s");
+        assert_eq!(iec(1024 * 1024), "1.00 MiB/s");
+    }
+
+    #[test]
+    fn plain_function_is_the_iec_style() {
+        for bytes in [0, 1000, 1023, 1024, 1_000_000, 1 << 30] {
+            assert_eq!(
+                human_bytes_per_sec(bytes),
+                human_bytes_per_sec_with(bytes, UnitStyle::Iec)
+            );
+        }
+    }
+
+    #[test]
+    fn bits_are_eight_per_byte_in_powers_of_1000() {
+        assert_eq!(human_bits_per_sec(1_000_000), "8.00 Mbps");
+        assert_eq!(human_bits_per_sec(125_000), "1.00 Mbps");
+    }
+
+    #[test]
+    fn bits_step_up_at_each_power_of_1000() {
+        assert_eq!(human_bits_per_sec(0), "0 bps");
+        assert_eq!(human_bits_per_sec(124), "992 bps");
+        assert_eq!(human_bits_per_sec(125), "1.00 Kbps");
+        assert_eq!(human_bits_per_sec(124_999), "999.99 Kbps");
+        assert_eq!(human_bits_per_sec(124_000_000), "992.00 Mbps");
+        assert_eq!(human_bits_per_sec(125_000_000), "1.00 Gbps");
+    }
+
+    #[test]
+    fn precision_sets_the_decimals_above_bytes() {
+        let formatter = RateFormatter::new().with_precision(0);
+        assert_eq!(formatter.format(1536), "2 KiB/s");
+        let formatter = RateFormatter::new().with_precision(3);
+        assert_eq!(formatter.format(1536), "1.500 KiB/s");
+        assert_eq!(formatter.format(512), "512 B/s");
+    }
+
+    #[test]
+    fn a_fixed_unit_is_used_whatever_the_value() {
+        let mib = RateFormatter::new().with_unit(RateUnit::Mega);
+        assert_eq!(mib.format(512 * 1024), "0.50 MiB/s");
+        assert_eq!(mib.format(0), "0.00 MiB/s");
+        assert_eq!(mib.format(3 << 30), "3072.00 MiB/s");
+
+        let bytes = RateFormatter::new().with_unit(RateUnit::Bytes);
+        assert_eq!(bytes.format(1 << 20), "1048576 B/s");
+
+        let mb = mib.with_style(UnitStyle::Si);
+        assert_eq!(mb.format(250_000), "0.25 MB/s");
+    }
+
+    #[test]
+    fn fractional_rates_clamp_nan_and_negatives_to_zero() {
+        let formatter = RateFormatter::new();
+        assert_eq!(formatter.format_f64(f64::NAN), "0 B/s");
+        assert_eq!(formatter.format_f64(-512.0), "0 B/s");
+        assert_eq!(formatter.format_f64(1536.0), "1.50 KiB/s");
+        assert_eq!(formatter.format_f64(0.4), "0 B/s");
+    }
+
+    #[test]
+    fn totals_have_no_per_second_suffix() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(1023), "1023 B");
+        assert_eq!(human_bytes(1024), "1.00 KiB");
+        assert_eq!(human_bytes(1536), "1.50 KiB");
+        assert_eq!(human_bytes(5 << 30), "5.00 GiB");
+        assert_eq!(human_bytes(1 << 40), "1.00 TiB");
+        // TiB is the largest unit
+        assert_eq!(human_bytes(1 << 50), "1024.00 TiB");
     }
 }
@@ -1,4 +1,6 @@
+pub mod filter;
 pub mod format;
+#[allow(clippy::module_inception)]
 pub mod net;
 pub mod tracker;
 pub mod wan;
@@ -7,16 +9,22 @@ pub mod wan;
 mod net_macos;
 
 #[cfg(target_os = "macos")]
-pub use net_macos::{fetch_net_stats, list_interfaces};
+pub use net_macos::{
+    default_route_interface, fetch_net_stats, list_interfaces, physical_interfaces,
+};
 
 #[cfg(target_os = "windows")]
 mod net_windows;
 
 #[cfg(target_os = "windows")]
-pub use net_windows::{fetch_net_stats, list_interfaces};
+pub use net_windows::{
+    default_route_interface, fetch_net_stats, list_interfaces, physical_interfaces,
+};
 
 #[cfg(target_os = "linux")]
 mod net_linux;
 
 #[cfg(target_os = "linux")]
-pub use net_linux::{fetch_net_stats, list_interfaces};
+pub use net_linux::{
+    default_route_interface, fetch_net_stats, list_interfaces, physical_interfaces,
+};
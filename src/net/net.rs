@@ -1,17 +1,451 @@
 use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::time::Duration;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// What kind of link an interface is; local backends report any of them but
+/// `Wan`, and fall back to `Net` when they can't tell
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum InterfaceType {
+    /// A wired (or otherwise unclassified) local interface
     Net,
+    /// A router interface read over SNMP
     Wan,
+    Loopback,
+    Wireless,
+    /// A software interface without hardware behind it: veths, VLANs, virtual
+    /// switch ports, 6to4 tunnels
+    Virtual,
+    /// A VPN or point-to-point tunnel (tun, WireGuard, utun, Windows PPP)
+    Vpn,
+    Bridge,
 }
 
+impl InterfaceType {
+    pub fn is_wan(&self) -> bool {
+        *self == InterfaceType::Wan
+    }
+
+    /// A local link with hardware behind it: wired (or unclassified) and wireless
+    pub fn is_physical(&self) -> bool {
+        matches!(self, InterfaceType::Net | InterfaceType::Wireless)
+    }
+}
+
+/// Size of the counters an interface reports, which decides where they wrap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CounterWidth {
+    /// Wraps at 4 GiB - seconds on a fast link (SNMP Counter32, macOS `if_data`)
+    Bits32,
+    Bits64,
+}
+
+/// Counters for one interface, as returned by every backend
+///
+/// Field support per source:
+///
+/// | Field            | Linux           | macOS          | Windows             | SNMP (WAN)        |
+/// |------------------|-----------------|----------------|---------------------|-------------------|
+/// | `interface`      | `/proc/net/dev` | `ifa_name`     | `Alias`             | caller-supplied   |
+/// | `rx_bytes`       | column 1        | `ifi_ibytes`   | `InOctets`          | `ifInOctets`/HC   |
+/// | `tx_bytes`       | column 9        | `ifi_obytes`   | `OutOctets`         | `ifOutOctets`/HC  |
+/// | `rx_packets`     | column 2        | `ifi_ipackets` | `In(N)UcastPkts`    | `ifInUcastPkts`   |
+/// | `tx_packets`     | column 10       | `ifi_opackets` | `Out(N)UcastPkts`   | `ifOutUcastPkts`  |
+/// | `rx_errors`      | column 3        | `ifi_ierrors`  | `InErrors`          | `None`            |
+/// | `tx_errors`      | column 11       | `ifi_oerrors`  | `OutErrors`         | `None`            |
+/// | `rx_dropped`     | column 4        | `ifi_iqdrops`  | `InDiscards`        | `None`            |
+/// | `tx_dropped`     | column 12       | `None`         | `OutDiscards`       | `None`            |
+/// | `is_up`          | `operstate`     | `ifa_flags`    | `OperStatus`        | `None`            |
+/// | `link_speed_bps` | `speed`         | `ifi_baudrate` | `TransmitLinkSpeed` | `None`            |
+/// | `kind`           | see below       | `ifi_type`     | `Type`              | `Wan`             |
+/// | `target`         | `None`          | `None`         | `None`              | agent address     |
+/// | `counter_width`  | 64-bit          | 32-bit         | 64-bit              | per response type |
+/// | `uptime`         | `None`          | `None`         | `None`              | `sysUpTime`       |
+///
+/// On Linux `kind` comes from `/sys/class/net/<iface>`: its `type` (loopback),
+/// `wireless` and `bridge` entries, and whether a `device` backs it. Tunnels
+/// without a link layer (`type` 65534: tun, WireGuard) are `Vpn`.
+///
+/// Deliberately has no `Default` impl: backends build it with a struct literal,
+/// so adding a field is a compile error in every backend until each one decides
+/// what to put there. When a source genuinely can't provide a value, model the
+/// field as an `Option` rather than filling in a placeholder.
 #[derive(Debug, Clone)]
 pub struct InterfaceStats {
     pub interface: String,
     pub rx_bytes: u64,
     pub tx_bytes: u64,
+    /// Packets received; None when the source doesn't count them (an SNMP agent
+    /// without ifInUcastPkts)
+    pub rx_packets: Option<u64>,
+    /// Packets sent, like `rx_packets`
+    pub tx_packets: Option<u64>,
+    /// Receive errors (bad frames, CRC); None when the source doesn't count them
+    pub rx_errors: Option<u64>,
+    /// Transmit errors, like `rx_errors`
+    pub tx_errors: Option<u64>,
+    /// Packets dropped on receive (e.g. full queues); None when not counted
+    pub rx_dropped: Option<u64>,
+    /// Packets dropped on transmit, like `rx_dropped`
+    pub tx_dropped: Option<u64>,
+    /// Whether the link is up (carrier present); None when the source can't tell,
+    /// e.g. Linux loopback and tunnels report "unknown"
+    pub is_up: Option<bool>,
+    /// Link capacity in bits/sec; None when unknown or the link is down
+    pub link_speed_bps: Option<u64>,
     pub kind: InterfaceType,
+    /// SNMP agent the counters were read from, so interfaces on different
+    /// routers never share an identity
+    pub target: Option<String>,
+    /// Lets the tracker tell a 32-bit counter wrapping from a reset
+    pub counter_width: CounterWidth,
+    /// How long the counters' source had been up when they were read (sysUpTime
+    /// for SNMP); None for local interfaces. Going backwards means it restarted.
+    pub uptime: Option<Duration>,
+}
+
+impl InterfaceStats {
+    /// Debug-build check that a backend populated the required fields
+    ///
+    /// Called by each backend on what it returns, so a backend that drifts from
+    /// the others fails loudly during development instead of showing blank cards.
+    ///
+    /// Local backends pass `Net` for `expected_kind` and may report any local kind.
+    pub(crate) fn debug_assert_populated(&self, expected_kind: InterfaceType) {
+        debug_assert!(
+            !self.interface.is_empty(),
+            "backend returned InterfaceStats without an interface name"
+        );
+        debug_assert!(
+            self.kind.is_wan() == expected_kind.is_wan(),
+            "backend returned {} as {:?}, expected {:?}",
+            self.interface,
+            self.kind,
+            expected_kind
+        );
+        debug_assert!(
+            self.target.is_some() == expected_kind.is_wan(),
+            "backend returned {} with target {:?}",
+            self.interface,
+            self.target
+        );
+    }
+}
+
+/// Why a backend couldn't read interface counters
+#[derive(Debug)]
+pub enum NetStatsError {
+    /// Reading a system file failed (Linux `/proc/net/dev`)
+    Io(io::Error),
+    /// An OS call returned this error code (`getifaddrs` errno, `GetIfTable2` status)
+    SysCall(i32),
+}
+
+impl fmt::Display for NetStatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetStatsError::Io(e) => write!(f, "{}", e),
+            NetStatsError::SysCall(code) => write!(f, "system call failed with error {}", code),
+        }
+    }
+}
+
+impl std::error::Error for NetStatsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NetStatsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetStatsError {
+    fn from(e: io::Error) -> Self {
+        NetStatsError::Io(e)
+    }
+}
+
+/// Interfaces a backend should report: exact names and/or `*` glob patterns
+///
+/// An empty set matches every interface. Names are compared case-sensitively,
+/// except on Windows where interface aliases (e.g. "Wi-Fi") are not.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterfaceSet {
+    entries: HashSet<String>,
+}
+
+impl InterfaceSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a name or pattern, returning whether it was new
+    pub fn insert(&mut self, entry: impl Into<String>) -> bool {
+        self.entries.insert(entry.into())
+    }
+
+    pub fn remove(&mut self, entry: &str) -> bool {
+        self.entries.remove(entry)
+    }
+
+    /// Whether exactly this name (or pattern) is in the set
+    pub fn contains(&self, entry: &str) -> bool {
+        self.entries.contains(entry)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter()
+    }
+
+    /// Whether the interface `name` should be reported
+    pub fn matches(&self, name: &str) -> bool {
+        self.is_empty()
+            || self.contains(name)
+            || self.entries.iter().any(|entry| glob_matches(entry, name))
+    }
+}
+
+impl FromIterator<String> for InterfaceSet {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
 }
 
-pub type InterfaceSet = HashSet<String>;
+impl<'a> FromIterator<&'a str> for InterfaceSet {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        iter.into_iter().map(str::to_string).collect()
+    }
+}
+
+impl Extend<String> for InterfaceSet {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl IntoIterator for InterfaceSet {
+    type Item = String;
+    type IntoIter = std::collections::hash_set::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// `*` matches any run of characters, including none; everything else is literal
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let fold = |s: &str| -> Vec<char> {
+        if cfg!(target_os = "windows") {
+            s.to_lowercase().chars().collect()
+        } else {
+            s.chars().collect()
+        }
+    };
+    let (pattern, name) = (fold(pattern), fold(name));
+
+    // Greedy match, backtracking to the last `*` on a mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut last_star = None; // (pattern index after the `*`, name index it resumes from)
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            p += 1;
+            last_star = Some((p, n));
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = last_star {
+            // Let the `*` swallow one more character and retry
+            p = star_p;
+            n = star_n + 1;
+            last_star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(interface: &str, kind: InterfaceType, target: Option<&str>) -> InterfaceStats {
+        InterfaceStats {
+            interface: interface.to_string(),
+            rx_bytes: 1000,
+            tx_bytes: 2000,
+            rx_packets: Some(10),
+            tx_packets: Some(20),
+            rx_errors: Some(0),
+            tx_errors: Some(0),
+            rx_dropped: Some(0),
+            tx_dropped: Some(0),
+            is_up: Some(true),
+            link_speed_bps: Some(1_000_000_000),
+            kind,
+            target: target.map(str::to_string),
+            counter_width: CounterWidth::Bits64,
+            uptime: None,
+        }
+    }
+
+    #[test]
+    fn local_stats_pass_for_every_local_kind() {
+        for kind in [
+            InterfaceType::Net,
+            InterfaceType::Loopback,
+            InterfaceType::Wireless,
+            InterfaceType::Virtual,
+            InterfaceType::Vpn,
+            InterfaceType::Bridge,
+        ] {
+            stats("eth0", kind, None).debug_assert_populated(InterfaceType::Net);
+        }
+    }
+
+    #[test]
+    fn optional_fields_may_be_left_unset() {
+        // The SNMP column of the table: no fault counters, link state or speed
+        let mut wan = stats("ppp0", InterfaceType::Wan, Some("192.168.1.1:161"));
+        wan.rx_errors = None;
+        wan.tx_errors = None;
+        wan.rx_dropped = None;
+        wan.tx_dropped = None;
+        wan.is_up = None;
+        wan.link_speed_bps = None;
+        wan.counter_width = CounterWidth::Bits32;
+        wan.uptime = Some(Duration::from_secs(60));
+        wan.debug_assert_populated(InterfaceType::Wan);
+
+        // macOS counts no transmit drops; loopback has no link state
+        let mut local = stats("lo0", InterfaceType::Loopback, None);
+        local.tx_dropped = None;
+        local.is_up = None;
+        local.link_speed_bps = None;
+        local.debug_assert_populated(InterfaceType::Net);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "without an interface name")]
+    fn empty_name_fails() {
+        stats("", InterfaceType::Net, None).debug_assert_populated(InterfaceType::Net);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "expected Net")]
+    fn local_backend_reporting_wan_fails() {
+        stats("eth0", InterfaceType::Wan, None).debug_assert_populated(InterfaceType::Net);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "expected Wan")]
+    fn snmp_reporting_local_kind_fails() {
+        stats("ppp0", InterfaceType::Net, Some("192.168.1.1:161"))
+            .debug_assert_populated(InterfaceType::Wan);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "with target None")]
+    fn wan_without_target_fails() {
+        stats("ppp0", InterfaceType::Wan, None).debug_assert_populated(InterfaceType::Wan);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "with target Some")]
+    fn local_with_target_fails() {
+        stats("eth0", InterfaceType::Net, Some("192.168.1.1:161"))
+            .debug_assert_populated(InterfaceType::Net);
+    }
+
+    #[test]
+    fn active_backend_fills_what_the_field_table_promises() {
+        let Ok(stats) = crate::net::fetch_net_stats(&InterfaceSet::new()) else {
+            return; // No counters to check in this environment
+        };
+        for s in &stats {
+            s.debug_assert_populated(InterfaceType::Net);
+            assert_eq!(s.target, None, "{}", s.interface);
+            assert_eq!(s.uptime, None, "{}", s.interface);
+            if cfg!(target_os = "macos") {
+                assert_eq!(s.counter_width, CounterWidth::Bits32, "{}", s.interface);
+                assert_eq!(s.tx_dropped, None, "{}", s.interface);
+            } else {
+                assert_eq!(s.counter_width, CounterWidth::Bits64, "{}", s.interface);
+                assert!(s.tx_dropped.is_some(), "{}", s.interface);
+            }
+            // Counted by every local backend
+            let counted = [
+                s.rx_packets,
+                s.tx_packets,
+                s.rx_errors,
+                s.tx_errors,
+                s.rx_dropped,
+            ];
+            assert!(
+                counted.iter().all(Option::is_some),
+                "{}: {:?}",
+                s.interface,
+                counted
+            );
+        }
+    }
+
+    #[test]
+    fn bare_star_matches_every_name() {
+        assert!(glob_matches("*", "eth0"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("**", "wlan0"));
+    }
+
+    #[test]
+    fn trailing_star_matches_any_suffix() {
+        assert!(glob_matches("eth*", "eth0"));
+        assert!(glob_matches("eth*", "eth"));
+        assert!(!glob_matches("eth*", "veth0"));
+        assert!(!glob_matches("eth0", "eth01"));
+    }
+
+    #[test]
+    fn star_backtracks_past_an_early_match() {
+        assert!(glob_matches("*a*b", "aab"));
+        assert!(glob_matches("*ab", "aab"));
+        assert!(glob_matches("en*s0", "enp0s0s0"));
+        assert!(!glob_matches("*a*b", "aabc"));
+        assert!(!glob_matches("*a*b", "bba"));
+    }
+
+    #[test]
+    fn case_folds_only_on_windows() {
+        let set: InterfaceSet = ["Wi-Fi"].into_iter().collect();
+        assert!(set.matches("Wi-Fi"));
+        assert_eq!(set.matches("wi-fi"), cfg!(target_os = "windows"));
+        assert_eq!(glob_matches("Wi-*", "WI-FI 2"), cfg!(target_os = "windows"));
+    }
+
+    #[test]
+    fn empty_set_matches_everything() {
+        let set = InterfaceSet::new();
+        assert!(set.matches("eth0"));
+        assert!(set.matches(""));
+
+        let set: InterfaceSet = ["eth*", "wlan0"].into_iter().collect();
+        assert!(set.matches("eth1"));
+        assert!(set.matches("wlan0"));
+        assert!(!set.matches("wlan1"));
+    }
+}
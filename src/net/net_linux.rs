@@ -1,14 +1,24 @@
 use std::fs::read_to_string;
+use std::path::Path;
 
-use crate::{InterfaceSet, InterfaceStats, InterfaceType};
+use crate::net::net::{CounterWidth, InterfaceSet, InterfaceStats, InterfaceType, NetStatsError};
 
-pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
-    let mut stats = Vec::new();
+pub fn fetch_net_stats(selected: &InterfaceSet) -> Result<Vec<InterfaceStats>, NetStatsError> {
+    let content = read_to_string("/proc/net/dev")?;
+    let stats = parse_net_dev(&content, selected);
+
+    for s in &stats {
+        s.debug_assert_populated(InterfaceType::Net);
+    }
+    Ok(stats)
+}
 
-    let content = match read_to_string("/proc/net/dev") {
-        Ok(c) => c,
-        Err(_) => return stats,
-    };
+/// Counters of the `selected` interfaces from the contents of `/proc/net/dev`
+///
+/// A line without a full set of numeric counters is skipped rather than failing the poll, so one
+/// odd interface doesn't blank out the others.
+fn parse_net_dev(content: &str, selected: &InterfaceSet) -> Vec<InterfaceStats> {
+    let mut stats = Vec::new();
 
     for line in content.lines().skip(2) {
         // example: "eth0: 123 0 0 0 0 0 0 0 456 0 0 0 0 0 0 0"
@@ -29,32 +39,98 @@ pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
             None => continue,
         };
 
-        if data.len() < 16 {
+        // columns: rx bytes packets errs drop fifo frame compressed multicast, then tx
+        let column = |i: usize| data.get(i)?.parse::<u64>().ok();
+        let counters = if data.len() >= 16 {
+            column(0).zip(column(8)).zip(column(1).zip(column(9)))
+        } else {
+            None
+        };
+        let Some(((rx_bytes, tx_bytes), (rx_packets, tx_packets))) = counters else {
             continue;
-        }
-
-        let rx_bytes = data[0].parse::<u64>().unwrap_or(0);
-        let tx_bytes = data[8].parse::<u64>().unwrap_or(0);
+        };
 
+        let (is_up, link_speed_bps) = link_state(iface);
         stats.push(InterfaceStats {
             interface: iface.to_string(),
             rx_bytes,
             tx_bytes,
-            kind: InterfaceType::Net,
+            rx_packets: Some(rx_packets),
+            tx_packets: Some(tx_packets),
+            rx_errors: column(2),
+            tx_errors: column(10),
+            rx_dropped: column(3),
+            tx_dropped: column(11),
+            is_up,
+            link_speed_bps,
+            kind: interface_kind(iface),
+            target: None,
+            counter_width: CounterWidth::Bits64,
+            uptime: None,
         });
     }
 
     stats
 }
 
+/// Link state and speed from `/sys/class/net/<iface>`
+fn link_state(iface: &str) -> (Option<bool>, Option<u64>) {
+    let sys = Path::new("/sys/class/net").join(iface);
+    // "unknown" is what loopback and most tunnels report: no carrier to speak of
+    let is_up = read_to_string(sys.join("operstate"))
+        .ok()
+        .and_then(|state| match state.trim() {
+            "up" => Some(true),
+            "unknown" => None,
+            _ => Some(false),
+        });
+    // Mb/s; -1, or a read error (EINVAL), while the link is down or on virtual interfaces
+    let link_speed_bps = read_to_string(sys.join("speed"))
+        .ok()
+        .and_then(|speed| speed.trim().parse::<u64>().ok())
+        .filter(|&mbps| mbps > 0)
+        .map(|mbps| mbps * 1_000_000);
+    (is_up, link_speed_bps)
+}
+
+/// Kind of a local interface from `/sys/class/net/<iface>`; `Net` if sysfs doesn't know it
+fn interface_kind(iface: &str) -> InterfaceType {
+    const ARPHRD_LOOPBACK: &str = "772";
+    const ARPHRD_NONE: &str = "65534"; // tun, WireGuard
+
+    let sys = Path::new("/sys/class/net").join(iface);
+    if !sys.exists() {
+        return InterfaceType::Net;
+    }
+    let link_type = read_to_string(sys.join("type")).unwrap_or_default();
+    match link_type.trim() {
+        ARPHRD_LOOPBACK => return InterfaceType::Loopback,
+        ARPHRD_NONE => return InterfaceType::Vpn,
+        _ => {}
+    }
+    if sys.join("wireless").exists() || sys.join("phy80211").exists() {
+        InterfaceType::Wireless
+    } else if sys.join("bridge").exists() {
+        InterfaceType::Bridge
+    } else if sys.join("device").exists() {
+        InterfaceType::Net
+    } else {
+        // No backing device: veth, tap, docker0-style links, VLANs
+        InterfaceType::Virtual
+    }
+}
+
 /// List all available network interface names
 pub fn list_interfaces() -> Vec<String> {
-    let mut names = Vec::new();
+    match read_to_string("/proc/net/dev") {
+        Ok(content) => parse_interface_names(&content),
+        Err(_) => Vec::new(),
+    }
+}
 
-    let content = match read_to_string("/proc/net/dev") {
-        Ok(c) => c,
-        Err(_) => return names,
-    };
+/// Interface names from the contents of `/proc/net/dev`, sorted and deduplicated
+fn parse_interface_names(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
 
     for line in content.lines().skip(2) {
         let line = line.trim();
@@ -69,5 +145,123 @@ pub fn list_interfaces() -> Vec<String> {
     }
 
     names.sort();
+    names.dedup();
     names
 }
+
+/// Name of the interface carrying the IPv4 default route, from `/proc/net/route`
+pub fn default_route_interface() -> Option<String> {
+    let content = read_to_string("/proc/net/route").ok()?;
+
+    // columns: Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 8 {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(cols[3], 16).ok()?;
+            let is_up = flags & 0x1 != 0; // RTF_UP
+            if cols[1] != "00000000" || cols[7] != "00000000" || !is_up {
+                return None;
+            }
+
+            let metric = cols[6].parse::<u32>().unwrap_or(u32::MAX);
+            Some((metric, cols[0]))
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, iface)| iface.to_string())
+}
+
+/// Physical (non-virtual, non-loopback) interfaces that are currently up
+///
+/// Physical as in `InterfaceType::is_physical`: sysfs links the interface to a
+/// backing device (`/sys/class/net/<iface>/device`); bridges, veths, tunnels and
+/// `lo` don't have one.
+pub fn physical_interfaces() -> Vec<String> {
+    let mut names: Vec<String> = list_interfaces()
+        .into_iter()
+        .filter(|iface| {
+            let operstate = Path::new("/sys/class/net").join(iface).join("operstate");
+            let is_up = read_to_string(operstate)
+                .map(|state| state.trim() == "up")
+                .unwrap_or(false);
+            is_up && interface_kind(iface).is_physical()
+        })
+        .collect();
+
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROC_NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+wlan0: 9876543   12345    0    0    0     0          0         0  1234567    6789    0    0    0     0       0          0
+    lo:  123456     789    0    0    0     0          0         0   123456     789    0    0    0     0       0          0
+  eth0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+  eth0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+docker0:     42       1    0    0    0     0          0         0       42       1    0    0    0     0       0          0
+";
+
+    #[test]
+    fn parses_names_past_the_two_header_lines() {
+        assert_eq!(
+            parse_interface_names(PROC_NET_DEV),
+            ["docker0", "eth0", "lo", "wlan0"]
+        );
+    }
+
+    #[test]
+    fn error_and_drop_columns_are_read_per_direction() {
+        let content = format!(
+            "{}\
+  eth1: 5000000    4000    7    3    0     0          0         0  2500000    3500    2    9    0     0       0          0
+",
+            PROC_NET_DEV
+        );
+        let selected: InterfaceSet = ["eth1", "wlan0"].into_iter().collect();
+        let stats = parse_net_dev(&content, &selected);
+        let names: Vec<&str> = stats.iter().map(|s| s.interface.as_str()).collect();
+        assert_eq!(names, ["wlan0", "eth1"]);
+
+        let eth1 = &stats[1];
+        assert_eq!((eth1.rx_bytes, eth1.tx_bytes), (5_000_000, 2_500_000));
+        assert_eq!((eth1.rx_packets, eth1.tx_packets), (Some(4000), Some(3500)));
+        assert_eq!((eth1.rx_errors, eth1.tx_errors), (Some(7), Some(2)));
+        assert_eq!((eth1.rx_dropped, eth1.tx_dropped), (Some(3), Some(9)));
+
+        let wlan0 = &stats[0];
+        assert_eq!((wlan0.rx_errors, wlan0.tx_errors), (Some(0), Some(0)));
+        assert_eq!((wlan0.rx_dropped, wlan0.tx_dropped), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let content = format!(
+            "{}\
+  eth1: 5000000    4000    7    3\n\
+  eth2: garbage\n",
+            PROC_NET_DEV
+        );
+        let selected: InterfaceSet = ["eth1", "eth2", "wlan0"].into_iter().collect();
+        let stats = parse_net_dev(&content, &selected);
+        let names: Vec<&str> = stats.iter().map(|s| s.interface.as_str()).collect();
+        assert_eq!(names, ["wlan0"]);
+        assert_eq!(stats[0].rx_bytes, 9_876_543);
+    }
+
+    #[test]
+    fn headers_alone_have_no_names() {
+        let headers = PROC_NET_DEV.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert!(parse_interface_names(&headers).is_empty());
+        assert!(parse_interface_names("").is_empty());
+    }
+}
@@ -1,18 +1,20 @@
-use crate::net::net::{InterfaceSet, InterfaceStats};
+use crate::net::net::{CounterWidth, InterfaceSet, InterfaceStats, InterfaceType, NetStatsError};
 
 use libc::*;
 use std::collections::{HashMap, HashSet};
 use std::ffi::CStr;
 
 #[cfg(target_os = "macos")]
-pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
+pub fn fetch_net_stats(selected: &InterfaceSet) -> Result<Vec<InterfaceStats>, NetStatsError> {
     unsafe {
         let mut ifap: *mut ifaddrs = std::ptr::null_mut();
         if getifaddrs(&mut ifap) != 0 {
-            return vec![];
+            let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(-1);
+            return Err(NetStatsError::SysCall(errno));
         }
 
-        let mut acc: HashMap<String, (u64, u64)> = HashMap::new();
+        // rx/tx bytes, rx/tx packets, rx/tx errors, rx drops; then up, baud rate, IFT_* type
+        let mut acc: HashMap<String, ([u64; 7], bool, u64, u8)> = HashMap::new();
         let mut cur = ifap;
 
         while !cur.is_null() {
@@ -21,11 +23,21 @@ pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
             if !ifa.ifa_data.is_null() {
                 let name = CStr::from_ptr(ifa.ifa_name).to_string_lossy().into_owned();
 
-                if selected.contains(&name) {
+                if selected.matches(&name) {
                     let data = &*(ifa.ifa_data as *const if_data);
-                    let entry = acc.entry(name).or_insert((0, 0));
-                    entry.0 += data.ifi_ibytes as u64;
-                    entry.1 += data.ifi_obytes as u64;
+                    let flags = ifa.ifa_flags as c_int;
+                    let (entry, is_up, baud_rate, if_type) = acc.entry(name).or_default();
+                    entry[0] += data.ifi_ibytes as u64;
+                    entry[1] += data.ifi_obytes as u64;
+                    entry[2] += data.ifi_ipackets as u64;
+                    entry[3] += data.ifi_opackets as u64;
+                    entry[4] += data.ifi_ierrors as u64;
+                    entry[5] += data.ifi_oerrors as u64;
+                    entry[6] += data.ifi_iqdrops as u64;
+                    // Administratively up and with a carrier, as in physical_interfaces
+                    *is_up = flags & IFF_UP != 0 && flags & IFF_RUNNING != 0;
+                    *baud_rate = data.ifi_baudrate as u64;
+                    *if_type = data.ifi_type;
                 }
             }
 
@@ -34,17 +46,68 @@ pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
 
         freeifaddrs(ifap);
 
-        acc.into_iter()
-            .map(|(iface, (rx, tx))| InterfaceStats {
+        let stats: Vec<InterfaceStats> = acc
+            .into_iter()
+            .map(|(iface, (counters, is_up, baud, if_type))| InterfaceStats {
+                kind: interface_kind(&iface, if_type),
                 interface: iface,
-                rx_bytes: rx,
-                tx_bytes: tx,
-                kind: super::net::InterfaceType::Net,
+                rx_bytes: counters[0],
+                tx_bytes: counters[1],
+                rx_packets: Some(counters[2]),
+                tx_packets: Some(counters[3]),
+                rx_errors: Some(counters[4]),
+                tx_errors: Some(counters[5]),
+                rx_dropped: Some(counters[6]),
+                // if_data has no output drop counter
+                tx_dropped: None,
+                is_up: Some(is_up),
+                link_speed_bps: Some(baud).filter(|&bps| bps > 0),
+                target: None,
+                // if_data's byte counters are 32-bit even on 64-bit macOS
+                counter_width: CounterWidth::Bits32,
+                uptime: None,
             })
-            .collect()
+            .collect();
+
+        for s in &stats {
+            s.debug_assert_populated(InterfaceType::Net);
+        }
+        Ok(stats)
     }
 }
 
+/// Kind of a local interface from its `IFT_*` type; `Net` for anything unrecognized
+///
+/// Wi-Fi ports report `IFT_ETHER` like wired ones, so they come out as `Net`.
+#[cfg(target_os = "macos")]
+fn interface_kind(name: &str, if_type: u8) -> InterfaceType {
+    const IFT_OTHER: u8 = 0x01;
+    const IFT_LOOP: u8 = 0x18;
+    const IFT_GIF: u8 = 0x37;
+    const IFT_STF: u8 = 0x39;
+    const IFT_IEEE80211: u8 = 0x47;
+    const IFT_L2VLAN: u8 = 0x87;
+    const IFT_BRIDGE: u8 = 0xd1;
+
+    match if_type {
+        IFT_LOOP => InterfaceType::Loopback,
+        IFT_IEEE80211 => InterfaceType::Wireless,
+        IFT_BRIDGE => InterfaceType::Bridge,
+        // VPN clients create utun (and legacy ipsec/ppp) interfaces
+        _ if ["utun", "ipsec", "ppp"].iter().any(|p| name.starts_with(p)) => InterfaceType::Vpn,
+        IFT_OTHER | IFT_GIF | IFT_STF | IFT_L2VLAN => InterfaceType::Virtual,
+        _ if is_pseudo_ether(name) => InterfaceType::Virtual,
+        _ => InterfaceType::Net,
+    }
+}
+
+/// Apple's AirDrop (`awdl`) and low-latency WLAN (`llw`) pseudo-interfaces, which
+/// report `IFT_ETHER`
+#[cfg(target_os = "macos")]
+fn is_pseudo_ether(name: &str) -> bool {
+    name.starts_with("awdl") || name.starts_with("llw")
+}
+
 /// List all available network interface names
 #[cfg(target_os = "macos")]
 pub fn list_interfaces() -> Vec<String> {
@@ -77,3 +140,68 @@ pub fn list_interfaces() -> Vec<String> {
         result
     }
 }
+
+/// Name of the interface carrying the IPv4 default route, via `route -n get default`
+#[cfg(target_os = "macos")]
+pub fn default_route_interface() -> Option<String> {
+    let output = std::process::Command::new("route")
+        .args(["-n", "get", "default"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // example line: "  interface: en0"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("interface:"))
+        .map(|iface| iface.trim().to_string())
+        .filter(|iface| !iface.is_empty())
+}
+
+/// Physical (non-virtual, non-loopback) interfaces that are currently up
+///
+/// Wired and Wi-Fi ports both report `IFT_ETHER`; Apple's AirDrop (`awdl`) and
+/// low-latency WLAN (`llw`) pseudo-interfaces do too, so they're skipped by name.
+#[cfg(target_os = "macos")]
+pub fn physical_interfaces() -> Vec<String> {
+    const IFT_ETHER: u8 = 0x06;
+
+    unsafe {
+        let mut ifap: *mut ifaddrs = std::ptr::null_mut();
+        if getifaddrs(&mut ifap) != 0 {
+            return vec![];
+        }
+
+        let mut names: HashSet<String> = HashSet::new();
+        let mut cur = ifap;
+
+        while !cur.is_null() {
+            let ifa = &*cur;
+
+            if !ifa.ifa_data.is_null() {
+                let data = &*(ifa.ifa_data as *const if_data);
+                let flags = ifa.ifa_flags as c_int;
+                let is_up = flags & IFF_UP != 0 && flags & IFF_RUNNING != 0;
+                let is_loopback = flags & IFF_LOOPBACK != 0;
+
+                if is_up && !is_loopback && data.ifi_type == IFT_ETHER {
+                    let name = CStr::from_ptr(ifa.ifa_name).to_string_lossy().into_owned();
+                    if !is_pseudo_ether(&name) {
+                        names.insert(name);
+                    }
+                }
+            }
+
+            cur = ifa.ifa_next;
+        }
+
+        freeifaddrs(ifap);
+
+        let mut result: Vec<String> = names.into_iter().collect();
+        result.sort();
+        result
+    }
+}
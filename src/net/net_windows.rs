@@ -1,21 +1,31 @@
 #[cfg(target_os = "windows")]
 use crate::net::net::InterfaceSet;
-use crate::net::net::{InterfaceStats, InterfaceType};
+use crate::net::net::{CounterWidth, InterfaceStats, InterfaceType, NetStatsError};
 
 use windows::{
     Win32::Foundation::ERROR_SUCCESS,
-    Win32::NetworkManagement::IpHelper::{GetIfTable2, MIB_IF_TABLE2},
+    Win32::NetworkManagement::IpHelper::{
+        FreeMibTable, GetIfTable2, GetIpForwardTable2, IF_TYPE_BRIDGE, IF_TYPE_ETHERNET_CSMACD,
+        IF_TYPE_IEEE80211, IF_TYPE_PPP, IF_TYPE_PROP_VIRTUAL, IF_TYPE_SOFTWARE_LOOPBACK,
+        IF_TYPE_TUNNEL, MIB_IF_ROW2, MIB_IF_TABLE2, MIB_IPFORWARD_TABLE2,
+    },
+    Win32::NetworkManagement::Ndis::IfOperStatusUp,
+    Win32::Networking::WinSock::AF_INET,
 };
 
 #[cfg(target_os = "windows")]
-pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
+pub fn fetch_net_stats(selected: &InterfaceSet) -> Result<Vec<InterfaceStats>, NetStatsError> {
     let mut results = Vec::new();
 
     unsafe {
         let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
 
-        if GetIfTable2(&mut table) != ERROR_SUCCESS || table.is_null() {
-            return results; // empty vec on failure (same semantics as macOS)
+        let status = GetIfTable2(&mut table);
+        if status != ERROR_SUCCESS {
+            return Err(NetStatsError::SysCall(status.0 as i32));
+        }
+        if table.is_null() {
+            return Err(NetStatsError::SysCall(-1));
         }
 
         let table_ref = &*table;
@@ -28,7 +38,7 @@ pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
                 .trim_end_matches('\0')
                 .to_string();
 
-            if !selected.is_empty() && !selected.contains(&name) {
+            if !selected.matches(&name) {
                 continue;
             }
 
@@ -36,12 +46,47 @@ pub fn fetch_net_stats(selected: &InterfaceSet) -> Vec<InterfaceStats> {
                 interface: name,
                 rx_bytes: row.InOctets,
                 tx_bytes: row.OutOctets,
-                kind: InterfaceType::Net,
+                rx_packets: Some(row.InUcastPkts + row.InNUcastPkts),
+                tx_packets: Some(row.OutUcastPkts + row.OutNUcastPkts),
+                rx_errors: Some(row.InErrors),
+                tx_errors: Some(row.OutErrors),
+                rx_dropped: Some(row.InDiscards),
+                tx_dropped: Some(row.OutDiscards),
+                is_up: Some(row.OperStatus == IfOperStatusUp),
+                // Zero or all ones when the adapter doesn't know
+                link_speed_bps: Some(row.TransmitLinkSpeed)
+                    .filter(|&bps| bps != 0 && bps != u64::MAX),
+                kind: interface_kind(row),
+                target: None,
+                counter_width: CounterWidth::Bits64,
+                uptime: None,
             });
         }
     }
 
-    results
+    for s in &results {
+        s.debug_assert_populated(InterfaceType::Net);
+    }
+    Ok(results)
+}
+
+/// Kind of a local interface from its `IF_TYPE_*`; `Net` for anything unrecognized
+#[cfg(target_os = "windows")]
+fn interface_kind(row: &MIB_IF_ROW2) -> InterfaceType {
+    // bit 0: HardwareInterface, as in physical_interfaces
+    let is_hardware = row.InterfaceAndOperStatusFlags._bitfield & 0x1 != 0;
+
+    match row.Type {
+        IF_TYPE_SOFTWARE_LOOPBACK => InterfaceType::Loopback,
+        IF_TYPE_IEEE80211 => InterfaceType::Wireless,
+        IF_TYPE_BRIDGE => InterfaceType::Bridge,
+        // Built-in VPN connections (PPTP, L2TP, SSTP, IKEv2) are PPP interfaces
+        IF_TYPE_PPP => InterfaceType::Vpn,
+        IF_TYPE_TUNNEL | IF_TYPE_PROP_VIRTUAL => InterfaceType::Virtual,
+        // Hyper-V vEthernet and most VPN adapters pose as Ethernet
+        IF_TYPE_ETHERNET_CSMACD if !is_hardware => InterfaceType::Virtual,
+        _ => InterfaceType::Net,
+    }
 }
 
 /// List all available network interface names
@@ -74,3 +119,108 @@ pub fn list_interfaces() -> Vec<String> {
 
     names
 }
+
+/// Name of the interface carrying the IPv4 default route, from the IP forwarding table
+#[cfg(target_os = "windows")]
+pub fn default_route_interface() -> Option<String> {
+    unsafe {
+        let mut routes: *mut MIB_IPFORWARD_TABLE2 = std::ptr::null_mut();
+
+        if GetIpForwardTable2(AF_INET, &mut routes) != ERROR_SUCCESS || routes.is_null() {
+            return None;
+        }
+
+        // Pick the 0.0.0.0/0 route with the lowest metric
+        let routes_ref = &*routes;
+        let routes_ptr = routes_ref.Table.as_ptr();
+        let mut best: Option<(u32, u32)> = None; // (metric, interface index)
+
+        for i in 0..routes_ref.NumEntries {
+            let row = &*routes_ptr.add(i as usize);
+            if row.DestinationPrefix.PrefixLength != 0 {
+                continue;
+            }
+            if best.is_none_or(|(metric, _)| row.Metric < metric) {
+                best = Some((row.Metric, row.InterfaceIndex));
+            }
+        }
+
+        FreeMibTable(routes as *const _);
+
+        let (_, if_index) = best?;
+
+        // Resolve the interface index to the same alias fetch_net_stats reports
+        let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+        if GetIfTable2(&mut table) != ERROR_SUCCESS || table.is_null() {
+            return None;
+        }
+
+        let table_ref = &*table;
+        let table_ptr = table_ref.Table.as_ptr();
+        let mut name = None;
+
+        for i in 0..table_ref.NumEntries {
+            let row = &*table_ptr.add(i as usize);
+            if row.InterfaceIndex == if_index {
+                name = Some(
+                    String::from_utf16_lossy(&row.Alias)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                );
+                break;
+            }
+        }
+
+        FreeMibTable(table as *const _);
+
+        name
+    }
+}
+
+/// Physical (non-virtual, non-loopback) interfaces that are currently up
+///
+/// Keeps hardware Ethernet and Wi-Fi adapters, dropping the filter-driver
+/// duplicates (e.g. "Ethernet-WFP Native MAC Layer LightWeight Filter-0000")
+/// that `GetIfTable2` lists alongside them.
+#[cfg(target_os = "windows")]
+pub fn physical_interfaces() -> Vec<String> {
+    let mut names = Vec::new();
+
+    unsafe {
+        let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+
+        if GetIfTable2(&mut table) != ERROR_SUCCESS || table.is_null() {
+            return names;
+        }
+
+        let table_ref = &*table;
+        let table_ptr = table_ref.Table.as_ptr();
+
+        for i in 0..table_ref.NumEntries {
+            let row = &*table_ptr.add(i as usize);
+
+            // bit 0: HardwareInterface, bit 1: FilterInterface
+            let flags = row.InterfaceAndOperStatusFlags._bitfield;
+            let is_hardware = flags & 0x1 != 0 && flags & 0x2 == 0;
+            let is_media = row.Type == IF_TYPE_ETHERNET_CSMACD || row.Type == IF_TYPE_IEEE80211;
+
+            if !is_hardware || !is_media || row.OperStatus != IfOperStatusUp {
+                continue;
+            }
+
+            let name = String::from_utf16_lossy(&row.Alias)
+                .trim_end_matches('\0')
+                .to_string();
+
+            if !name.is_empty() {
+                names.push(name);
+            }
+        }
+
+        FreeMibTable(table as *const _);
+    }
+
+    names.sort();
+    names.dedup();
+    names
+}
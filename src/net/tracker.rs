@@ -1,50 +1,1029 @@
-use crate::net::net::{InterfaceStats, InterfaceType};
-use std::collections::HashMap;
+use crate::net::net::{CounterWidth, InterfaceStats, InterfaceType};
+use std::collections::{HashMap, HashSet, VecDeque, vec_deque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct NetDelta {
-    pub interface: String,
+    /// Shared with the tracker, so a delta doesn't cost a copy of the name
+    pub interface: Arc<str>,
     pub rx_delta: u64,
     pub tx_delta: u64,
+    /// Packets received since the previous sample; None unless both samples had
+    /// packet counts
+    pub rx_packets: Option<u64>,
+    /// Packets sent since the previous sample, like `rx_packets`
+    pub tx_packets: Option<u64>,
+    /// Receive errors since the previous sample; None unless both samples had them
+    pub rx_errors: Option<u64>,
+    /// Transmit errors since the previous sample, like `rx_errors`
+    pub tx_errors: Option<u64>,
+    /// Receive drops since the previous sample, like `rx_errors`
+    pub rx_dropped: Option<u64>,
+    /// Transmit drops since the previous sample, like `rx_errors`
+    pub tx_dropped: Option<u64>,
     pub kind: InterfaceType,
+    /// SNMP agent of a WAN interface, shared with the tracker like `interface`
+    pub target: Option<Arc<str>>,
+    /// Time since the previous sample of this interface (zero for the first one)
+    pub interval: Duration,
+    /// The source restarted since the previous sample (its uptime went backwards),
+    /// so this is a fresh baseline like a first sample
+    pub restarted: bool,
+    /// Receive rate in bytes/sec after the tracker's moving average; the raw rate
+    /// when smoothing is off
+    pub smoothed_rx: f64,
+    /// Transmit rate in bytes/sec, like `smoothed_rx`
+    pub smoothed_tx: f64,
+}
+
+impl NetDelta {
+    pub fn rx_bytes_per_sec(&self) -> f64 {
+        self.per_sec(self.rx_delta)
+    }
+
+    pub fn tx_bytes_per_sec(&self) -> f64 {
+        self.per_sec(self.tx_delta)
+    }
+
+    fn per_sec(&self, delta: u64) -> f64 {
+        if self.interval.is_zero() {
+            return 0.0;
+        }
+        delta as f64 / self.interval.as_secs_f64()
+    }
+}
+
+/// What an interface did since the tracker started (or `reset_summary`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InterfaceSummary {
+    /// Bytes received
+    pub session_rx_total: u64,
+    /// Bytes sent
+    pub session_tx_total: u64,
+    /// Highest receive rate, bytes/sec
+    pub peak_rx: u64,
+    /// Highest transmit rate, bytes/sec
+    pub peak_tx: u64,
+}
+
+/// Tracker key: a local and a WAN interface may share a name, and so may interfaces
+/// on two routers, so kind and SNMP target are part of the identity
+type TrackerKey = (Option<Arc<str>>, Arc<str>, InterfaceType);
+
+/// Last reading of an interface
+#[derive(Clone, Copy)]
+struct Baseline {
+    rx: u64,
+    tx: u64,
+    packets: Option<(u64, u64)>,
+    faults: [Option<u64>; 4], // rx/tx errors, rx/tx drops
+    uptime: Option<Duration>,
+    at: Instant,
+}
+
+/// What `update` reports for an interface seen for the first time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FirstSample {
+    /// Report a zero delta (baseline only)
+    #[default]
+    Zero,
+    /// Leave the interface out of the result until a real delta exists
+    Skip,
+}
+
+/// One delta kept by `DeltaTracker::with_history`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// When the sample was taken
+    pub at: Instant,
+    pub rx_delta: u64,
+    pub tx_delta: u64,
+    /// Time since the sample before it
+    pub interval: Duration,
 }
 
 #[derive(Default)]
 pub struct DeltaTracker {
-    previous: HashMap<String, (u64, u64)>, // (rx, tx)
+    previous: HashMap<TrackerKey, Baseline>,
+    session: HashMap<TrackerKey, (u64, u64)>, // (rx, tx) bytes since creation or reset_session
+    peaks: HashMap<TrackerKey, (u64, u64)>,   // (rx, tx) highest bytes/sec since reset_peaks
+    averages: HashMap<TrackerKey, (f64, f64)>, // (rx, tx) smoothed bytes/sec
+    smoothing: Option<f64>,                   // Moving-average alpha
+    history: HashMap<TrackerKey, VecDeque<Sample>>, // Oldest first, at most history_len
+    names: HashSet<Arc<str>>, // Interned interface names and targets, shared by keys and deltas
+    history_len: usize,
+    max_age: Option<Duration>, // Baselines not updated for this long are dropped
+    first_sample: FirstSample,
 }
 
 impl DeltaTracker {
     pub fn new() -> Self {
         Self {
             previous: HashMap::new(),
+            session: HashMap::new(),
+            peaks: HashMap::new(),
+            averages: HashMap::new(),
+            smoothing: None,
+            history: HashMap::new(),
+            names: HashSet::new(),
+            history_len: 0,
+            max_age: None,
+            first_sample: FirstSample::Zero,
         }
     }
 
+    /// Choose how the first observation of an interface is reported
+    pub fn with_first_sample(mut self, first_sample: FirstSample) -> Self {
+        self.first_sample = first_sample;
+        self
+    }
+
+    /// Smooth the rates in `NetDelta::smoothed_rx`/`smoothed_tx` with an exponential
+    /// moving average; `alpha` (clamped to 0.01..=1) is the weight of the newest
+    /// sample, so smaller is steadier but slower to follow a change
+    pub fn with_smoothing(mut self, alpha: f64) -> Self {
+        self.smoothing = Some(alpha.clamp(0.01, 1.0));
+        self
+    }
+
+    /// Keep the last `capacity` deltas of each interface, for `history`,
+    /// `avg_over` and `max_over`
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history_len = capacity;
+        self
+    }
+
+    /// Forget an interface, session total and peak included, once it hasn't been
+    /// updated for `max_age`, so the tracker stays bounded without `prune` calls
+    /// and an interface that comes back later starts from a fresh baseline
+    ///
+    /// Age rather than a count of missed updates, since callers may pass only
+    /// some of their sources to each `update`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
     pub fn update(&mut self, stats: &[InterfaceStats]) -> Vec<NetDelta> {
+        self.update_at(stats, Instant::now())
+    }
+
+    /// `update` with the sample time given, for samples taken earlier or a
+    /// simulated clock; rates are only right if `now` never goes backwards
+    pub fn update_at(&mut self, stats: &[InterfaceStats], now: Instant) -> Vec<NetDelta> {
         let mut deltas = Vec::with_capacity(stats.len());
+        self.record(stats, now, &mut deltas);
+        deltas
+    }
+
+    /// `update` into a caller-owned buffer, which is cleared first; polling with
+    /// the same buffer saves allocating a new Vec every time
+    pub fn update_into(&mut self, stats: &[InterfaceStats], deltas: &mut Vec<NetDelta>) {
+        deltas.clear();
+        self.record(stats, Instant::now(), deltas);
+    }
+
+    /// Append the deltas of `stats` sampled at `now` to `deltas`
+    fn record(&mut self, stats: &[InterfaceStats], now: Instant, deltas: &mut Vec<NetDelta>) {
+        if let Some(max_age) = self.max_age {
+            self.expire(now, max_age);
+        }
 
         for s in stats {
-            let (prev_rx, prev_tx) = self
-                .previous
-                .get(&s.interface)
-                .copied()
-                .unwrap_or((s.rx_bytes, s.tx_bytes));
+            let interface = self.intern(&s.interface);
+            let target = s.target.as_deref().map(|target| self.intern(target));
+            let key = (target, interface.clone(), s.kind.clone());
+            let packets = s.rx_packets.zip(s.tx_packets);
+            let baseline = Baseline {
+                rx: s.rx_bytes,
+                tx: s.tx_bytes,
+                packets,
+                faults: [s.rx_errors, s.tx_errors, s.rx_dropped, s.tx_dropped],
+                uptime: s.uptime,
+                at: now,
+            };
+            let previous = self.previous.insert(key.clone(), baseline);
+            let session = self.session.entry(key.clone()).or_default();
+
+            // A router that rebooted started its counters over: diffing against the
+            // old ones would read as a reset (or a bogus 32-bit wrap), so re-baseline.
+            // sysUpTime itself wraps after ~497 days, which just costs one sample.
+            let restarted = match (previous.and_then(|p| p.uptime), s.uptime) {
+                (Some(before), Some(now_up)) => now_up < before,
+                _ => false,
+            };
 
-            let rx_delta = s.rx_bytes.saturating_sub(prev_rx);
-            let tx_delta = s.tx_bytes.saturating_sub(prev_tx);
+            let prev = match previous {
+                Some(prev) if !restarted => prev,
+                _ if self.first_sample == FirstSample::Skip => continue,
+                _ => baseline,
+            };
 
-            self.previous
-                .insert(s.interface.clone(), (s.rx_bytes as u64, s.tx_bytes as u64));
+            let width = s.counter_width;
+            let max = plausible_delta(s.link_speed_bps, now.saturating_duration_since(prev.at));
+            let rx_delta = counter_delta(prev.rx, s.rx_bytes, width, max);
+            let tx_delta = counter_delta(prev.tx, s.tx_bytes, width, max);
+            let packet_deltas = prev
+                .packets
+                .zip(packets)
+                .map(|((prev_rx, prev_tx), (rx, tx))| {
+                    (
+                        counter_delta(prev_rx, rx, width, max),
+                        counter_delta(prev_tx, tx, width, max),
+                    )
+                });
+            let fault = |i: usize| {
+                let (previous, current) = prev.faults[i].zip(baseline.faults[i])?;
+                Some(counter_delta(previous, current, width, max))
+            };
+            // Resets and restarts come out as zero deltas, so the totals only grow
+            session.0 = session.0.saturating_add(rx_delta);
+            session.1 = session.1.saturating_add(tx_delta);
 
-            deltas.push(NetDelta {
-                interface: s.interface.clone(),
+            let mut delta = NetDelta {
+                interface,
                 rx_delta,
                 tx_delta,
+                rx_packets: packet_deltas.map(|(rx, _)| rx),
+                tx_packets: packet_deltas.map(|(_, tx)| tx),
+                rx_errors: fault(0),
+                tx_errors: fault(1),
+                rx_dropped: fault(2),
+                tx_dropped: fault(3),
                 kind: s.kind.clone(),
+                target: key.0.clone(),
+                interval: now.saturating_duration_since(prev.at),
+                restarted,
+                smoothed_rx: 0.0,
+                smoothed_tx: 0.0,
+            };
+            let rates = (delta.rx_bytes_per_sec(), delta.tx_bytes_per_sec());
+            // Rates, not deltas, so a late poll doesn't look like a burst
+            if !delta.interval.is_zero() {
+                let peak = self.peaks.entry(key.clone()).or_default();
+                peak.0 = peak.0.max(rates.0.round() as u64);
+                peak.1 = peak.1.max(rates.1.round() as u64);
+            }
+            (delta.smoothed_rx, delta.smoothed_tx) = match self.smoothing {
+                // A baseline has no rate yet; the first real one seeds the average
+                Some(_) if delta.interval.is_zero() => rates,
+                Some(alpha) => {
+                    let average = self.averages.entry(key.clone()).or_insert(rates);
+                    average.0 += alpha * (rates.0 - average.0);
+                    average.1 += alpha * (rates.1 - average.1);
+                    *average
+                }
+                None => rates,
+            };
+
+            // Baselines have no interval to average over
+            if self.history_len > 0 && !delta.interval.is_zero() {
+                let samples = self
+                    .history
+                    .entry(key)
+                    .or_insert_with(|| VecDeque::with_capacity(self.history_len));
+                if samples.len() == self.history_len {
+                    samples.pop_front();
+                }
+                samples.push_back(Sample {
+                    at: now,
+                    rx_delta,
+                    tx_delta,
+                    interval: delta.interval,
+                });
+            }
+            deltas.push(delta);
+        }
+    }
+
+    /// The shared copy of `name`, added on first use
+    fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return name.clone();
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(name.clone());
+        name
+    }
+
+    /// Drop interned names no key or caller-held delta uses anymore
+    fn release_names(&mut self) {
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+    }
+
+    /// Forget the baselines of interfaces not named in `live`, so one that comes
+    /// back starts over instead of diffing against an old reading
+    ///
+    /// Not done by `update`, since callers may poll sources separately and pass
+    /// only some of them. Session totals and peaks are kept.
+    pub fn prune(&mut self, live: &[&str]) {
+        self.previous
+            .retain(|(_, interface, _), _| live.contains(&&**interface));
+        self.averages
+            .retain(|(_, interface, _), _| live.contains(&&**interface));
+        self.history
+            .retain(|(_, interface, _), _| live.contains(&&**interface));
+        self.release_names();
+    }
+
+    /// Drop everything kept for interfaces last updated over `max_age` before `now`;
+    /// unlike `prune` that includes session totals and peaks, whose keys would
+    /// otherwise keep the names interned
+    fn expire(&mut self, now: Instant, max_age: Duration) {
+        let stale: Vec<TrackerKey> = self
+            .previous
+            .iter()
+            .filter(|(_, baseline)| now.saturating_duration_since(baseline.at) > max_age)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            self.previous.remove(&key);
+            self.averages.remove(&key);
+            self.history.remove(&key);
+            self.session.remove(&key);
+            self.peaks.remove(&key);
+        }
+        self.release_names();
+    }
+
+    /// Number of interfaces with a baseline
+    pub fn len(&self) -> usize {
+        self.previous.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty()
+    }
+
+    /// Forget every baseline, average, session total and peak
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.averages.clear();
+        self.history.clear();
+        self.session.clear();
+        self.peaks.clear();
+        self.names.clear();
+    }
+
+    /// The kept deltas of `iface`, oldest first; empty without `with_history`
+    ///
+    /// Interfaces with the same name on different sources should be told apart
+    /// by name (e.g. a target label) to get separate histories; otherwise one
+    /// of them is returned.
+    pub fn history(&self, iface: &str) -> vec_deque::Iter<'_, Sample> {
+        self.samples(iface)
+            .map_or_else(Default::default, |samples| samples.iter())
+    }
+
+    /// Average (rx, tx) bytes/sec of `iface` over the kept samples taken within
+    /// `window` of its newest one; None without samples
+    pub fn avg_over(&self, iface: &str, window: Duration) -> Option<(f64, f64)> {
+        let (rx, tx, elapsed) = self
+            .recent(iface, window)
+            .fold((0u64, 0u64, Duration::ZERO), |(rx, tx, elapsed), s| {
+                (rx + s.rx_delta, tx + s.tx_delta, elapsed + s.interval)
             });
+        if elapsed.is_zero() {
+            return None;
         }
+        let secs = elapsed.as_secs_f64();
+        Some((rx as f64 / secs, tx as f64 / secs))
+    }
 
-        deltas
+    /// Highest single-sample (rx, tx) bytes/sec of `iface` within `window` of
+    /// its newest sample; None without samples
+    pub fn max_over(&self, iface: &str, window: Duration) -> Option<(f64, f64)> {
+        self.recent(iface, window)
+            .map(|s| {
+                let secs = s.interval.as_secs_f64();
+                (s.rx_delta as f64 / secs, s.tx_delta as f64 / secs)
+            })
+            .reduce(|a, b| (a.0.max(b.0), a.1.max(b.1)))
+    }
+
+    /// The kept deltas of `iface`, if it has any
+    fn samples(&self, iface: &str) -> Option<&VecDeque<Sample>> {
+        self.history
+            .iter()
+            .find(|((_, interface, _), _)| &**interface == iface)
+            .map(|(_, samples)| samples)
+    }
+
+    /// Samples of `iface` taken within `window` of its newest one
+    fn recent(&self, iface: &str, window: Duration) -> vec_deque::Iter<'_, Sample> {
+        let Some(samples) = self.samples(iface) else {
+            return Default::default();
+        };
+        let Some(newest) = samples.back() else {
+            return samples.iter();
+        };
+        let first = samples.partition_point(|s| newest.at.duration_since(s.at) >= window);
+        samples.range(first..)
+    }
+
+    /// Bytes received and sent by `iface` since the tracker was created or
+    /// `reset_session` was called; None for an interface never seen
+    ///
+    /// Interfaces with the same name on different sources (e.g. `ppp0` on two
+    /// routers) are added together.
+    pub fn session_total(&self, iface: &str) -> Option<(u64, u64)> {
+        self.session
+            .iter()
+            .filter(|((_, interface, _), _)| &**interface == iface)
+            .map(|(_, total)| *total)
+            .reduce(|a, b| (a.0.saturating_add(b.0), a.1.saturating_add(b.1)))
+    }
+
+    /// Highest (rx, tx) bytes/sec seen on `iface` since the tracker was created
+    /// or `reset_peaks` was called; None before its first real delta
+    ///
+    /// Interfaces with the same name on different sources share the highest.
+    pub fn peak(&self, iface: &str) -> Option<(u64, u64)> {
+        self.peaks
+            .iter()
+            .filter(|((_, interface, _), _)| &**interface == iface)
+            .map(|(_, peak)| *peak)
+            .reduce(|a, b| (a.0.max(b.0), a.1.max(b.1)))
+    }
+
+    /// Session totals and peaks of `iface` together; None for an interface never seen
+    pub fn summary(&self, iface: &str) -> Option<InterfaceSummary> {
+        let (session_rx_total, session_tx_total) = self.session_total(iface)?;
+        let (peak_rx, peak_tx) = self.peak(iface).unwrap_or_default();
+        Some(InterfaceSummary {
+            session_rx_total,
+            session_tx_total,
+            peak_rx,
+            peak_tx,
+        })
+    }
+
+    /// `reset_session` and `reset_peaks` together
+    pub fn reset_summary(&mut self) {
+        self.reset_session();
+        self.reset_peaks();
+    }
+
+    /// Forget every peak; the next delta of each interface sets a new one
+    pub fn reset_peaks(&mut self) {
+        self.peaks.clear();
+    }
+
+    /// Forget the peak of `iface` (on every source that has one by that name); its
+    /// next delta sets a new one
+    pub fn reset_peak(&mut self, iface: &str) {
+        self.peaks
+            .retain(|(_, interface, _), _| &**interface != iface);
+    }
+
+    /// Start the session totals over from zero; the counter baselines are kept,
+    /// so the next update still reports a real delta
+    pub fn reset_session(&mut self) {
+        for total in self.session.values_mut() {
+            *total = (0, 0);
+        }
+    }
+}
+
+/// Bytes between two readings of a counter
+///
+/// A 32-bit counter that drops has wrapped if the bytes across the wrap fit within
+/// `max`; any other decrease is a reset (agent or interface restart) and counts as
+/// zero.
+fn counter_delta(previous: u64, current: u64, width: CounterWidth, max: u64) -> u64 {
+    const MAX32: u64 = u32::MAX as u64;
+    match width {
+        CounterWidth::Bits32 if current < previous && previous <= MAX32 => {
+            let wrapped = (MAX32 - previous) + current + 1;
+            if wrapped <= max { wrapped } else { 0 }
+        }
+        _ => current.saturating_sub(previous),
+    }
+}
+
+/// The most a counter can plausibly move in `elapsed`: the line rate with some
+/// slack when the link speed is known, otherwise a quarter of the 32-bit range
+fn plausible_delta(link_speed_bps: Option<u64>, elapsed: Duration) -> u64 {
+    match link_speed_bps {
+        Some(bps) if bps > 0 => (bps as f64 / 8.0 * elapsed.as_secs_f64() * 1.25) as u64,
+        _ => u32::MAX as u64 / 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(interface: &str, rx_bytes: u64, tx_bytes: u64) -> InterfaceStats {
+        InterfaceStats {
+            interface: interface.to_string(),
+            rx_bytes,
+            tx_bytes,
+            rx_packets: None,
+            tx_packets: None,
+            rx_errors: None,
+            tx_errors: None,
+            rx_dropped: None,
+            tx_dropped: None,
+            is_up: Some(true),
+            link_speed_bps: None,
+            kind: InterfaceType::Net,
+            target: None,
+            counter_width: CounterWidth::Bits64,
+            uptime: None,
+        }
+    }
+
+    #[test]
+    fn expired_interface_comes_back_from_a_fresh_baseline() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new().with_max_age(Duration::from_secs(10));
+        tracker.update_at(&[stats("tun0", 1000, 100)], secs(0));
+        tracker.update_at(&[stats("tun0", 3000, 300)], secs(1));
+        assert_eq!(tracker.session_total("tun0"), Some((2000, 200)));
+        assert_eq!(tracker.peak("tun0"), Some((2000, 200)));
+
+        // Gone for longer than max_age, then back with its counters moved on
+        tracker.update_at(&[stats("eth0", 0, 0)], secs(20));
+        assert_eq!(tracker.session_total("tun0"), None);
+        assert_eq!(tracker.peak("tun0"), None);
+        let deltas = tracker.update_at(&[stats("tun0", 900_000, 9000)], secs(21));
+        assert_eq!((deltas[0].rx_delta, deltas[0].tx_delta), (0, 0));
+        assert!(deltas[0].interval.is_zero());
+
+        let deltas = tracker.update_at(&[stats("tun0", 901_000, 9100)], secs(22));
+        assert_eq!((deltas[0].rx_delta, deltas[0].tx_delta), (1000, 100));
+        assert_eq!(tracker.session_total("tun0"), Some((1000, 100)));
+    }
+
+    #[test]
+    fn churning_interfaces_keep_the_maps_bounded() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new()
+            .with_max_age(Duration::from_secs(5))
+            .with_history(4);
+        // A new VPN name every second, each seen twice
+        for n in 0..100u64 {
+            let now = start + Duration::from_secs(n);
+            let live = [
+                stats(&format!("tun{}", n), n, n),
+                stats(&format!("tun{}", n + 1), n, n),
+            ];
+            tracker.update_at(&live, now);
+        }
+        for len in [
+            tracker.previous.len(),
+            tracker.session.len(),
+            tracker.peaks.len(),
+            tracker.averages.len(),
+            tracker.history.len(),
+            tracker.names.len(),
+        ] {
+            assert!(len <= 7, "{}", len);
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_newest_samples_in_order() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new().with_history(3);
+        for n in 0..6u64 {
+            tracker.update_at(
+                &[stats("eth0", n * 100, n * 10)],
+                start + Duration::from_secs(n),
+            );
+        }
+        // The baseline isn't a sample, so deltas 1..=5 were kept and 1 and 2 fell out
+        let kept: Vec<_> = tracker.history("eth0").map(|s| s.at).collect();
+        let expected: Vec<_> = (3..6).map(|n| start + Duration::from_secs(n)).collect();
+        assert_eq!(kept, expected);
+        assert!(
+            tracker
+                .history("eth0")
+                .all(|s| s.rx_delta == 100 && s.tx_delta == 10)
+        );
+        assert_eq!(tracker.history("wlan0").len(), 0);
+    }
+
+    #[test]
+    fn window_averages_over_elapsed_time_and_peaks_per_sample() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new().with_history(10);
+        // (seconds since start, rx counter): 1000 B over 1 s, 4000 B over 2 s, 600 B over 3 s
+        for (at, rx) in [(0, 0), (1, 1000), (3, 5000), (6, 5600)] {
+            tracker.update_at(&[stats("eth0", rx, 0)], start + Duration::from_secs(at));
+        }
+
+        let everything = Duration::from_secs(60);
+        assert_eq!(
+            tracker.avg_over("eth0", everything),
+            Some((5600.0 / 6.0, 0.0))
+        );
+        assert_eq!(tracker.max_over("eth0", everything), Some((2000.0, 0.0)));
+
+        // Samples taken less than 4 s before the newest one (at 6 s): those at 3 s and 6 s
+        let recent = Duration::from_secs(4);
+        assert_eq!(tracker.avg_over("eth0", recent), Some((4600.0 / 5.0, 0.0)));
+        assert_eq!(tracker.max_over("eth0", recent), Some((2000.0, 0.0)));
+
+        // Only the newest sample
+        let newest = Duration::from_secs(1);
+        assert_eq!(tracker.avg_over("eth0", newest), Some((200.0, 0.0)));
+        assert_eq!(tracker.max_over("eth0", newest), Some((200.0, 0.0)));
+
+        assert_eq!(tracker.avg_over("wlan0", everything), None);
+        assert_eq!(tracker.max_over("wlan0", everything), None);
+    }
+
+    #[test]
+    fn reset_peak_forgets_only_that_interface() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[stats("eth0", 0, 0), stats("wlan0", 0, 0)], start);
+        let burst = [stats("eth0", 5000, 500), stats("wlan0", 3000, 300)];
+        tracker.update_at(&burst, start + Duration::from_secs(1));
+
+        tracker.reset_peak("eth0");
+        assert_eq!(tracker.peak("eth0"), None);
+        assert_eq!(tracker.peak("wlan0"), Some((3000, 300)));
+
+        // The next delta sets a new peak, however low
+        let quiet = [stats("eth0", 5100, 510), stats("wlan0", 3000, 300)];
+        tracker.update_at(&quiet, start + Duration::from_secs(2));
+        assert_eq!(tracker.peak("eth0"), Some((100, 10)));
+        assert_eq!(tracker.peak("wlan0"), Some((3000, 300)));
+    }
+
+    #[test]
+    fn summary_adds_up_totals_and_keeps_the_highest_rates() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new();
+        assert_eq!(tracker.summary("eth0"), None);
+
+        tracker.update_at(&[stats("eth0", 10_000, 1000)], secs(0));
+        assert_eq!(tracker.summary("eth0"), Some(InterfaceSummary::default()));
+        tracker.update_at(&[stats("eth0", 12_000, 1200)], secs(1));
+        tracker.update_at(&[stats("eth0", 18_000, 1300)], secs(2));
+        // 2000 and 1000 bytes over two seconds: below the peaks as rates
+        tracker.update_at(&[stats("eth0", 20_000, 2300)], secs(4));
+
+        assert_eq!(
+            tracker.summary("eth0"),
+            Some(InterfaceSummary {
+                session_rx_total: 10_000,
+                session_tx_total: 1300,
+                peak_rx: 6000,
+                peak_tx: 500,
+            })
+        );
+    }
+
+    #[test]
+    fn reset_summary_keeps_the_baselines() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[stats("eth0", 0, 0)], secs(0));
+        tracker.update_at(&[stats("eth0", 5000, 500)], secs(1));
+
+        tracker.reset_summary();
+        assert_eq!(tracker.summary("eth0"), Some(InterfaceSummary::default()));
+
+        // Counted from the last reading, not from zero
+        let deltas = tracker.update_at(&[stats("eth0", 5300, 530)], secs(2));
+        assert_eq!((deltas[0].rx_delta, deltas[0].tx_delta), (300, 30));
+        assert_eq!(
+            tracker.summary("eth0"),
+            Some(InterfaceSummary {
+                session_rx_total: 300,
+                session_tx_total: 30,
+                peak_rx: 300,
+                peak_tx: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn packet_deltas_need_packet_counts_on_both_samples() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let sample = |rx_packets, tx_packets| InterfaceStats {
+            rx_packets,
+            tx_packets,
+            ..stats("eth0", 0, 0)
+        };
+        let packets = |d: &NetDelta| (d.rx_packets, d.tx_packets);
+        let mut tracker = DeltaTracker::new();
+
+        let deltas = tracker.update_at(&[sample(Some(100), Some(40))], secs(0));
+        assert_eq!(packets(&deltas[0]), (Some(0), Some(0)));
+        let deltas = tracker.update_at(&[sample(Some(150), Some(45))], secs(1));
+        assert_eq!(packets(&deltas[0]), (Some(50), Some(5)));
+
+        // Missing on the new sample, then on the previous one: unknown, not zero
+        let deltas = tracker.update_at(&[sample(None, None)], secs(2));
+        assert_eq!(packets(&deltas[0]), (None, None));
+        let deltas = tracker.update_at(&[sample(Some(200), Some(50))], secs(3));
+        assert_eq!(packets(&deltas[0]), (None, None));
+        // Counted again once both samples have them
+        let deltas = tracker.update_at(&[sample(Some(230), Some(52))], secs(4));
+        assert_eq!(packets(&deltas[0]), (Some(30), Some(2)));
+
+        // Only one direction known counts as neither
+        let deltas = tracker.update_at(&[sample(Some(260), None)], secs(5));
+        assert_eq!(packets(&deltas[0]), (None, None));
+    }
+
+    #[test]
+    fn error_and_drop_deltas_follow_their_counters() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let sample = |errors, dropped| InterfaceStats {
+            rx_errors: Some(errors),
+            tx_errors: Some(errors / 2),
+            rx_dropped: dropped,
+            tx_dropped: dropped.map(|d| d * 2),
+            ..stats("eth0", 0, 0)
+        };
+        let faults = |d: &NetDelta| (d.rx_errors, d.tx_errors, d.rx_dropped, d.tx_dropped);
+        let mut tracker = DeltaTracker::new();
+
+        tracker.update_at(&[sample(10, Some(1))], secs(0));
+        let deltas = tracker.update_at(&[sample(14, Some(4))], secs(1));
+        assert_eq!(faults(&deltas[0]), (Some(4), Some(2), Some(3), Some(6)));
+
+        // Drops stop being reported: unknown, while errors still count
+        let deltas = tracker.update_at(&[sample(20, None)], secs(2));
+        assert_eq!(faults(&deltas[0]), (Some(6), Some(3), None, None));
+        let deltas = tracker.update_at(&[sample(20, Some(9))], secs(3));
+        assert_eq!(faults(&deltas[0]), (Some(0), Some(0), None, None));
+
+        // A counter reset reads as zero, like the byte counters
+        let deltas = tracker.update_at(&[sample(2, Some(9))], secs(4));
+        assert_eq!(faults(&deltas[0]), (Some(0), Some(0), Some(0), Some(0)));
+    }
+
+    /// A router's counters as read with its sysUpTime
+    fn router(rx_bytes: u64, uptime_secs: u64) -> InterfaceStats {
+        InterfaceStats {
+            kind: InterfaceType::Wan,
+            target: Some("192.0.2.1".to_string()),
+            counter_width: CounterWidth::Bits32,
+            uptime: Some(Duration::from_secs(uptime_secs)),
+            ..stats("ppp0", rx_bytes, 0)
+        }
+    }
+
+    #[test]
+    fn uptime_going_backwards_rebaselines() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[router(3_000_000_000, 100)], secs(0));
+        let deltas = tracker.update_at(&[router(4_000_000_000, 101)], secs(1));
+        assert_eq!(deltas[0].rx_delta, 1_000_000_000);
+        assert!(!deltas[0].restarted);
+
+        // Rebooted: from the upper half of the range this would read as a 32-bit wrap
+        let deltas = tracker.update_at(&[router(100, 3)], secs(2));
+        assert_eq!(deltas[0].rx_delta, 0);
+        assert!(deltas[0].restarted);
+        assert!(deltas[0].interval.is_zero());
+
+        let deltas = tracker.update_at(&[router(5100, 4)], secs(3));
+        assert_eq!(deltas[0].rx_delta, 5000);
+        assert!(!deltas[0].restarted);
+        assert_eq!(deltas[0].interval, Duration::from_secs(1));
+        assert_eq!(tracker.session_total("ppp0"), Some((1_000_005_000, 0)));
+    }
+
+    #[test]
+    fn restart_is_skipped_like_a_first_sample() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new().with_first_sample(FirstSample::Skip);
+        assert!(tracker.update_at(&[router(1000, 100)], secs(0)).is_empty());
+        assert_eq!(tracker.update_at(&[router(2000, 101)], secs(1)).len(), 1);
+        assert!(tracker.update_at(&[router(10, 2)], secs(2)).is_empty());
+        let deltas = tracker.update_at(&[router(510, 3)], secs(3));
+        assert_eq!(deltas[0].rx_delta, 500);
+    }
+
+    #[test]
+    fn counters_without_uptime_never_look_restarted() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[stats("eth0", 5000, 0)], start);
+        // A counter reset on a local interface: zero, but not a restart
+        let deltas = tracker.update_at(&[stats("eth0", 10, 0)], start + Duration::from_secs(1));
+        assert_eq!(deltas[0].rx_delta, 0);
+        assert!(!deltas[0].restarted);
+        assert_eq!(deltas[0].interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn rates_divide_by_the_time_between_samples() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new();
+        let first = tracker.update_at(&[stats("eth0", 0, 0)], start);
+        assert_eq!(first[0].rx_bytes_per_sec(), 0.0);
+
+        // A late poll: 3000 bytes over 1.5 s is 2000 B/s, not 3000
+        let at = start + Duration::from_millis(1500);
+        let deltas = tracker.update_at(&[stats("eth0", 3000, 600)], at);
+        assert_eq!(deltas[0].interval, Duration::from_millis(1500));
+        assert_eq!(deltas[0].rx_bytes_per_sec(), 2000.0);
+        assert_eq!(deltas[0].tx_bytes_per_sec(), 400.0);
+
+        // And an early one
+        let at = at + Duration::from_millis(250);
+        let deltas = tracker.update_at(&[stats("eth0", 3500, 700)], at);
+        assert_eq!(deltas[0].rx_bytes_per_sec(), 2000.0);
+        assert_eq!(deltas[0].tx_bytes_per_sec(), 400.0);
+    }
+
+    #[test]
+    fn each_interface_keeps_its_own_interval() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[stats("eth0", 0, 0), router(0, 100)], secs(0));
+        tracker.update_at(&[stats("eth0", 1000, 0)], secs(1));
+        // The router is polled every 5 s
+        let deltas = tracker.update_at(&[stats("eth0", 2000, 0), router(50_000, 105)], secs(5));
+        assert_eq!(deltas[0].interval, Duration::from_secs(4));
+        assert_eq!(deltas[0].rx_bytes_per_sec(), 250.0);
+        assert_eq!(deltas[1].interval, Duration::from_secs(5));
+        assert_eq!(deltas[1].rx_bytes_per_sec(), 10_000.0);
+    }
+
+    #[test]
+    fn first_rate_seeds_the_average() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new().with_smoothing(0.2);
+        tracker.update_at(&[stats("eth0", 0, 0)], start);
+        let deltas = tracker.update_at(
+            &[stats("eth0", 40_000, 8000)],
+            start + Duration::from_secs(1),
+        );
+        assert_eq!(
+            (deltas[0].smoothed_rx, deltas[0].smoothed_tx),
+            (40_000.0, 8000.0)
+        );
+    }
+
+    #[test]
+    fn noisy_rates_settle_around_their_mean() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new().with_smoothing(0.2);
+        let mut rx = 0;
+        tracker.update_at(&[stats("eth0", rx, 0)], start);
+        for n in 1..=60u64 {
+            // Sampling jitter: 40 MB/s and 5 MB/s in turn
+            rx += if n % 2 == 1 { 40_000_000 } else { 5_000_000 };
+            let deltas = tracker.update_at(&[stats("eth0", rx, 0)], start + Duration::from_secs(n));
+            let raw = deltas[0].rx_bytes_per_sec();
+            assert!(raw == 40e6 || raw == 5e6, "{}", raw);
+            if n > 30 {
+                let smoothed = deltas[0].smoothed_rx;
+                assert!((smoothed - 22.5e6).abs() < 2.5e6, "{}", smoothed);
+            }
+        }
+    }
+
+    #[test]
+    fn unsmoothed_rates_are_the_raw_ones() {
+        let start = Instant::now();
+        let mut tracker = DeltaTracker::new();
+        tracker.update_at(&[stats("eth0", 0, 0)], start);
+        let deltas = tracker.update_at(&[stats("eth0", 3000, 0)], start + Duration::from_secs(2));
+        assert_eq!(deltas[0].smoothed_rx, 1500.0);
+    }
+
+    #[test]
+    fn returning_interface_starts_a_new_average() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut tracker = DeltaTracker::new().with_smoothing(0.2);
+        tracker.update_at(&[stats("tun0", 0, 0)], secs(0));
+        tracker.update_at(&[stats("tun0", 90_000, 0)], secs(1));
+
+        tracker.prune(&[]);
+        let deltas = tracker.update_at(&[stats("tun0", 500_000, 0)], secs(10));
+        assert_eq!(deltas[0].smoothed_rx, 0.0);
+        let deltas = tracker.update_at(&[stats("tun0", 501_000, 0)], secs(11));
+        assert_eq!(deltas[0].smoothed_rx, 1000.0);
+    }
+
+    /// xorshift64: cheap deterministic "random" numbers for the wrap sequences
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn wrapping_32_bit_counters_lose_no_bytes() {
+        for seed in 1..=50u64 {
+            let mut random = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            let start = Instant::now();
+            let mut tracker = DeltaTracker::new();
+            let mut counter = next_random(&mut random) as u32;
+            let sample = |counter: u32| InterfaceStats {
+                counter_width: CounterWidth::Bits32,
+                ..stats("eth0", counter as u64, 0)
+            };
+            tracker.update_at(&[sample(counter)], start);
+
+            let mut sent = 0u64;
+            for n in 1..=200u64 {
+                // Anything under a quarter of the range per poll is told apart from
+                // a reset when the link speed is unknown
+                let step = next_random(&mut random) % (u32::MAX as u64 / 4);
+                counter = counter.wrapping_add(step as u32);
+                sent += step;
+                let at = start + Duration::from_secs(n);
+                let deltas = tracker.update_at(&[sample(counter)], at);
+                assert_eq!(deltas[0].rx_delta, step, "seed {} poll {}", seed, n);
+            }
+            assert_eq!(
+                tracker.session_total("eth0"),
+                Some((sent, 0)),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn drops_that_cannot_be_a_wrap_are_resets() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let sample = |rx_bytes, counter_width| InterfaceStats {
+            counter_width,
+            ..stats("eth0", rx_bytes, 0)
+        };
+        let mut tracker = DeltaTracker::new();
+
+        // From the lower half of the 32-bit range
+        tracker.update_at(&[sample(1_000_000, CounterWidth::Bits32)], secs(0));
+        let deltas = tracker.update_at(&[sample(500, CounterWidth::Bits32)], secs(1));
+        assert_eq!(deltas[0].rx_delta, 0);
+        // Counting resumes from the new value
+        let deltas = tracker.update_at(&[sample(1500, CounterWidth::Bits32)], secs(2));
+        assert_eq!(deltas[0].rx_delta, 1000);
+
+        // 64-bit counters never wrap in practice
+        tracker.update_at(
+            &[sample(u32::MAX as u64 - 10, CounterWidth::Bits64)],
+            secs(3),
+        );
+        let deltas = tracker.update_at(&[sample(5, CounterWidth::Bits64)], secs(4));
+        assert_eq!(deltas[0].rx_delta, 0);
+    }
+
+    #[test]
+    fn resets_from_the_upper_half_are_not_wraps() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let sample = |rx_bytes, link_speed_bps| InterfaceStats {
+            counter_width: CounterWidth::Bits32,
+            link_speed_bps,
+            ..stats("eth0", rx_bytes, 0)
+        };
+        let mut tracker = DeltaTracker::new();
+
+        // Reading as a wrap would be ~1.3 GB in a second
+        tracker.update_at(&[sample(3_000_000_000, None)], secs(0));
+        let deltas = tracker.update_at(&[sample(1000, None)], secs(1));
+        assert_eq!(deltas[0].rx_delta, 0);
+        let deltas = tracker.update_at(&[sample(3000, None)], secs(2));
+        assert_eq!(deltas[0].rx_delta, 2000);
+
+        // Same on a gigabit link, which moves at most ~125 MB a second
+        tracker.update_at(&[sample(3_000_000_000, Some(1_000_000_000))], secs(3));
+        let deltas = tracker.update_at(&[sample(1000, Some(1_000_000_000))], secs(4));
+        assert_eq!(deltas[0].rx_delta, 0);
+    }
+
+    #[test]
+    fn fast_links_may_wrap_by_more_than_the_default_bound() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let sample = |rx_bytes| InterfaceStats {
+            counter_width: CounterWidth::Bits32,
+            link_speed_bps: Some(10_000_000_000),
+            ..stats("eth0", rx_bytes, 0)
+        };
+        let mut tracker = DeltaTracker::new();
+
+        // 1.2 GB in a second is within a 10 Gbit/s line rate
+        tracker.update_at(&[sample(3_200_000_000)], secs(0));
+        let deltas = tracker.update_at(&[sample(105_032_704)], secs(1));
+        assert_eq!(deltas[0].rx_delta, 1_200_000_000);
     }
 }
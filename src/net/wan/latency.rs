@@ -0,0 +1,14 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Measure round-trip time to a host with a TCP connect (no raw-socket privileges needed)
+/// Returns None if the host can't be resolved or doesn't answer within the timeout
+pub fn tcp_connect_rtt(target: &str, timeout: Duration) -> Option<Duration> {
+    let addr = target.to_socket_addrs().ok()?.next()?;
+
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addr, timeout) {
+        Ok(_stream) => Some(start.elapsed()),
+        Err(_) => None,
+    }
+}
@@ -1 +1,2 @@
+pub mod latency;
 pub mod snmp;
@@ -1,101 +1,1467 @@
-use crate::net::net::{InterfaceStats, InterfaceType};
+use crate::net::net::{CounterWidth, InterfaceStats, InterfaceType};
+use regex::{Regex, RegexBuilder};
 use snmp2::{Oid, SyncSession, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use std::{fmt, io, thread};
 
-/// Fetch SNMP WAN interface counters
+/// Timeout for each SNMP request
+pub const SNMP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How many times an unanswered request is sent again before giving up
+pub const SNMP_RETRIES: u32 = 1;
+
+/// Agent port used when a target doesn't name one
+pub const SNMP_PORT: u16 = 161;
+
+/// Why reading counters from an SNMP agent failed
+#[derive(Debug)]
+pub enum SnmpError {
+    /// The settings can never work (empty community, unparseable address, ...)
+    Config(String),
+    /// The UDP session couldn't be set up (bad address, no local socket)
+    Session(io::Error),
+    /// The agent didn't answer in time - offline, wrong community, or SNMP disabled
+    Timeout,
+    /// The agent answered, but not with a counter (e.g. noSuchInstance for a bad ifIndex)
+    UnexpectedValue(String),
+    /// SNMPv3 engine discovery or time synchronization failed, or the agent's
+    /// user-based security rejected the request (unknown user, wrong passwords)
+    Engine(snmp2::Error),
+    /// Any other request failure (malformed response, ...)
+    Request(snmp2::Error),
+}
+
+impl fmt::Display for SnmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpError::Config(reason) => write!(f, "invalid SNMP settings: {}", reason),
+            SnmpError::Session(e) => write!(f, "can't create SNMP session: {}", e),
+            SnmpError::Timeout => write!(f, "no response from SNMP agent"),
+            SnmpError::UnexpectedValue(value) => write!(f, "expected a counter, got {}", value),
+            SnmpError::Engine(e) => write!(f, "SNMPv3 engine discovery failed: {}", e),
+            SnmpError::Request(e) => write!(f, "SNMP request failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnmpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnmpError::Session(e) => Some(e),
+            SnmpError::Engine(e) | SnmpError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<snmp2::Error> for SnmpError {
+    fn from(e: snmp2::Error) -> Self {
+        match e {
+            // A receive on the session's socket only fails once the timeout runs out
+            snmp2::Error::Receive => SnmpError::Timeout,
+            // A USM report: the engine time left the agent's window, or the credentials are wrong
+            #[cfg(feature = "snmpv3")]
+            snmp2::Error::AuthFailure(_) => SnmpError::Engine(e),
+            e => SnmpError::Request(e),
+        }
+    }
+}
+
+/// RX and TX byte counters, and the width they wrap at
+type Counters = (u64, u64, CounterWidth);
+
+/// Decode a counter varbind: None when the agent doesn't have the object
+/// (noSuchObject/noSuchInstance), an error for any other non-counter answer
+fn decode_counter(value: Option<&Value>) -> Result<Option<(u64, CounterWidth)>, SnmpError> {
+    match value {
+        Some(Value::Counter32(v)) => Ok(Some((*v as u64, CounterWidth::Bits32))),
+        Some(Value::Counter64(v)) => Ok(Some((*v, CounterWidth::Bits64))),
+        Some(Value::NoSuchObject | Value::NoSuchInstance) => Ok(None),
+        Some(value) => Err(SnmpError::UnexpectedValue(format!("{:?}", value))),
+        None => Err(SnmpError::UnexpectedValue("no varbind".to_string())),
+    }
+}
+
+/// Credentials for an SNMP agent
+///
+/// `Debug` masks the community and passwords, so configs can be logged.
+#[derive(Clone, PartialEq, Eq)]
+pub enum SnmpAuth {
+    V2c {
+        community: Vec<u8>,
+    },
+    /// User-based security: authNoPriv without a `priv_protocol`, authPriv with one
+    ///
+    /// Needs the `snmpv3` feature; without it opening a session fails.
+    V3 {
+        user: String,
+        auth_protocol: SnmpAuthProtocol,
+        auth_password: String,
+        priv_protocol: Option<SnmpPrivProtocol>,
+        priv_password: String,
+    },
+}
+
+impl fmt::Debug for SnmpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MASKED: &str = "***";
+        match self {
+            SnmpAuth::V2c { .. } => f.debug_struct("V2c").field("community", &MASKED).finish(),
+            SnmpAuth::V3 {
+                user,
+                auth_protocol,
+                priv_protocol,
+                ..
+            } => f
+                .debug_struct("V3")
+                .field("user", user)
+                .field("auth_protocol", auth_protocol)
+                .field("auth_password", &MASKED)
+                .field("priv_protocol", priv_protocol)
+                .field("priv_password", &MASKED)
+                .finish(),
+        }
+    }
+}
+
+/// SNMPv3 authentication (HMAC) algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnmpAuthProtocol {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// SNMPv3 privacy (encryption) algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnmpPrivProtocol {
+    Des,
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+/// Parses the names net-snmp uses (`SHA`, `SHA-256`, ...), case-insensitively
+impl FromStr for SnmpAuthProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "md5" => Ok(SnmpAuthProtocol::Md5),
+            "sha" | "sha1" => Ok(SnmpAuthProtocol::Sha1),
+            "sha224" => Ok(SnmpAuthProtocol::Sha224),
+            "sha256" => Ok(SnmpAuthProtocol::Sha256),
+            "sha384" => Ok(SnmpAuthProtocol::Sha384),
+            "sha512" => Ok(SnmpAuthProtocol::Sha512),
+            _ => Err(format!("unknown SNMPv3 auth protocol {:?}", s)),
+        }
+    }
+}
+
+/// Parses the names net-snmp uses (`DES`, `AES`, `AES-256`, ...), case-insensitively
+impl FromStr for SnmpPrivProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "des" => Ok(SnmpPrivProtocol::Des),
+            "aes" | "aes128" => Ok(SnmpPrivProtocol::Aes128),
+            "aes192" => Ok(SnmpPrivProtocol::Aes192),
+            "aes256" => Ok(SnmpPrivProtocol::Aes256),
+            _ => Err(format!("unknown SNMPv3 privacy protocol {:?}", s)),
+        }
+    }
+}
+
+/// Where an SNMP agent is and how to talk to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnmpConfig {
+    /// `host`, `host:port` or an IP address (`[v6]:port` with a port)
+    pub target: String,
+    pub auth: SnmpAuth,
+    /// How long to wait for each answer
+    pub timeout: Duration,
+    /// How many times an unanswered request is sent again, so a single lost
+    /// datagram doesn't fail a poll
+    pub retries: u32,
+    /// Port used when `target` has none
+    pub port: u16,
+}
+
+impl SnmpConfig {
+    /// Default timeout, retries and port
+    pub fn new(target: &str, auth: SnmpAuth) -> Self {
+        Self {
+            target: target.to_string(),
+            auth,
+            timeout: SNMP_TIMEOUT,
+            retries: SNMP_RETRIES,
+            port: SNMP_PORT,
+        }
+    }
+
+    /// v2c with `community`
+    pub fn v2c(target: &str, community: &[u8]) -> Self {
+        Self::new(
+            target,
+            SnmpAuth::V2c {
+                community: community.to_vec(),
+            },
+        )
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// `host:port` of the agent, with `port` added when the target has none
+    pub fn address(&self) -> Result<String, SnmpError> {
+        let target = self.target.trim();
+        if target.is_empty() {
+            return Err(SnmpError::Config("no target address".to_string()));
+        }
+        if target.parse::<SocketAddr>().is_ok() {
+            return Ok(target.to_string());
+        }
+        if let Ok(ip) = target.parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, self.port).to_string());
+        }
+
+        // A host name, with or without a port
+        match target.rsplit_once(':') {
+            None => Ok(format!("{}:{}", target, self.port)),
+            Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => {
+                Ok(target.to_string())
+            }
+            _ => Err(SnmpError::Config(format!(
+                "bad target address {:?}",
+                self.target
+            ))),
+        }
+    }
+
+    /// Reject settings that can't work before any socket is opened
+    fn validate(&self) -> Result<(), SnmpError> {
+        if self.timeout.is_zero() {
+            return Err(SnmpError::Config("timeout must not be zero".to_string()));
+        }
+        match &self.auth {
+            SnmpAuth::V2c { community } if community.is_empty() => {
+                Err(SnmpError::Config("empty community".to_string()))
+            }
+            SnmpAuth::V3 { user, .. } if user.is_empty() => {
+                Err(SnmpError::Config("empty SNMPv3 user".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// One row of an agent's interface table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnmpInterface {
+    /// ifIndex, as passed to `fetch_wan_stats`
+    pub index: u32,
+    /// ifDescr
+    pub name: String,
+    /// 64-bit HC counters when the agent has them (and they're enabled), else 32-bit
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// None when the agent doesn't report ifOperStatus
+    pub oper_status: Option<OperStatus>,
+}
+
+/// One WAN interface to poll, for setups with several uplinks or routers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WanTarget {
+    pub config: SnmpConfig,
+    /// ifIndex on the agent
+    pub if_index: u32,
+    /// Interface name in the returned stats; keep it unique across targets
+    pub label: String,
+}
+
+impl WanTarget {
+    pub fn new(config: SnmpConfig, if_index: u32, label: &str) -> Self {
+        Self {
+            config,
+            if_index,
+            label: label.to_string(),
+        }
+    }
+}
+
+/// Link state and negotiated speed of an interface
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WanInfo {
+    /// ifOperStatus is up(1)
+    pub oper_up: bool,
+    /// ifHighSpeed, or ifSpeed on agents without it; None when the agent reports
+    /// no speed (0, common for ppp links)
+    pub speed_mbps: Option<u64>,
+}
+
+/// ifOperStatus (IF-MIB)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperStatus {
+    Up,
+    Down,
+    Testing,
+    Unknown,
+    Dormant,
+    NotPresent,
+    LowerLayerDown,
+}
+
+impl OperStatus {
+    fn from_mib(value: i64) -> Option<Self> {
+        match value {
+            1 => Some(OperStatus::Up),
+            2 => Some(OperStatus::Down),
+            3 => Some(OperStatus::Testing),
+            4 => Some(OperStatus::Unknown),
+            5 => Some(OperStatus::Dormant),
+            6 => Some(OperStatus::NotPresent),
+            7 => Some(OperStatus::LowerLayerDown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OperStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OperStatus::Up => "up",
+            OperStatus::Down => "down",
+            OperStatus::Testing => "testing",
+            OperStatus::Unknown => "unknown",
+            OperStatus::Dormant => "dormant",
+            OperStatus::NotPresent => "notPresent",
+            OperStatus::LowerLayerDown => "lowerLayerDown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A session with one SNMP agent, kept open across polls
+///
+/// The target is resolved and the UDP socket bound once (and for v3, the
+/// agent's engine discovered once); a request to an agent that has gone away
+/// just times out, and the same session works again once it answers. Keep one
+/// per target for as long as it's polled.
+pub struct SnmpClient {
+    target: String,
+    session: SyncSession,
+    use_hc: bool,
+    retries: u32,
+    uptime: Option<Duration>,    // sysUpTime from the last counter request
+    packets: Option<(u64, u64)>, // ifIn/OutUcastPkts from the last counter request
+}
+
+impl SnmpClient {
+    pub fn new(config: &SnmpConfig) -> Result<Self, SnmpError> {
+        config.validate()?;
+        let target = config.address()?;
+        let session = match &config.auth {
+            SnmpAuth::V2c { community } => {
+                SyncSession::new_v2c(target.as_str(), community, Some(config.timeout), 0)
+                    .map_err(SnmpError::Session)?
+            }
+            SnmpAuth::V3 { .. } => open_v3(&target, &config.auth, config.timeout)?,
+        };
+        Ok(Self {
+            target,
+            session,
+            use_hc: true,
+            retries: config.retries,
+            uptime: None,
+            packets: None,
+        })
+    }
+
+    /// Read the 64-bit HC counters when the agent has them (the default), or only
+    /// ever ifInOctets/ifOutOctets - for older agents whose ifXTable misbehaves
+    pub fn with_hc_counters(mut self, enabled: bool) -> Self {
+        self.use_hc = enabled;
+        self
+    }
+
+    /// `host:port` of the agent
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Send a request, and send it again up to `retries` times while the agent
+    /// doesn't answer. `send` copies what it needs out of the response, which
+    /// borrows the session.
+    fn request<T>(
+        &mut self,
+        mut send: impl FnMut(&mut SyncSession) -> Result<T, SnmpError>,
+    ) -> Result<T, SnmpError> {
+        let mut retries = self.retries;
+        loop {
+            match send(&mut self.session) {
+                // A lost datagram, or a late answer to the previous attempt
+                Err(SnmpError::Timeout | SnmpError::Request(snmp2::Error::RequestIdMismatch))
+                    if retries > 0 =>
+                {
+                    retries -= 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Look up an interface's name (ifDescr) by index
+    pub fn interface_name(&mut self, if_index: u32) -> Option<String> {
+        let descr_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 2, if_index as u64]).unwrap();
+        self.request(|sess| {
+            Ok(match sess.get(&descr_oid)?.varbinds.next() {
+                Some((_, Value::OctetString(bytes))) => {
+                    Some(String::from_utf8_lossy(bytes).to_string())
+                }
+                _ => None,
+            })
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Read an interface's ifOperStatus
+    pub fn oper_status(&mut self, if_index: u32) -> Option<OperStatus> {
+        let status_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 8, if_index as u64]).unwrap();
+        self.request(|sess| {
+            Ok(match sess.get(&status_oid)?.varbinds.next() {
+                Some((_, Value::Integer(status))) => OperStatus::from_mib(status),
+                _ => None,
+            })
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Auto-detect interface index by name (e.g., "ppp0")
+    /// Returns Some((index, full_name)) for the best match of
+    /// `detect_interface_indices`: the first one that is up, else the first one
+    pub fn detect_interface_index(&mut self, name_pattern: &str) -> Option<(u32, String)> {
+        let candidates = self.detect_interface_indices(name_pattern);
+        let (index, name, _) = self.rank_candidates(candidates).into_iter().next()?;
+        Some((index, name))
+    }
+
+    /// Every interface whose ifDescr matches `name_pattern`, a case-insensitive
+    /// regex (e.g. `^(ppp|wan)`); a pattern that isn't valid regex is matched as
+    /// plain text
+    pub fn detect_interface_indices(&mut self, name_pattern: &str) -> Vec<(u32, String)> {
+        self.detect_interfaces(&[name_pattern])
+    }
+
+    /// Every interface whose ifDescr matches any of `patterns` and none of the
+    /// exclusions, written with a leading `!` (e.g. `["wan", "!vlan"]`)
+    pub fn detect_interfaces(&mut self, patterns: &[&str]) -> Vec<(u32, String)> {
+        let (excluded, included): (Vec<&str>, Vec<&str>) = patterns
+            .iter()
+            .partition(|pattern| pattern.starts_with('!'));
+        let included: Vec<Regex> = included.into_iter().map(name_regex).collect();
+        let excluded: Vec<Regex> = excluded
+            .into_iter()
+            .map(|pattern| name_regex(&pattern[1..]))
+            .collect();
+
+        self.interface_names()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, name)| {
+                included.iter().any(|pattern| pattern.is_match(name))
+                    && !excluded.iter().any(|pattern| pattern.is_match(name))
+            })
+            .collect()
+    }
+
+    /// Candidates with their ifOperStatus, best first: interfaces that are up
+    /// come ahead of the rest, otherwise the agent's order is kept
+    pub fn rank_candidates(
+        &mut self,
+        candidates: Vec<(u32, String)>,
+    ) -> Vec<(u32, String, Option<OperStatus>)> {
+        let mut ranked: Vec<_> = candidates
+            .into_iter()
+            .map(|(index, name)| (index, name, self.oper_status(index)))
+            .collect();
+        ranked.sort_by_key(|(_, _, status)| *status != Some(OperStatus::Up));
+        ranked
+    }
+
+    /// Every interface the agent lists, with its current counters and status
+    pub fn list_interfaces(&mut self) -> Result<Vec<SnmpInterface>, SnmpError> {
+        let names = self.interface_names()?;
+        let use_hc = self.use_hc;
+        let mut interfaces = Vec::with_capacity(names.len());
+
+        for (index, name) in names {
+            let idx = index as u64;
+            let oids = [
+                Oid::from(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6, idx]).unwrap(), // ifHCInOctets
+                Oid::from(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 10, idx]).unwrap(), // ifHCOutOctets
+                Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, idx]).unwrap(),    // ifInOctets
+                Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 16, idx]).unwrap(),    // ifOutOctets
+                Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 8, idx]).unwrap(),     // ifOperStatus
+            ];
+            let oids: Vec<&Oid> = oids.iter().collect();
+
+            let (rx_bytes, tx_bytes, oper_status) = self.request(|sess| {
+                let pdu = sess.get_many(&oids)?;
+                let values: Vec<Value> = pdu.varbinds.map(|(_oid, value)| value).collect();
+                // HC answers are ignored when the client is kept on 32-bit counters
+                let hc = |i: usize| values.get(i).filter(|_| use_hc);
+
+                let oper_status = match values.get(4) {
+                    Some(Value::Integer(status)) => OperStatus::from_mib(*status),
+                    _ => None,
+                };
+                Ok((
+                    prefer_hc(hc(0), values.get(2)),
+                    prefer_hc(hc(1), values.get(3)),
+                    oper_status,
+                ))
+            })?;
+
+            interfaces.push(SnmpInterface {
+                index,
+                name,
+                rx_bytes,
+                tx_bytes,
+                oper_status,
+            });
+        }
+
+        Ok(interfaces)
+    }
+
+    /// Walk ifDescr with GETNEXT: (ifIndex, name) for every interface, in table order
+    fn interface_names(&mut self) -> Result<Vec<(u32, String)>, SnmpError> {
+        let column = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 2]).unwrap();
+        let mut names = Vec::new();
+        let mut current = column.to_owned();
+
+        loop {
+            let next = self.request(|sess| {
+                let mut pdu = sess.getnext(&current)?;
+                Ok(pdu.varbinds.next().map(|(oid, value)| {
+                    let name = match value {
+                        Value::OctetString(bytes) => {
+                            Some(String::from_utf8_lossy(bytes).to_string())
+                        }
+                        _ => None,
+                    };
+                    (oid.to_owned(), name)
+                }))
+            })?;
+            let Some((oid, name)) = next else {
+                break;
+            };
+            // Walked past the end of the column (or of the whole MIB)
+            if !oid.starts_with(&column) {
+                break;
+            }
+            let Some(name) = name else {
+                break;
+            };
+            let Some(index) = oid.iter().and_then(|arcs| arcs.last()) else {
+                break;
+            };
+            // A broken agent that doesn't move forward would loop forever
+            let index = index as u32;
+            if names.last().is_some_and(|(last, _)| index <= *last) {
+                break;
+            }
+
+            names.push((index, name));
+            current = oid;
+        }
+
+        Ok(names)
+    }
+
+    /// Read an interface's link state and speed in one request
+    ///
+    /// Separate from the counters so pollers can read it far less often; an agent
+    /// without ifHighSpeed/ifSpeed still gets a WanInfo, with no speed.
+    pub fn wan_info(&mut self, if_index: u32) -> Result<WanInfo, SnmpError> {
+        let idx = if_index as u64;
+        let oids = [
+            Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 8, idx]).unwrap(), // ifOperStatus
+            Oid::from(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 15, idx]).unwrap(), // ifHighSpeed
+            Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 5, idx]).unwrap(), // ifSpeed
+        ];
+        let oids: Vec<&Oid> = oids.iter().collect();
+
+        self.request(|sess| {
+            let pdu = sess.get_many(&oids)?;
+            let values: Vec<Value> = pdu.varbinds.map(|(_oid, value)| value).collect();
+
+            let oper_up = match values.first() {
+                Some(Value::Integer(status)) => *status == 1,
+                value => {
+                    return Err(SnmpError::UnexpectedValue(format!(
+                        "ifOperStatus for index {}: {:?}",
+                        if_index, value
+                    )));
+                }
+            };
+            let high_speed = gauge(values.get(1)).filter(|&mbps| mbps > 0);
+            // ifSpeed (bits/s) saturates at 4294967295 on links of 4 Gbps and up
+            let speed = gauge(values.get(2))
+                .filter(|&bps| bps < u32::MAX as u64)
+                .map(|bps| bps / 1_000_000);
+            let speed_mbps = high_speed.or(speed).filter(|&mbps| mbps > 0);
+            Ok(WanInfo {
+                oper_up,
+                speed_mbps,
+            })
+        })
+    }
+
+    /// Whether the agent answers a GET for sysDescr.0
+    pub fn is_available(&mut self) -> bool {
+        let sys_descr_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 1, 1, 0]).unwrap();
+
+        // Any value means SNMP responds
+        self.request(|sess| Ok(sess.get(&sys_descr_oid)?.varbinds.next().is_some()))
+            .unwrap_or(false)
+    }
+
+    /// Fetch WAN interface counters, from the 64-bit HC counters when the agent
+    /// has them and ifInOctets/ifOutOctets otherwise
+    ///
+    /// This doesn't remember anything between calls, so an agent without HC
+    /// counters costs an extra request every time; when polling, go through a
+    /// `WanCounterSource`, which remembers the fallback (and also catches agents
+    /// whose HC counters don't move).
+    pub fn fetch_wan_stats(
+        &mut self,
+        if_index: u32,
+        iface_name: &str,
+    ) -> Result<InterfaceStats, SnmpError> {
+        let hc = if self.use_hc {
+            self.hc_counters(if_index)?
+        } else {
+            None
+        };
+        let counters = match hc {
+            Some((rx, tx)) => (rx, tx, CounterWidth::Bits64),
+            None => self.counters32(if_index)?,
+        };
+        Ok(self.wan_stats(iface_name, counters))
+    }
+
+    /// GET counters for `if_index` in one request, handing their answers (in the
+    /// order asked for) to `decode`
+    ///
+    /// sysUpTime.0 rides along, so the stats can show when the agent restarted, and
+    /// so do the unicast packet counters. Those are Counter32 on every agent; under
+    /// 64-bit byte counters a packet counter wrap reads as one zero-packet sample.
+    fn get_counters<T>(
+        &mut self,
+        if_index: u32,
+        counter_oids: &[&Oid],
+        decode: impl Fn(&[Option<Value>]) -> Result<T, SnmpError>,
+    ) -> Result<T, SnmpError> {
+        let idx = if_index as u64;
+        let uptime_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 1, 3, 0]).unwrap();
+        let rx_packets_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 11, idx]).unwrap();
+        let tx_packets_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 17, idx]).unwrap();
+        let mut oids = vec![&uptime_oid];
+        oids.extend_from_slice(counter_oids);
+        oids.extend([&rx_packets_oid, &tx_packets_oid]);
+
+        let (counters, uptime, packets) = self.request(|sess| {
+            let mut pdu = sess.get_many(&oids)?;
+            let uptime = match pdu.varbinds.next() {
+                // Hundredths of a second
+                Some((_, Value::Timeticks(ticks))) => {
+                    Some(Duration::from_millis(ticks as u64 * 10))
+                }
+                _ => None,
+            };
+            let values: Vec<_> = counter_oids
+                .iter()
+                .map(|_| pdu.varbinds.next().map(|(_oid, value)| value))
+                .collect();
+            let counters = decode(&values)?;
+            // Optional extras: an agent without them still has its bytes read
+            let mut packet = || {
+                let value = pdu.varbinds.next().map(|(_oid, value)| value);
+                decode_counter(value.as_ref())
+                    .ok()
+                    .flatten()
+                    .map(|(count, _)| count)
+            };
+            let rx_packets = packet();
+            let tx_packets = packet();
+            let packets = rx_packets.zip(tx_packets);
+            Ok((counters, uptime, packets))
+        })?;
+        self.uptime = uptime;
+        self.packets = packets;
+        Ok(counters)
+    }
+
+    /// Unified InterfaceStats for `(rx, tx, width)` counters just read from this agent
+    fn wan_stats(&self, iface_name: &str, counters: Counters) -> InterfaceStats {
+        let (rx_bytes, tx_bytes, counter_width) = counters;
+        let stats = InterfaceStats {
+            interface: iface_name.to_string(),
+            rx_bytes,
+            tx_bytes,
+            rx_packets: self.packets.map(|(rx, _)| rx),
+            tx_packets: self.packets.map(|(_, tx)| tx),
+            // ifIn/OutErrors and ifIn/OutDiscards aren't read
+            rx_errors: None,
+            tx_errors: None,
+            rx_dropped: None,
+            tx_dropped: None,
+            // Link state and speed come from wan_info, read less often
+            is_up: None,
+            link_speed_bps: None,
+            kind: InterfaceType::Wan,
+            target: Some(self.target.clone()),
+            counter_width,
+            uptime: self.uptime,
+        };
+        stats.debug_assert_populated(InterfaceType::Wan);
+        stats
+    }
+
+    /// GET ifHCInOctets/ifHCOutOctets, None if the agent doesn't expose them
+    fn hc_counters(&mut self, if_index: u32) -> Result<Option<(u64, u64)>, SnmpError> {
+        let [rx_oid, tx_oid] = hc_oids(if_index);
+        self.get_counters(if_index, &[&rx_oid, &tx_oid], |values| {
+            Ok(decode_hc(values[0].as_ref(), values[1].as_ref()))
+        })
+    }
+
+    /// GET ifInOctets/ifOutOctets (Counter32 on most agents)
+    fn counters32(&mut self, if_index: u32) -> Result<Counters, SnmpError> {
+        let [rx_oid, tx_oid] = oids32(if_index);
+        self.get_counters(if_index, &[&rx_oid, &tx_oid], |values| {
+            decode32(values[0].as_ref(), values[1].as_ref(), if_index)
+        })
+    }
+
+    /// `hc_counters` and `counters32` in one request
+    fn both_counters(
+        &mut self,
+        if_index: u32,
+    ) -> Result<(Option<(u64, u64)>, Counters), SnmpError> {
+        let [hc_rx_oid, hc_tx_oid] = hc_oids(if_index);
+        let [rx_oid, tx_oid] = oids32(if_index);
+        let oids = [&hc_rx_oid, &hc_tx_oid, &rx_oid, &tx_oid];
+        self.get_counters(if_index, &oids, |values| {
+            let hc = decode_hc(values[0].as_ref(), values[1].as_ref());
+            Ok((
+                hc,
+                decode32(values[2].as_ref(), values[3].as_ref(), if_index)?,
+            ))
+        })
+    }
+}
+
+/// ifHCInOctets/ifHCOutOctets of an interface
+fn hc_oids(if_index: u32) -> [Oid<'static>; 2] {
+    let idx = if_index as u64;
+    [
+        Oid::from(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6, idx]).unwrap(),
+        Oid::from(&[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 10, idx]).unwrap(),
+    ]
+}
+
+/// ifInOctets/ifOutOctets of an interface
+fn oids32(if_index: u32) -> [Oid<'static>; 2] {
+    let idx = if_index as u64;
+    [
+        Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, idx]).unwrap(),
+        Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 16, idx]).unwrap(),
+    ]
+}
+
+/// (rx, tx) from the ifHCInOctets/ifHCOutOctets answers; None unless both are
+/// 64-bit counters (missing, or answered with something else)
+fn decode_hc(rx: Option<&Value>, tx: Option<&Value>) -> Option<(u64, u64)> {
+    match (decode_counter(rx), decode_counter(tx)) {
+        (Ok(Some((rx, CounterWidth::Bits64))), Ok(Some((tx, CounterWidth::Bits64)))) => {
+            Some((rx, tx))
+        }
+        _ => None,
+    }
+}
+
+/// (rx, tx, width) from the ifInOctets/ifOutOctets answers
+fn decode32(rx: Option<&Value>, tx: Option<&Value>, if_index: u32) -> Result<Counters, SnmpError> {
+    match (decode_counter(rx)?, decode_counter(tx)?) {
+        (Some((rx_bytes, counter_width)), Some((tx_bytes, _))) => {
+            Ok((rx_bytes, tx_bytes, counter_width))
+        }
+        _ => Err(SnmpError::UnexpectedValue(format!(
+            "no ifInOctets/ifOutOctets for index {}",
+            if_index
+        ))),
+    }
+}
+
+/// Open a v3 session and run engine discovery, which also syncs engine boots/time
+#[cfg(feature = "snmpv3")]
+fn open_v3(target: &str, auth: &SnmpAuth, timeout: Duration) -> Result<SyncSession, SnmpError> {
+    use snmp2::v3;
+
+    let SnmpAuth::V3 {
+        user,
+        auth_protocol,
+        auth_password,
+        priv_protocol,
+        priv_password,
+    } = auth
+    else {
+        unreachable!("open_v3 called with v2c credentials");
+    };
+
+    let auth_protocol = match auth_protocol {
+        SnmpAuthProtocol::Md5 => v3::AuthProtocol::Md5,
+        SnmpAuthProtocol::Sha1 => v3::AuthProtocol::Sha1,
+        SnmpAuthProtocol::Sha224 => v3::AuthProtocol::Sha224,
+        SnmpAuthProtocol::Sha256 => v3::AuthProtocol::Sha256,
+        SnmpAuthProtocol::Sha384 => v3::AuthProtocol::Sha384,
+        SnmpAuthProtocol::Sha512 => v3::AuthProtocol::Sha512,
+    };
+    let level = match priv_protocol {
+        None => v3::Auth::AuthNoPriv,
+        Some(priv_protocol) => v3::Auth::AuthPriv {
+            cipher: match priv_protocol {
+                SnmpPrivProtocol::Des => v3::Cipher::Des,
+                SnmpPrivProtocol::Aes128 => v3::Cipher::Aes128,
+                SnmpPrivProtocol::Aes192 => v3::Cipher::Aes192,
+                SnmpPrivProtocol::Aes256 => v3::Cipher::Aes256,
+            },
+            privacy_password: priv_password.as_bytes().to_vec(),
+        },
+    };
+    let security = v3::Security::new(user.as_bytes(), auth_password.as_bytes())
+        .with_auth_protocol(auth_protocol)
+        .with_auth(level);
+
+    let mut session =
+        SyncSession::new_v3(target, Some(timeout), 0, security).map_err(SnmpError::Session)?;
+    session.init().map_err(|e| match SnmpError::from(e) {
+        SnmpError::Request(e) => SnmpError::Engine(e),
+        e => e, // An agent that doesn't answer at all is still a timeout
+    })?;
+    Ok(session)
+}
+
+#[cfg(not(feature = "snmpv3"))]
+fn open_v3(_target: &str, _auth: &SnmpAuth, _timeout: Duration) -> Result<SyncSession, SnmpError> {
+    Err(SnmpError::Session(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SNMPv3 needs netgauge built with the `snmpv3` feature",
+    )))
+}
+
+/// Fetch SNMP WAN interface counters over a one-off session
+///
+/// See `SnmpClient::fetch_wan_stats`; keep an `SnmpClient` instead when polling.
 pub fn fetch_wan_stats(
-    target: &str,
-    community: &[u8],
+    config: &SnmpConfig,
     if_index: u32,
     iface_name: &str,
-) -> InterfaceStats {
-    // Convert if_index to u64 for OID
-    let idx = if_index as u64;
+) -> Result<InterfaceStats, SnmpError> {
+    SnmpClient::new(config)?.fetch_wan_stats(if_index, iface_name)
+}
 
-    // Build OIDs for ifInOctets and ifOutOctets
-    let rx_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, idx]).unwrap();
-    let tx_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 16, idx]).unwrap();
+/// Fetch counters for every target, one result per target in the same order
+///
+/// Targets on the same agent share a session. A target that fails, or an agent
+/// that doesn't answer, only fails its own entries.
+pub fn fetch_wan_stats_many(targets: &[WanTarget]) -> Vec<Result<InterfaceStats, SnmpError>> {
+    let mut clients: Vec<(&SnmpConfig, SnmpClient)> = Vec::new();
+    targets
+        .iter()
+        .map(|target| {
+            let position = match clients.iter().position(|(c, _)| **c == target.config) {
+                Some(position) => position,
+                None => {
+                    clients.push((&target.config, SnmpClient::new(&target.config)?));
+                    clients.len() - 1
+                }
+            };
+            clients[position]
+                .1
+                .fetch_wan_stats(target.if_index, &target.label)
+        })
+        .collect()
+}
 
-    // Create SNMP v2c session
-    let timeout = std::time::Duration::from_secs(2);
-    let mut sess = SyncSession::new_v2c(target, community, Some(timeout), 0)
-        .expect("Failed to create SNMP session");
+/// Case-insensitive regex for an interface name pattern, falling back to a
+/// literal match so substrings like `"ppp"` keep working whatever they contain
+fn name_regex(pattern: &str) -> Regex {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .or_else(|_| {
+            RegexBuilder::new(&regex::escape(pattern))
+                .case_insensitive(true)
+                .build()
+        })
+        .expect("escaped pattern is valid regex")
+}
 
-    // Fetch RX bytes
-    let rx_bytes = match sess.get(&rx_oid).unwrap().varbinds.next() {
-        Some((_oid, Value::Counter32(v))) => v as u64,
-        Some((_oid, Value::Counter64(v))) => v,
-        _ => 0,
-    };
+/// A Gauge32 value (speeds), None when the agent doesn't have the object
+fn gauge(value: Option<&Value>) -> Option<u64> {
+    match value {
+        Some(Value::Unsigned32(v) | Value::Counter32(v)) => Some(*v as u64),
+        _ => None,
+    }
+}
 
-    // Fetch TX bytes
-    let tx_bytes = match sess.get(&tx_oid).unwrap().varbinds.next() {
-        Some((_oid, Value::Counter32(v))) => v as u64,
-        Some((_oid, Value::Counter64(v))) => v,
+/// A byte counter from an HC/32-bit pair of answers, preferring the 64-bit one;
+/// 0 if neither is available
+fn prefer_hc(hc: Option<&Value>, fallback: Option<&Value>) -> u64 {
+    match (hc, fallback) {
+        (Some(Value::Counter64(v)), _) => *v,
+        (_, Some(Value::Counter32(v))) => *v as u64,
+        (_, Some(Value::Counter64(v))) => *v,
         _ => 0,
-    };
+    }
+}
+
+/// Which counters a WAN interface is read from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HcSupport {
+    /// HC counters not yet seen moving - 32-bit values are reported meanwhile
+    Unverified,
+    /// ifHCInOctets/ifHCOutOctets track traffic, use them
+    Trusted,
+    /// The agent lies about (or lacks) HC counters, use ifInOctets/ifOutOctets
+    Fallback32,
+}
+
+/// Per-interface HC verification state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HcState {
+    /// Last (hc, 32-bit) sample, compared against the next one
+    Unverified(Option<((u64, u64), (u64, u64))>),
+    /// HC counters are reported relative to the 32-bit value at the switch, so
+    /// changing counter family doesn't look like a traffic spike to the tracker
+    Trusted {
+        reported: (u64, u64),
+        hc: (u64, u64),
+    },
+    Fallback32,
+}
+
+impl HcState {
+    /// Next state of an unverified interface after a new (hc, 32-bit) sample
+    fn verify(
+        previous: Option<((u64, u64), (u64, u64))>,
+        hc: (u64, u64),
+        c32: (u64, u64),
+    ) -> HcState {
+        match previous {
+            Some((prev_hc, _)) if hc != prev_hc => HcState::Trusted { reported: c32, hc },
+            Some((_, prev_c32)) if c32 != prev_c32 => HcState::Fallback32,
+            _ => HcState::Unverified(Some((hc, c32))),
+        }
+    }
+
+    /// What a trusted interface reports for a new HC sample, given what it last
+    /// reported and the HC sample behind that
+    fn next_reported(reported: (u64, u64), base: (u64, u64), hc: (u64, u64)) -> (u64, u64) {
+        // Agent restarted and the HC counters reset: continue from the last value
+        let base = if hc.0 < base.0 || hc.1 < base.1 {
+            hc
+        } else {
+            base
+        };
+        (reported.0 + (hc.0 - base.0), reported.1 + (hc.1 - base.1))
+    }
+}
+
+/// WAN counter reader that prefers 64-bit HC counters but falls back to the
+/// 32-bit ones for agents that advertise ifHCInOctets without implementing it
+///
+/// Some routers answer the HC OIDs with 0 (or a stuck small value) while traffic
+/// flows. Until an interface's HC counters are seen moving alongside the 32-bit
+/// ones, the 32-bit values are reported; if the 32-bit counters move and the HC
+/// counters don't, the interface stays on 32-bit for the rest of the session.
+/// Keep one instance across polls so the decision is remembered; it can be
+/// shared by several clients since decisions are kept per target.
+#[derive(Default)]
+pub struct WanCounterSource {
+    states: HashMap<(String, u32), HcState>,
+}
+
+impl WanCounterSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current decision for an interface
+    pub fn hc_support(&self, target: &str, if_index: u32) -> HcSupport {
+        match self.states.get(&(target.to_string(), if_index)) {
+            None | Some(HcState::Unverified(_)) => HcSupport::Unverified,
+            Some(HcState::Trusted { .. }) => HcSupport::Trusted,
+            Some(HcState::Fallback32) => HcSupport::Fallback32,
+        }
+    }
 
-    // Return unified InterfaceStats
-    InterfaceStats {
-        interface: iface_name.to_string(),
-        rx_bytes,
-        tx_bytes,
-        kind: InterfaceType::Wan,
+    /// Whether an interface has been switched to the 32-bit counters
+    pub fn is_fallback(&self, target: &str, if_index: u32) -> bool {
+        self.hc_support(target, if_index) == HcSupport::Fallback32
+    }
+
+    /// Fetch WAN counters, applying (and updating) the HC decision for `if_index`
+    ///
+    /// A failed request leaves the decision as it was, so the next poll just retries.
+    /// A client built `with_hc_counters(false)` always gets the 32-bit counters.
+    /// Nothing is printed: compare `is_fallback` before and after to report a
+    /// switch to the 32-bit counters.
+    pub fn fetch(
+        &mut self,
+        client: &mut SnmpClient,
+        if_index: u32,
+        iface_name: &str,
+    ) -> Result<InterfaceStats, SnmpError> {
+        if !client.use_hc {
+            let counters = client.counters32(if_index)?;
+            return Ok(client.wan_stats(iface_name, counters));
+        }
+
+        let key = (client.target().to_string(), if_index);
+        let state = self
+            .states
+            .get(&key)
+            .copied()
+            .unwrap_or(HcState::Unverified(None));
+
+        let counters = match state {
+            HcState::Fallback32 => client.counters32(if_index)?,
+            HcState::Trusted { reported, hc: base } => match client.hc_counters(if_index)? {
+                Some(hc) => {
+                    let next = HcState::next_reported(reported, base, hc);
+                    self.states
+                        .insert(key, HcState::Trusted { reported: next, hc });
+                    (next.0, next.1, CounterWidth::Bits64)
+                }
+                None => {
+                    self.fall_back(key);
+                    client.counters32(if_index)?
+                }
+            },
+            // Both counter families in one request, so verifying costs no extra round trip
+            HcState::Unverified(previous) => {
+                let (hc, counters) = client.both_counters(if_index)?;
+                match hc {
+                    Some(hc) => {
+                        let c32 = (counters.0, counters.1);
+                        self.states.insert(key, HcState::verify(previous, hc, c32));
+                    }
+                    None => self.fall_back(key),
+                }
+                counters
+            }
+        };
+
+        Ok(client.wan_stats(iface_name, counters))
+    }
+
+    /// The agent has no HC counters (noSuchObject/noSuchInstance): use 32-bit from now on
+    fn fall_back(&mut self, key: (String, u32)) {
+        self.states.insert(key, HcState::Fallback32);
     }
 }
 
+/// Read an interface's link state and speed over a one-off session
+pub fn fetch_wan_info(config: &SnmpConfig, if_index: u32) -> Result<WanInfo, SnmpError> {
+    SnmpClient::new(config)?.wan_info(if_index)
+}
+
 /// Check if SNMP is available on a router
-/// Returns true if a simple SNMP get succeeds
-pub fn is_snmp_available(target: &str, community: &[u8]) -> bool {
-    let timeout = std::time::Duration::from_secs(2);
-
-    // Try creating a session
-    let mut session = match SyncSession::new_v2c(target, community, Some(timeout), 0) {
-        Ok(s) => s,
-        Err(_) => return false,
-    };
+/// Returns true if a simple SNMP get succeeds; use `SnmpClient::new` directly
+/// to learn why an agent is unavailable (e.g. a v3 engine discovery failure)
+pub fn is_snmp_available(config: &SnmpConfig) -> bool {
+    SnmpClient::new(config).is_ok_and(|mut client| client.is_available())
+}
+
+/// `SnmpClient::fetch_wan_stats` without blocking the caller: the request runs on
+/// its own thread, so an agent that doesn't answer never stalls an async executor
+///
+/// The caller keeps the client across polls, so every call reuses its session;
+/// concurrent calls on one client take turns.
+pub async fn fetch_wan_stats_async(
+    client: &Arc<Mutex<SnmpClient>>,
+    if_index: u32,
+    iface_name: &str,
+) -> Result<InterfaceStats, SnmpError> {
+    let (client, iface_name) = (client.clone(), iface_name.to_string());
+    Blocking::spawn(move || lock(&client).fetch_wan_stats(if_index, &iface_name)).await
+}
 
-    // Try fetching a common OID (sysDescr.0)
-    let sys_descr_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 1, 1, 0]).unwrap();
+/// `SnmpClient::is_available` without blocking the caller; see `fetch_wan_stats_async`
+pub async fn is_snmp_available_async(client: &Arc<Mutex<SnmpClient>>) -> bool {
+    let client = client.clone();
+    Blocking::spawn(move || lock(&client).is_available()).await
+}
 
-    match session.get(&sys_descr_oid) {
-        Ok(mut vb) => match vb.varbinds.next() {
-            Some((_oid, Value::OctetString(_))) => true, // SNMP responds correctly
-            Some((_oid, _)) => true,                     // SNMP responds with some value
-            None => false,                               // No varbind returned
-        },
-        Err(_) => false, // SNMP get failed
+/// A shared client, also after a request on it panicked: the session itself is
+/// still usable, the next request just starts over
+fn lock(client: &Mutex<SnmpClient>) -> std::sync::MutexGuard<'_, SnmpClient> {
+    client
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Result slot shared between a `Blocking` future and its thread
+struct BlockingState<T> {
+    result: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Future for a blocking call run on a thread of its own
+///
+/// Runtime-agnostic, so the library doesn't pick an executor for the app. A
+/// call that panics re-raises the panic in the task awaiting it.
+struct Blocking<T> {
+    state: Arc<Mutex<BlockingState<thread::Result<T>>>>,
+}
+
+impl<T: Send + 'static> Blocking<T> {
+    fn spawn(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let state = Arc::new(Mutex::new(BlockingState {
+            result: None,
+            waker: None,
+        }));
+        let shared = state.clone();
+        thread::spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+            let mut state = shared.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        Self { state }
     }
 }
 
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => std::panic::resume_unwind(payload),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Look up an interface's name (ifDescr) by index
+pub fn interface_name(config: &SnmpConfig, if_index: u32) -> Option<String> {
+    SnmpClient::new(config).ok()?.interface_name(if_index)
+}
+
+/// List a router's interfaces (ifTable) with their counters and status
+pub fn list_snmp_interfaces(config: &SnmpConfig) -> Result<Vec<SnmpInterface>, SnmpError> {
+    SnmpClient::new(config)?.list_interfaces()
+}
+
 /// Auto-detect interface index by name (e.g., "ppp0")
-/// Returns Some((index, full_name)) if found, None otherwise
-pub fn detect_interface_index(target: &str, community: &[u8], name_pattern: &str) -> Option<(u32, String)> {
-    let timeout = std::time::Duration::from_secs(2);
+/// Returns Some((index, full_name)) for the best match (preferring one that is
+/// up), None otherwise
+pub fn detect_interface_index(config: &SnmpConfig, name_pattern: &str) -> Option<(u32, String)> {
+    SnmpClient::new(config)
+        .ok()?
+        .detect_interface_index(name_pattern)
+}
 
-    let mut sess = match SyncSession::new_v2c(target, community, Some(timeout), 0) {
-        Ok(s) => s,
-        Err(_) => return None,
-    };
+/// Every interface whose name matches `name_pattern` (a case-insensitive regex,
+/// e.g. `^(ppp|wan)`), for routers with more than one uplink
+pub fn detect_interface_indices(config: &SnmpConfig, name_pattern: &str) -> Vec<(u32, String)> {
+    match SnmpClient::new(config) {
+        Ok(mut client) => client.detect_interface_indices(name_pattern),
+        Err(_) => Vec::new(),
+    }
+}
 
-    // Search through interface indexes 1-100
-    for idx in 1u64..=100 {
-        let descr_oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 2, idx]).unwrap();
+/// Every interface matching any of `patterns` and none of the `!`-prefixed
+/// exclusions (case-insensitive), e.g. `&["wan", "!vlan"]`
+pub fn detect_interfaces(config: &SnmpConfig, patterns: &[&str]) -> Vec<(u32, String)> {
+    match SnmpClient::new(config) {
+        Ok(mut client) => client.detect_interfaces(patterns),
+        Err(_) => Vec::new(),
+    }
+}
 
-        let name = match sess.get(&descr_oid) {
-            Ok(resp) => match resp.varbinds.into_iter().next() {
-                Some((_, Value::OctetString(bytes))) => {
-                    String::from_utf8_lossy(&bytes).to_string()
-                }
-                _ => continue,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TEST-NET-1, reserved for documentation: nothing answers there
+    fn unreachable() -> SnmpConfig {
+        SnmpConfig::v2c("192.0.2.1", b"public")
+            .with_timeout(Duration::from_millis(200))
+            .with_retries(0)
+    }
+
+    /// No answer, or - without a route to the address - a failed send
+    fn is_unanswered(result: &Result<InterfaceStats, SnmpError>) -> bool {
+        matches!(result, Err(SnmpError::Timeout | SnmpError::Request(_)))
+    }
+
+    #[test]
+    fn debug_output_masks_the_secrets() {
+        let v3 = SnmpConfig::new(
+            "192.0.2.1",
+            SnmpAuth::V3 {
+                user: "monitor".to_string(),
+                auth_protocol: SnmpAuthProtocol::Sha256,
+                auth_password: "auth-secret".to_string(),
+                priv_protocol: Some(SnmpPrivProtocol::Aes128),
+                priv_password: "priv-secret".to_string(),
             },
-            Err(_) => continue,
-        };
+        );
+        let debug = format!("{:?}", v3);
+        assert!(!debug.contains("auth-secret"), "{}", debug);
+        assert!(!debug.contains("priv-secret"), "{}", debug);
+        assert!(debug.contains("monitor"), "{}", debug);
+        assert!(debug.contains("Sha256"), "{}", debug);
+
+        let debug = format!("{:?}", SnmpConfig::v2c("192.0.2.1", b"c0mmunity"));
+        assert!(!debug.contains("c0mmunity"), "{}", debug);
+        assert!(!debug.contains("99, 48"), "{}", debug);
+    }
+
+    #[test]
+    fn unreachable_agent_is_an_error_not_a_panic() {
+        let result = fetch_wan_stats(&unreachable(), 1, "wan");
+        assert!(is_unanswered(&result), "{:?}", result);
+        assert!(fetch_wan_info(&unreachable(), 1).is_err());
+        assert!(!is_snmp_available(&unreachable()));
+    }
 
-        // Check if interface name contains the pattern (case-insensitive)
-        if name.to_lowercase().contains(&name_pattern.to_lowercase()) {
-            return Some((idx as u32, name));
+    /// Run a future to completion on the test thread
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(thread::Thread);
+        impl std::task::Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn async_requests_go_through_the_callers_client() {
+        let client = Arc::new(Mutex::new(SnmpClient::new(&unreachable()).unwrap()));
+
+        // While the caller holds the client, the request can't go out
+        let held = client.lock().unwrap();
+        let mut fetch = std::pin::pin!(fetch_wan_stats_async(&client, 1, "wan"));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(fetch.as_mut().poll(&mut cx).is_pending());
+        thread::sleep(Duration::from_millis(50));
+        assert!(fetch.as_mut().poll(&mut cx).is_pending());
+        drop(held);
+
+        let result = block_on(fetch);
+        assert!(is_unanswered(&result), "{:?}", result);
+        // Same client again, and no copies of it left behind
+        assert!(!block_on(is_snmp_available_async(&client)));
+        assert_eq!(Arc::strong_count(&client), 1);
+    }
+
+    #[test]
+    fn unreachable_agent_keeps_the_counter_decision_open() {
+        let mut client = SnmpClient::new(&unreachable()).unwrap();
+        let mut source = WanCounterSource::new();
+        for _ in 0..2 {
+            let result = source.fetch(&mut client, 1, "wan");
+            assert!(is_unanswered(&result), "{:?}", result);
+        }
+        assert_eq!(source.hc_support(client.target(), 1), HcSupport::Unverified);
+        assert!(!source.is_fallback(client.target(), 1));
+    }
+
+    #[test]
+    fn failing_targets_fail_only_their_own_entries() {
+        let targets = [
+            WanTarget::new(unreachable(), 1, "wan1"),
+            WanTarget::new(SnmpConfig::v2c("192.0.2.1", b""), 2, "wan2"),
+        ];
+        let results = fetch_wan_stats_many(&targets);
+        assert_eq!(results.len(), 2);
+        assert!(is_unanswered(&results[0]), "{:?}", results[0]);
+        assert!(
+            matches!(results[1], Err(SnmpError::Config(_))),
+            "{:?}",
+            results[1]
+        );
+    }
+
+    #[test]
+    fn settings_that_cant_work_fail_before_any_request() {
+        let configs = [
+            SnmpConfig::v2c("", b"public"),
+            SnmpConfig::v2c("  ", b"public"),
+            SnmpConfig::v2c("192.0.2.1", b""),
+            SnmpConfig::v2c("router:161:1", b"public"),
+            unreachable().with_timeout(Duration::ZERO),
+        ];
+        for config in configs {
+            let result = fetch_wan_stats(&config, 1, "wan");
+            assert!(matches!(result, Err(SnmpError::Config(_))), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn unresolvable_host_is_a_session_error() {
+        // .invalid never resolves (RFC 6761)
+        let config = SnmpConfig::v2c("router.invalid", b"public");
+        let result = fetch_wan_stats(&config, 1, "wan");
+        assert!(matches!(result, Err(SnmpError::Session(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn hc_pair_decodes_only_as_two_64_bit_counters() {
+        let (rx, tx) = (Value::Counter64(1 << 40), Value::Counter64(7));
+        assert_eq!(decode_hc(Some(&rx), Some(&tx)), Some((1 << 40, 7)));
+
+        let c32 = Value::Counter32(5);
+        let cases = [
+            (Some(&Value::NoSuchObject), Some(&Value::NoSuchObject)),
+            (Some(&Value::NoSuchInstance), Some(&tx)),
+            (Some(&c32), Some(&c32)),
+            (Some(&rx), Some(&c32)),
+            (Some(&rx), None),
+        ];
+        for (rx, tx) in cases {
+            assert_eq!(decode_hc(rx, tx), None, "{:?} {:?}", rx, tx);
+        }
+    }
+
+    #[test]
+    fn pair_32_decodes_with_its_width() {
+        let (rx, tx) = (Value::Counter32(u32::MAX), Value::Counter32(3));
+        let decoded = decode32(Some(&rx), Some(&tx), 2).unwrap();
+        assert_eq!(decoded, (u32::MAX as u64, 3, CounterWidth::Bits32));
+
+        // Some agents answer ifInOctets with a Counter64
+        let (rx, tx) = (Value::Counter64(1 << 40), Value::Counter64(9));
+        let decoded = decode32(Some(&rx), Some(&tx), 2).unwrap();
+        assert_eq!(decoded, (1 << 40, 9, CounterWidth::Bits64));
+    }
+
+    #[test]
+    fn missing_or_odd_32_bit_counters_are_errors() {
+        let tx = Value::Counter32(3);
+        let cases = [
+            (Some(&Value::NoSuchInstance), Some(&Value::NoSuchInstance)),
+            (Some(&Value::NoSuchObject), Some(&tx)),
+            (Some(&Value::OctetString(b"eth0")), Some(&tx)),
+            (None, None),
+        ];
+        for (rx, tx) in cases {
+            let result = decode32(rx, tx, 2);
+            assert!(
+                matches!(result, Err(SnmpError::UnexpectedValue(_))),
+                "{:?}",
+                result
+            );
         }
     }
 
-    None
+    #[test]
+    fn moving_hc_counters_become_trusted_from_the_32_bit_value() {
+        let first = HcState::verify(None, (100, 200), (100, 200));
+        assert_eq!(first, HcState::Unverified(Some(((100, 200), (100, 200)))));
+
+        let HcState::Unverified(previous) = first else {
+            unreachable!()
+        };
+        let second = HcState::verify(previous, (150, 260), (150, 260));
+        assert_eq!(
+            second,
+            HcState::Trusted {
+                reported: (150, 260),
+                hc: (150, 260)
+            }
+        );
+    }
+
+    #[test]
+    fn stuck_hc_counters_fall_back_to_32_bit() {
+        let previous = Some(((0, 0), (100, 200)));
+        assert_eq!(
+            HcState::verify(previous, (0, 0), (150, 260)),
+            HcState::Fallback32
+        );
+    }
+
+    #[test]
+    fn idle_interface_stays_unverified() {
+        let previous = Some(((0, 0), (100, 200)));
+        assert_eq!(
+            HcState::verify(previous, (0, 0), (100, 200)),
+            HcState::Unverified(previous)
+        );
+    }
+
+    #[test]
+    fn trusted_counters_advance_by_the_hc_delta() {
+        // Switched over with the 32-bit counters at 1000/2000 and HC at 5000/9000
+        let next = HcState::next_reported((1000, 2000), (5000, 9000), (5500, 9100));
+        assert_eq!(next, (1500, 2100));
+    }
+
+    #[test]
+    fn agent_restart_continues_from_the_last_reported_value() {
+        let next = HcState::next_reported((1500, 2100), (5500, 9100), (40, 10));
+        assert_eq!(next, (1500, 2100));
+
+        // And moves on from the new base
+        let next = HcState::next_reported(next, (40, 10), (90, 30));
+        assert_eq!(next, (1550, 2120));
+    }
 }
@@ -0,0 +1,102 @@
+//! `DeltaTracker::update_into` on interfaces it already knows makes no heap
+//! allocations. Its own test binary, since it swaps in a counting global allocator.
+
+use netgauge::{CounterWidth, DeltaTracker, InterfaceStats, InterfaceType};
+use std::time::Duration;
+
+/// Counts the heap allocations of the thread that turns it on
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        static COUNTING: Cell<bool> = const { Cell::new(false) };
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct Counting;
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // try_with: this also runs while thread-locals are torn down
+            let _ = COUNTING.try_with(|counting| {
+                if counting.get() {
+                    ALLOCATIONS.with(|n| n.set(n.get() + 1));
+                }
+            });
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: Counting = Counting;
+
+    /// Heap allocations (and reallocations) `f` makes on this thread
+    pub fn allocations(f: impl FnOnce()) -> usize {
+        ALLOCATIONS.with(|n| n.set(0));
+        COUNTING.with(|counting| counting.set(true));
+        f();
+        COUNTING.with(|counting| counting.set(false));
+        ALLOCATIONS.with(|n| n.get())
+    }
+}
+
+fn stats(interface: &str, rx_bytes: u64, tx_bytes: u64) -> InterfaceStats {
+    InterfaceStats {
+        interface: interface.to_string(),
+        rx_bytes,
+        tx_bytes,
+        rx_packets: None,
+        tx_packets: None,
+        rx_errors: None,
+        tx_errors: None,
+        rx_dropped: None,
+        tx_dropped: None,
+        is_up: Some(true),
+        link_speed_bps: None,
+        kind: InterfaceType::Net,
+        target: None,
+        counter_width: CounterWidth::Bits64,
+        uptime: None,
+    }
+}
+
+#[test]
+fn polling_known_interfaces_into_a_buffer_does_not_allocate() {
+    assert_eq!(
+        counting::allocations(|| drop(std::hint::black_box(String::from("eth0")))),
+        1
+    );
+
+    let wan = |rx_bytes, tx_bytes| InterfaceStats {
+        kind: InterfaceType::Wan,
+        target: Some("192.0.2.1".to_string()),
+        counter_width: CounterWidth::Bits32,
+        ..stats("ppp0", rx_bytes, tx_bytes)
+    };
+    let ticks: Vec<Vec<InterfaceStats>> = (0..20u64)
+        .map(|n| vec![stats("eth0", n * 1000, n * 100), wan(n * 500, n * 50)])
+        .collect();
+    let mut tracker = DeltaTracker::new()
+        .with_smoothing(0.3)
+        .with_history(4)
+        .with_max_age(Duration::from_secs(60));
+    let mut deltas = Vec::new();
+
+    // Interning, map entries, history buffers and `deltas` are set up here
+    for tick in &ticks[..10] {
+        tracker.update_into(tick, &mut deltas);
+    }
+    let allocations = counting::allocations(|| {
+        for tick in &ticks[10..] {
+            tracker.update_into(tick, &mut deltas);
+        }
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(deltas.len(), 2);
+    assert_eq!(deltas[1].target.as_deref(), Some("192.0.2.1"));
+}